@@ -0,0 +1,57 @@
+//! Ingestion benchmark
+//!
+//! Generates a synthetic dumpstate fixture (cached between runs) and times
+//! `StreamingIndexBuilder` on it.
+//!
+//! Usage: `cargo run --release --example index_bench -- [size_mb] [fixture_path]`
+
+use lazy_milktea_lib::{write_logcat_fixture, StreamingIndexBuilder};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::Instant;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let size_mb: u64 = args.next().and_then(|s| s.parse().ok()).unwrap_or(256);
+    let fixture: PathBuf = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("lazy_milktea_fixture_{}mb.txt", size_mb)));
+    let target_bytes = size_mb * 1024 * 1024;
+
+    let existing = std::fs::metadata(&fixture).map(|m| m.len()).unwrap_or(0);
+    if existing < target_bytes {
+        let started = Instant::now();
+        let file = std::fs::File::create(&fixture).expect("create fixture");
+        let summary = write_logcat_fixture(BufWriter::new(file), target_bytes, 0x5eed).expect("write fixture");
+        println!(
+            "generated {} ({} lines) in {:.1?}",
+            fixture.display(),
+            summary.log_lines,
+            started.elapsed()
+        );
+    }
+
+    let bytes = std::fs::metadata(&fixture).map(|m| m.len()).unwrap_or(0);
+    let db_path = std::env::temp_dir().join("lazy_milktea_bench.db");
+
+    let started = Instant::now();
+    let summary = StreamingIndexBuilder::new(&db_path)
+        .build_from_file(&fixture)
+        .expect("index fixture");
+    let elapsed = started.elapsed();
+
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "indexed {} rows from {:.0} MB in {:.2?} ({:.1} MB/s, {:.0} rows/s)",
+        summary.total_rows,
+        bytes as f64 / 1048576.0,
+        elapsed,
+        bytes as f64 / 1048576.0 / secs,
+        summary.total_rows as f64 / secs
+    );
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", db_path.display(), suffix));
+    }
+}
@@ -0,0 +1,478 @@
+use crate::cache::meta::CacheMeta;
+use crate::cache::presets::PresetStore;
+use crate::error::{LogcatError, Result};
+use crate::index::fields::default_field_tags;
+use crate::types::DeviceInfo;
+use dirs::home_dir;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the cache root under the user's home directory
+const CACHE_ROOT_NAME: &str = ".lazy_milktea_cache";
+
+/// File holding the cache configuration (inside the cache root)
+const CONFIG_FILE: &str = "cache_config.json";
+
+/// Global filter presets (inside the cache root)
+const PRESETS_FILE: &str = "filter_presets.json";
+
+/// Database file name inside each report directory
+pub const DB_FILE: &str = "logcat.db";
+
+/// Prefix of directories a parse builds into before replacing the cache;
+/// hidden from `list`
+const STAGING_PREFIX: &str = ".staging-";
+
+/// Prefix of a replaced cache directory awaiting removal
+const REPLACED_PREFIX: &str = ".replaced-";
+
+/// Default disk quota for all cached reports (20GB)
+const DEFAULT_MAX_BYTES: u64 = 20 * 1024 * 1024 * 1024;
+
+/// Cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheConfig {
+    /// Disk quota for all cached reports; `None` disables eviction
+    pub max_bytes: Option<u64>,
+    /// Tags whose messages get `key=value` fields extracted (`*` for all)
+    #[serde(default = "default_field_tags")]
+    pub field_tags: Vec<String>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+            field_tags: default_field_tags(),
+        }
+    }
+}
+
+/// A cached report as listed to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedReport {
+    /// Cache id (directory name under the cache root)
+    pub id: String,
+    pub source_path: Option<String>,
+    pub device: Option<DeviceInfo>,
+    pub total_rows: Option<usize>,
+    pub min_timestamp_ms: Option<u64>,
+    pub max_timestamp_ms: Option<u64>,
+    pub created_ms: Option<i64>,
+    pub last_opened_ms: Option<i64>,
+    /// Size on disk including WAL files (bytes)
+    pub size_bytes: u64,
+    /// Whether the cache has a usable database
+    pub has_database: bool,
+}
+
+/// Manages the on-disk report cache
+pub struct CacheManager {
+    root: PathBuf,
+}
+
+impl CacheManager {
+    /// Create a manager rooted at an explicit directory
+    pub fn new(root: &Path) -> Result<Self> {
+        std::fs::create_dir_all(root)?;
+        Ok(Self { root: root.to_path_buf() })
+    }
+
+    /// Create a manager rooted at `~/.lazy_milktea_cache`
+    pub fn open_default() -> Result<Self> {
+        let home = home_dir()
+            .ok_or_else(|| LogcatError::CacheNotFound("cannot find home dir".to_string()))?;
+        Self::new(&home.join(CACHE_ROOT_NAME))
+    }
+
+    /// Cache root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Cache id for a bugreport path
+    pub fn id_for_report(report_path: &str) -> String {
+        Path::new(report_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("report")
+            .to_string()
+    }
+
+    /// Prepare (create) the cache directory for a bugreport
+    pub fn prepare_report_dir(&self, report_path: &str) -> Result<PathBuf> {
+        let dir = self.root.join(Self::id_for_report(report_path));
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Create a fresh directory to build a bugreport's cache in
+    ///
+    /// The existing cache (if any) stays untouched until the staging directory
+    /// is moved over it with `commit_staging_dir`, so a failed or cancelled
+    /// parse can simply discard the staging directory.
+    pub fn prepare_staging_dir(&self, report_path: &str) -> Result<PathBuf> {
+        let dir = self.root.join(format!(
+            "{}{}-{}",
+            STAGING_PREFIX,
+            Self::id_for_report(report_path),
+            unique_suffix()
+        ));
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Replace the cache of `report_path` with a fully built staging directory
+    ///
+    /// Returns the final cache directory.
+    pub fn commit_staging_dir(&self, staging: &Path, report_path: &str) -> Result<PathBuf> {
+        let id = Self::id_for_report(report_path);
+        let dir = self.root.join(&id);
+        if !dir.exists() {
+            std::fs::rename(staging, &dir)?;
+            return Ok(dir);
+        }
+
+        // Move the old cache aside first so a failed swap can be undone
+        let replaced = self.root.join(format!("{}{}-{}", REPLACED_PREFIX, id, unique_suffix()));
+        std::fs::rename(&dir, &replaced)?;
+        if let Err(e) = std::fs::rename(staging, &dir) {
+            std::fs::rename(&replaced, &dir)?;
+            return Err(e.into());
+        }
+        if let Err(e) = std::fs::remove_dir_all(&replaced) {
+            log::warn!("Failed to remove replaced cache {}: {}", replaced.display(), e);
+        }
+        Ok(dir)
+    }
+
+    /// Remove staging and replaced directories left by interrupted parses
+    ///
+    /// Directories of caches in `keep` belong to running parses and are left
+    /// alone. Returns the freed bytes.
+    pub fn remove_stale_staging(&self, keep: &[&str]) -> Result<u64> {
+        let mut freed = 0;
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let rest = match name.strip_prefix(STAGING_PREFIX).or_else(|| name.strip_prefix(REPLACED_PREFIX)) {
+                Some(rest) => rest,
+                None => continue,
+            };
+            // `<id>-<pid>-<nanos>`; ids may contain dashes themselves
+            let id = rest.rsplitn(3, '-').last().unwrap_or_default();
+            if !keep.contains(&id) {
+                freed += dir_size(&entry.path());
+                std::fs::remove_dir_all(entry.path())?;
+            }
+        }
+        Ok(freed)
+    }
+
+    /// Resolve an existing report directory by cache id
+    pub fn report_dir(&self, id: &str) -> Result<PathBuf> {
+        validate_id(id)?;
+        let dir = self.root.join(id);
+        if !dir.is_dir() {
+            return Err(LogcatError::CacheNotFound(id.to_string()));
+        }
+        Ok(dir)
+    }
+
+    /// List all cached reports, most recently opened first
+    pub fn list(&self) -> Result<Vec<CachedReport>> {
+        let mut reports = Vec::new();
+
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let id = match entry.file_name().to_str() {
+                Some(s) if !s.starts_with('.') => s.to_string(),
+                _ => continue,
+            };
+            reports.push(self.describe(&id, &entry.path()));
+        }
+
+        reports.sort_by_key(|r| std::cmp::Reverse(r.last_opened_ms));
+        Ok(reports)
+    }
+
+    fn describe(&self, id: &str, dir: &Path) -> CachedReport {
+        let meta = CacheMeta::load(dir).ok();
+        CachedReport {
+            id: id.to_string(),
+            source_path: meta.as_ref().map(|m| m.source_path.clone()),
+            device: meta.as_ref().map(|m| m.device.clone()),
+            total_rows: meta.as_ref().map(|m| m.total_rows),
+            min_timestamp_ms: meta.as_ref().and_then(|m| m.min_timestamp_ms),
+            max_timestamp_ms: meta.as_ref().and_then(|m| m.max_timestamp_ms),
+            created_ms: meta.as_ref().map(|m| m.created_ms),
+            last_opened_ms: meta.as_ref().map(|m| m.last_opened_ms),
+            size_bytes: dir_size(dir),
+            has_database: dir.join(DB_FILE).exists(),
+        }
+    }
+
+    /// Mark a cached report as opened now
+    pub fn touch(&self, id: &str) -> Result<CacheMeta> {
+        let dir = self.report_dir(id)?;
+        let mut meta = CacheMeta::load(&dir)?;
+        meta.last_opened_ms = now_ms();
+        meta.save(&dir)?;
+        Ok(meta)
+    }
+
+    /// Delete one cached report, returning the freed bytes
+    pub fn delete(&self, id: &str) -> Result<u64> {
+        let dir = self.report_dir(id)?;
+        let size = dir_size(&dir);
+        std::fs::remove_dir_all(&dir)?;
+        Ok(size)
+    }
+
+    /// Delete all cached reports except those in `keep`, returning the freed bytes
+    ///
+    /// Like eviction, skips reports without metadata, which may still be
+    /// being built.
+    pub fn clear(&self, keep: &[&str]) -> Result<u64> {
+        let mut freed = 0;
+        for report in self.list()? {
+            if report.last_opened_ms.is_some() && !keep.contains(&report.id.as_str()) {
+                freed += self.delete(&report.id)?;
+            }
+        }
+        Ok(freed)
+    }
+
+    /// Load the cache configuration (defaults if missing)
+    pub fn config(&self) -> CacheConfig {
+        std::fs::read(self.root.join(CONFIG_FILE))
+            .ok()
+            .and_then(|b| serde_json::from_slice(&b).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the cache configuration
+    pub fn set_config(&self, config: &CacheConfig) -> Result<()> {
+        let json = serde_json::to_vec_pretty(config)?;
+        std::fs::write(self.root.join(CONFIG_FILE), json)?;
+        Ok(())
+    }
+
+    /// Global filter preset store
+    pub fn presets(&self) -> PresetStore {
+        PresetStore::new(&self.root.join(PRESETS_FILE))
+    }
+
+    /// Evict least recently opened reports until the cache fits the quota
+    ///
+    /// Reports listed in `keep` (e.g. currently open ones or ones a job is
+    /// writing) are never evicted, nor are reports without metadata, which
+    /// may still be being built. Returns the ids of evicted reports.
+    pub fn enforce_quota(&self, keep: &[&str]) -> Result<Vec<String>> {
+        let max_bytes = match self.config().max_bytes {
+            Some(m) => m,
+            None => return Ok(Vec::new()),
+        };
+
+        self.remove_stale_staging(keep)?;
+
+        let mut reports = self.list()?;
+        let mut total: u64 = reports.iter().map(|r| r.size_bytes).sum();
+
+        // Oldest first
+        reports.sort_by_key(|r| r.last_opened_ms);
+
+        let mut evicted = Vec::new();
+        for report in reports {
+            if total <= max_bytes {
+                break;
+            }
+            if report.last_opened_ms.is_none() || keep.contains(&report.id.as_str()) {
+                continue;
+            }
+            total = total.saturating_sub(self.delete(&report.id)?);
+            evicted.push(report.id);
+        }
+
+        Ok(evicted)
+    }
+}
+
+/// Reject ids that could escape the cache root
+fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty() || id == "." || id == ".." || id.contains(['/', '\\']) {
+        return Err(LogcatError::CacheNotFound(format!("invalid cache id: {}", id)));
+    }
+    Ok(())
+}
+
+/// Total size of all files under a directory
+fn dir_size(dir: &Path) -> u64 {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return 0,
+    };
+
+    entries
+        .flatten()
+        .map(|e| match e.metadata() {
+            Ok(m) if m.is_dir() => dir_size(&e.path()),
+            Ok(m) => m.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Suffix keeping concurrent staging directories apart
+fn unique_suffix() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), nanos)
+}
+
+/// Current time in Unix milliseconds
+pub fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_root() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("test_cache_{}", nanos))
+    }
+
+    fn add_report(manager: &CacheManager, id: &str, bytes: usize, last_opened_ms: i64) {
+        let dir = manager.prepare_report_dir(&format!("/tmp/{}.zip", id)).unwrap();
+        std::fs::write(dir.join(DB_FILE), vec![0u8; bytes]).unwrap();
+        CacheMeta {
+            source_path: format!("/tmp/{}.zip", id),
+            last_opened_ms,
+            ..Default::default()
+        }
+        .save(&dir)
+        .unwrap();
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let root = temp_root();
+        let manager = CacheManager::new(&root).unwrap();
+        add_report(&manager, "old", 100, 1);
+        add_report(&manager, "new", 100, 2);
+
+        let reports = manager.list().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].id, "new");
+        assert!(reports[0].has_database);
+        assert!(reports[0].size_bytes >= 100);
+
+        assert!(manager.delete("old").unwrap() >= 100);
+        assert_eq!(manager.list().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_reject_invalid_id() {
+        let root = temp_root();
+        let manager = CacheManager::new(&root).unwrap();
+        assert!(manager.delete("..").is_err());
+        assert!(manager.delete("a/b").is_err());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_enforce_quota_evicts_lru() {
+        let root = temp_root();
+        let manager = CacheManager::new(&root).unwrap();
+        manager.set_config(&CacheConfig { max_bytes: Some(3500), ..CacheConfig::default() }).unwrap();
+
+        add_report(&manager, "a", 1000, 1);
+        add_report(&manager, "b", 1000, 2);
+        add_report(&manager, "c", 1000, 3);
+
+        // "a" is the least recently opened but is kept
+        let evicted = manager.enforce_quota(&["a"]).unwrap();
+        assert_eq!(evicted, vec!["b".to_string()]);
+
+        let ids: Vec<String> = manager.list().unwrap().into_iter().map(|r| r.id).collect();
+        assert!(ids.contains(&"a".to_string()));
+        assert!(ids.contains(&"c".to_string()));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_enforce_quota_skips_reports_without_meta() {
+        let root = temp_root();
+        let manager = CacheManager::new(&root).unwrap();
+        manager.set_config(&CacheConfig { max_bytes: Some(1500), ..CacheConfig::default() }).unwrap();
+
+        // A parse still writing its database has no meta.json yet
+        let building = manager.prepare_report_dir("/tmp/building.zip").unwrap();
+        std::fs::write(building.join(DB_FILE), vec![0u8; 1000]).unwrap();
+        add_report(&manager, "a", 1000, 1);
+
+        assert_eq!(manager.enforce_quota(&[]).unwrap(), vec!["a".to_string()]);
+        assert!(building.join(DB_FILE).exists());
+
+        // Clearing keeps reports owned by a running job and unfinished ones
+        add_report(&manager, "b", 10, 2);
+        add_report(&manager, "c", 10, 3);
+        manager.clear(&["c"]).unwrap();
+        let mut ids: Vec<String> = manager.list().unwrap().into_iter().map(|r| r.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["building".to_string(), "c".to_string()]);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_staging_dir_replaces_cache_on_commit() {
+        let root = temp_root();
+        let manager = CacheManager::new(&root).unwrap();
+        add_report(&manager, "report", 10, 1);
+
+        // A discarded staging dir leaves the existing cache intact
+        let staging = manager.prepare_staging_dir("/tmp/report.zip").unwrap();
+        assert_eq!(manager.list().unwrap().len(), 1);
+        std::fs::remove_dir_all(&staging).unwrap();
+        assert!(root.join("report").join(DB_FILE).exists());
+
+        let staging = manager.prepare_staging_dir("/tmp/report.zip").unwrap();
+        std::fs::write(staging.join(DB_FILE), vec![0u8; 20]).unwrap();
+        let dir = manager.commit_staging_dir(&staging, "/tmp/report.zip").unwrap();
+        assert_eq!(dir, root.join("report"));
+        assert_eq!(std::fs::metadata(dir.join(DB_FILE)).unwrap().len(), 20);
+        assert!(!dir.join("meta.json").exists());
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 1);
+
+        // Staging dirs of running parses survive, stale ones are removed
+        let running = manager.prepare_staging_dir("/tmp/my-report.zip").unwrap();
+        manager.prepare_staging_dir("/tmp/other.zip").unwrap();
+        manager.remove_stale_staging(&["my-report"]).unwrap();
+        assert!(running.exists());
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 2);
+
+        manager.remove_stale_staging(&[]).unwrap();
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,53 @@
+use crate::error::{LogcatError, Result};
+use crate::time::AnchorInfo;
+use crate::types::DeviceInfo;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File name of the per-report metadata stored next to `logcat.db`
+pub const META_FILE: &str = "meta.json";
+
+/// Metadata describing a cached report
+///
+/// Written after a successful parse so the report can be listed and
+/// reopened later without the original bugreport file.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheMeta {
+    /// Path of the bugreport the cache was built from
+    pub source_path: String,
+    pub device: DeviceInfo,
+    pub anr_count: usize,
+    pub crash_count: usize,
+    pub total_rows: usize,
+    pub error_count: usize,
+    pub fatal_count: usize,
+    pub min_timestamp_ms: Option<u64>,
+    pub max_timestamp_ms: Option<u64>,
+    /// Provenance of the timestamps (absent in caches from older versions)
+    #[serde(default)]
+    pub time_anchor: Option<AnchorInfo>,
+    /// When the cache was built (Unix ms)
+    pub created_ms: i64,
+    /// When the report was last parsed or reopened (Unix ms)
+    pub last_opened_ms: i64,
+}
+
+impl CacheMeta {
+    /// Load metadata from a cache directory
+    pub fn load(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join(META_FILE);
+        if !path.exists() {
+            return Err(LogcatError::CacheNotFound(path.display().to_string()));
+        }
+        let bytes = std::fs::read(&path)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Save metadata into a cache directory
+    pub fn save(&self, cache_dir: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(cache_dir.join(META_FILE), json)?;
+        Ok(())
+    }
+}
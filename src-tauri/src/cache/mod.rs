@@ -0,0 +1,7 @@
+mod manager;
+mod meta;
+mod presets;
+
+pub use manager::{CacheManager, CacheConfig, CachedReport, DB_FILE, now_ms};
+pub use meta::CacheMeta;
+pub use presets::{FilterPreset, PresetImport};
@@ -0,0 +1,256 @@
+use crate::cache::now_ms;
+use crate::error::{LogcatError, Result};
+use crate::types::LogFilters;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Version written to preset files
+const PRESET_FILE_VERSION: u32 = 1;
+
+/// A named, reusable set of filters (may be just a query-language string)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterPreset {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub filters: LogFilters,
+    #[serde(default)]
+    pub created_ms: i64,
+    #[serde(default)]
+    pub updated_ms: i64,
+}
+
+/// On-disk format of the preset store and of exported preset files
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PresetFile {
+    version: u32,
+    presets: Vec<FilterPreset>,
+}
+
+/// Outcome of importing a preset file
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresetImport {
+    pub added: usize,
+    pub replaced: usize,
+    /// Presets whose name already existed (when not overwriting)
+    pub skipped: usize,
+}
+
+/// Global filter presets stored as JSON in the cache root
+pub struct PresetStore {
+    path: PathBuf,
+}
+
+impl PresetStore {
+    /// Store backed by the JSON file at `path` (created on first save)
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    /// All presets, sorted by name
+    pub fn list(&self) -> Result<Vec<FilterPreset>> {
+        let mut presets = read_file(&self.path)?;
+        presets.sort_by_key(|p| p.name.to_lowercase());
+        Ok(presets)
+    }
+
+    /// Preset by name
+    pub fn get(&self, name: &str) -> Result<FilterPreset> {
+        read_file(&self.path)?
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| LogcatError::PresetNotFound(name.to_string()))
+    }
+
+    /// Create or replace the preset called `name`
+    pub fn save(&self, name: &str, description: Option<String>, filters: LogFilters) -> Result<FilterPreset> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(LogcatError::InvalidFilter("Preset name is empty".to_string()));
+        }
+
+        let mut presets = read_file(&self.path)?;
+        let now = now_ms();
+        let preset = match presets.iter_mut().find(|p| p.name == name) {
+            Some(existing) => {
+                existing.description = description;
+                existing.filters = filters;
+                existing.updated_ms = now;
+                existing.clone()
+            }
+            None => {
+                let preset = FilterPreset {
+                    name: name.to_string(),
+                    description,
+                    filters,
+                    created_ms: now,
+                    updated_ms: now,
+                };
+                presets.push(preset.clone());
+                preset
+            }
+        };
+
+        write_file(&self.path, presets)?;
+        Ok(preset)
+    }
+
+    /// Delete a preset, returning whether it existed
+    pub fn delete(&self, name: &str) -> Result<bool> {
+        let mut presets = read_file(&self.path)?;
+        let before = presets.len();
+        presets.retain(|p| p.name != name);
+        if presets.len() == before {
+            return Ok(false);
+        }
+        write_file(&self.path, presets)?;
+        Ok(true)
+    }
+
+    /// Write presets (all, or only `names`) to a shareable file
+    pub fn export_to(&self, path: &Path, names: Option<&[String]>) -> Result<usize> {
+        let mut presets = self.list()?;
+        if let Some(names) = names {
+            presets.retain(|p| names.contains(&p.name));
+        }
+        let count = presets.len();
+        write_file(path, presets)?;
+        Ok(count)
+    }
+
+    /// Merge presets from a file written by `export_to`
+    pub fn import_from(&self, path: &Path, overwrite: bool) -> Result<PresetImport> {
+        if !path.exists() {
+            return Err(LogcatError::CacheNotFound(path.display().to_string()));
+        }
+
+        let mut presets = read_file(&self.path)?;
+        let mut result = PresetImport::default();
+
+        for mut incoming in read_file(path)? {
+            incoming.name = incoming.name.trim().to_string();
+            if incoming.name.is_empty() {
+                result.skipped += 1;
+                continue;
+            }
+            match presets.iter_mut().find(|p| p.name == incoming.name) {
+                Some(existing) if overwrite => {
+                    incoming.created_ms = existing.created_ms;
+                    incoming.updated_ms = now_ms();
+                    *existing = incoming;
+                    result.replaced += 1;
+                }
+                Some(_) => result.skipped += 1,
+                None => {
+                    let now = now_ms();
+                    incoming.created_ms = now;
+                    incoming.updated_ms = now;
+                    presets.push(incoming);
+                    result.added += 1;
+                }
+            }
+        }
+
+        write_file(&self.path, presets)?;
+        Ok(result)
+    }
+}
+
+/// Presets in a file (empty if it does not exist yet)
+fn read_file(path: &Path) -> Result<Vec<FilterPreset>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file: PresetFile = serde_json::from_slice(&std::fs::read(path)?)?;
+    Ok(file.presets)
+}
+
+fn write_file(path: &Path, presets: Vec<FilterPreset>) -> Result<()> {
+    let file = PresetFile {
+        version: PRESET_FILE_VERSION,
+        presets,
+    };
+    std::fs::write(path, serde_json::to_vec_pretty(&file)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("test_presets_{}_{}.json", name, nanos))
+    }
+
+    fn tag_filters(tag: &str) -> LogFilters {
+        LogFilters {
+            tag: Some(tag.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_save_list_delete() {
+        let path = temp_path("store");
+        let store = PresetStore::new(&path);
+        assert!(store.list().unwrap().is_empty());
+
+        store.save("Wi-Fi stack", None, tag_filters("WifiService|wpa_supplicant")).unwrap();
+        let query = LogFilters {
+            query: Some("tag:Binder level>=E".to_string()),
+            ..Default::default()
+        };
+        store.save("Binder errors", Some("binder failures".to_string()), query).unwrap();
+
+        // Saving an existing name replaces its filters
+        let created = store.get("Wi-Fi stack").unwrap().created_ms;
+        let updated = store.save("Wi-Fi stack", None, tag_filters("WifiHAL")).unwrap();
+        assert_eq!(updated.created_ms, created);
+
+        let names: Vec<String> = store.list().unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["Binder errors", "Wi-Fi stack"]);
+        assert_eq!(store.get("Wi-Fi stack").unwrap().filters.tag.as_deref(), Some("WifiHAL"));
+
+        assert!(store.delete("Binder errors").unwrap());
+        assert!(!store.delete("Binder errors").unwrap());
+        assert!(matches!(store.get("Binder errors"), Err(LogcatError::PresetNotFound(_))));
+        assert!(store.save("  ", None, LogFilters::default()).is_err());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_export_import() {
+        let ours = temp_path("ours");
+        let theirs = temp_path("theirs");
+        let shared = temp_path("shared");
+
+        let mine = PresetStore::new(&ours);
+        mine.save("Camera HAL", None, tag_filters("CameraHal")).unwrap();
+
+        let team = PresetStore::new(&theirs);
+        team.save("Camera HAL", None, tag_filters("Camera3")).unwrap();
+        team.save("Wi-Fi stack", None, tag_filters("WifiService")).unwrap();
+        assert_eq!(team.export_to(&shared, None).unwrap(), 2);
+
+        let result = mine.import_from(&shared, false).unwrap();
+        assert_eq!((result.added, result.replaced, result.skipped), (1, 0, 1));
+        assert_eq!(mine.get("Camera HAL").unwrap().filters.tag.as_deref(), Some("CameraHal"));
+
+        let result = mine.import_from(&shared, true).unwrap();
+        assert_eq!((result.added, result.replaced, result.skipped), (0, 2, 0));
+        assert_eq!(mine.get("Camera HAL").unwrap().filters.tag.as_deref(), Some("Camera3"));
+
+        for path in [ours, theirs, shared] {
+            std::fs::remove_file(&path).ok();
+        }
+    }
+}
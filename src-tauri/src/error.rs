@@ -26,12 +26,39 @@ pub enum LogcatError {
     #[error("Invalid filter: {0}")]
     InvalidFilter(String),
 
+    #[error("Query syntax error at {position}: {message}")]
+    QuerySyntax { position: usize, message: String },
+
     #[error("Regex error: {0}")]
     Regex(#[from] regex::Error),
 
     #[error("Cache not found: {0}")]
     CacheNotFound(String),
 
+    #[error("Report not found: {0}")]
+    ReportNotFound(String),
+
+    #[error("Row not found: {0}")]
+    RowNotFound(i64),
+
+    #[error("Preset not found: {0}")]
+    PresetNotFound(String),
+
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+
+    #[error("Job expired: {0} finished and was pruned")]
+    JobExpired(String),
+
+    #[error("Cache in use by a running job: {0}")]
+    CacheBusy(String),
+
+    #[error("Index not ready: {0} still being built")]
+    IndexPending(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
     #[error("No bugreport found in archive")]
     NoBugreportFound,
 
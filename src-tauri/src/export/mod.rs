@@ -0,0 +1,3 @@
+mod writer;
+
+pub use writer::{export_logs, ExportOptions, ExportSummary};
@@ -0,0 +1,445 @@
+use crate::error::{LogcatError, Result};
+use crate::query::QueryExecutor;
+use crate::report::bookmarks::{list_bookmarks, Bookmark};
+use crate::types::{LogFilters, LogRow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Rows written between progress callbacks
+const PROGRESS_INTERVAL: usize = 5000;
+
+/// Output format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// `logcat -v threadtime` text, as in the bugreport
+    Threadtime,
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+    /// Self-contained HTML page with level colouring
+    Html,
+}
+
+/// What to export: the rows matching `filters` within the time range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub filters: LogFilters,
+    pub start_ms: Option<f64>,
+    pub end_ms: Option<f64>,
+    /// Annotate bookmarked rows with their label and note
+    #[serde(default)]
+    pub include_bookmarks: bool,
+}
+
+/// Outcome of a finished export
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub path: String,
+    pub format: ExportFormat,
+    pub rows: usize,
+    /// Exported rows that carried a bookmark
+    pub bookmarks: usize,
+    pub bytes: u64,
+}
+
+/// Write every row matching `options` to `path`
+///
+/// Output goes to `<path>.part` and is renamed into place on success, so a
+/// cancelled or failed export never leaves a truncated file behind.
+/// `on_progress` receives `(rows_written, total_rows)`.
+pub fn export_logs<F>(
+    executor: &QueryExecutor,
+    path: &Path,
+    options: &ExportOptions,
+    cancel_flag: &AtomicBool,
+    mut on_progress: F,
+) -> Result<ExportSummary>
+where
+    F: FnMut(usize, usize),
+{
+    let total = executor.count_in_range(&options.filters, options.start_ms, options.end_ms)?;
+    on_progress(0, total);
+
+    let bookmarks: HashMap<i64, Bookmark> = if options.include_bookmarks {
+        list_bookmarks(executor.connection())?
+            .into_iter()
+            .map(|b| (b.row_id, b))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let part_path = part_path(path);
+    let written = write_rows(executor, &part_path, options, &bookmarks, cancel_flag, total, &mut on_progress);
+    let (rows, bookmarked) = match written {
+        Ok(counts) => counts,
+        Err(e) => {
+            let _ = std::fs::remove_file(&part_path);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&part_path, path)?;
+    on_progress(rows, total);
+
+    Ok(ExportSummary {
+        path: path.display().to_string(),
+        format: options.format,
+        rows,
+        bookmarks: bookmarked,
+        bytes: std::fs::metadata(path)?.len(),
+    })
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+fn write_rows<F>(
+    executor: &QueryExecutor,
+    part_path: &Path,
+    options: &ExportOptions,
+    bookmarks: &HashMap<i64, Bookmark>,
+    cancel_flag: &AtomicBool,
+    total: usize,
+    on_progress: &mut F,
+) -> Result<(usize, usize)>
+where
+    F: FnMut(usize, usize),
+{
+    let mut out = BufWriter::new(File::create(part_path)?);
+    let format = options.format;
+    let with_bookmarks = options.include_bookmarks;
+
+    write_header(&mut out, format, with_bookmarks)?;
+
+    let mut written = 0;
+    let mut bookmarked = 0;
+    executor.for_each_row(&options.filters, options.start_ms, options.end_ms, |row| {
+        if cancel_flag.load(Ordering::Relaxed) {
+            return Err(LogcatError::Cancelled);
+        }
+
+        let bookmark = row.id.and_then(|id| bookmarks.get(&id));
+        if bookmark.is_some() {
+            bookmarked += 1;
+        }
+        write_row(&mut out, format, with_bookmarks, row, bookmark)?;
+
+        written += 1;
+        if written % PROGRESS_INTERVAL == 0 {
+            on_progress(written, total);
+        }
+        Ok(())
+    })?;
+
+    write_footer(&mut out, format, written)?;
+    out.flush()?;
+    Ok((written, bookmarked))
+}
+
+fn write_header<W: Write>(out: &mut W, format: ExportFormat, with_bookmarks: bool) -> Result<()> {
+    match format {
+        ExportFormat::Threadtime | ExportFormat::Jsonl => {}
+        ExportFormat::Csv => {
+            let bookmark_columns = if with_bookmarks { ",bookmark_label,bookmark_note" } else { "" };
+            writeln!(out, "id,ts,ts_iso,level,tag,pid,tid,msg{}", bookmark_columns)?;
+        }
+        ExportFormat::Html => {
+            write!(out, "{}", HTML_HEAD)?;
+            let bookmark_column = if with_bookmarks { "<th>Bookmark</th>" } else { "" };
+            writeln!(
+                out,
+                "<table>\n<thead><tr><th>Time</th><th>PID</th><th>TID</th><th>Level</th><th>Tag</th><th>Message</th>{}</tr></thead>\n<tbody>",
+                bookmark_column,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_row<W: Write>(
+    out: &mut W,
+    format: ExportFormat,
+    with_bookmarks: bool,
+    row: &LogRow,
+    bookmark: Option<&Bookmark>,
+) -> Result<()> {
+    match format {
+        ExportFormat::Threadtime => {
+            // Bookmarks go on a separator line like logcat's buffer headers
+            if let Some(b) = bookmark {
+                writeln!(out, "--------- bookmark: {}", bookmark_text(b))?;
+            }
+            writeln!(out, "{}", threadtime_line(row))?;
+        }
+        ExportFormat::Csv => {
+            let fields = [
+                row.id.map(|id| id.to_string()).unwrap_or_default(),
+                row.ts.clone(),
+                row.ts_iso.clone().unwrap_or_default(),
+                row.level.clone(),
+                row.tag.clone(),
+                row.pid.to_string(),
+                row.tid.to_string(),
+                row.msg.clone(),
+            ];
+            let mut line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            if with_bookmarks {
+                line.push(csv_field(bookmark.map_or("", |b| b.label.as_str())));
+                line.push(csv_field(bookmark.map_or("", |b| b.note.as_str())));
+            }
+            writeln!(out, "{}", line.join(","))?;
+        }
+        ExportFormat::Jsonl => {
+            serde_json::to_writer(&mut *out, &JsonlRecord { row, bookmark })?;
+            writeln!(out)?;
+        }
+        ExportFormat::Html => {
+            let class = if bookmark.is_some() { " bookmarked" } else { "" };
+            write!(
+                out,
+                "<tr class=\"lv-{}{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+                html_escape(&row.level),
+                class,
+                html_escape(&row.ts),
+                row.pid,
+                row.tid,
+                html_escape(&row.level),
+                html_escape(row.tag.trim_end()),
+                html_escape(&row.msg),
+            )?;
+            if with_bookmarks {
+                let text = bookmark.map(bookmark_text).unwrap_or_default();
+                write!(out, "<td>{}</td>", html_escape(&text))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_footer<W: Write>(out: &mut W, format: ExportFormat, rows: usize) -> Result<()> {
+    if format == ExportFormat::Html {
+        writeln!(out, "</tbody>\n</table>\n<p class=\"summary\">{} rows</p>\n</body>\n</html>", rows)?;
+    }
+    Ok(())
+}
+
+/// JSON Lines record: the row plus its bookmark, if any
+#[derive(Serialize)]
+struct JsonlRecord<'a> {
+    #[serde(flatten)]
+    row: &'a LogRow,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bookmark: Option<&'a Bookmark>,
+}
+
+/// Row in `logcat -v threadtime` layout
+fn threadtime_line(row: &LogRow) -> String {
+    format!(
+        "{} {:>5} {:>5} {} {:<8}: {}",
+        row.ts,
+        row.pid,
+        row.tid,
+        row.level,
+        row.tag.trim_end(),
+        row.msg,
+    )
+}
+
+fn bookmark_text(bookmark: &Bookmark) -> String {
+    match (bookmark.label.is_empty(), bookmark.note.is_empty()) {
+        (_, true) => bookmark.label.clone(),
+        (true, false) => bookmark.note.clone(),
+        (false, false) => format!("{} - {}", bookmark.label, bookmark.note),
+    }
+}
+
+/// RFC 4180 field, quoted only when needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Logcat export</title>
+<style>
+body { font-family: ui-monospace, Menlo, Consolas, monospace; font-size: 12px; margin: 16px; background: #fff; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+th { text-align: left; position: sticky; top: 0; background: #eee; }
+td, th { padding: 1px 6px; vertical-align: top; white-space: pre-wrap; }
+td:nth-child(2), td:nth-child(3) { text-align: right; }
+tr.lv-V { color: #888; }
+tr.lv-D { color: #2a6bb0; }
+tr.lv-I { color: #222; }
+tr.lv-W { color: #b36b00; }
+tr.lv-E { color: #c62828; }
+tr.lv-F { color: #fff; background: #c62828; }
+tr.bookmarked { outline: 1px solid #f0b400; background: #fff8dc; }
+.summary { color: #888; }
+</style>
+</head>
+<body>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexBuilder;
+    use crate::report::bookmarks::{ensure_schema, set_bookmark, BookmarkInput};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("test_export_{}", nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn create_executor(dir: &Path) -> QueryExecutor {
+        let db_path = dir.join("logcat.db");
+        IndexBuilder::new(&db_path)
+            .unwrap()
+            .build_from_text(concat!(
+                "08-24 14:22:33.123  1234  5678 I Tag: hello\n",
+                "08-24 14:22:34.000  1234  5678 E Tag: a, \"quoted\" <b>\n",
+                "08-24 14:22:35.000    42    42 W Other: later\n",
+            ))
+            .unwrap();
+        let executor = QueryExecutor::open(&db_path).unwrap();
+        ensure_schema(executor.connection()).unwrap();
+        executor
+    }
+
+    fn options(format: ExportFormat) -> ExportOptions {
+        ExportOptions {
+            format,
+            filters: LogFilters::default(),
+            start_ms: None,
+            end_ms: None,
+            include_bookmarks: true,
+        }
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let dir = temp_dir();
+        let executor = create_executor(&dir);
+        let input = BookmarkInput {
+            label: "boom".to_string(),
+            note: "see ticket".to_string(),
+            ..Default::default()
+        };
+        set_bookmark(executor.connection(), 2, &input).unwrap();
+        let never = AtomicBool::new(false);
+
+        let path = dir.join("out.txt");
+        let summary = export_logs(&executor, &path, &options(ExportFormat::Threadtime), &never, |_, _| {}).unwrap();
+        assert_eq!((summary.rows, summary.bookmarks), (3, 1));
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "08-24 14:22:33.123  1234  5678 I Tag     : hello");
+        assert_eq!(lines[1], "--------- bookmark: boom - see ticket");
+        assert!(!dir.join("out.txt.part").exists());
+
+        let path = dir.join("out.csv");
+        export_logs(&executor, &path, &options(ExportFormat::Csv), &never, |_, _| {}).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        assert!(csv.starts_with("id,ts,ts_iso,level,tag,pid,tid,msg,bookmark_label,bookmark_note\n"));
+        assert!(csv.contains(",\"a, \"\"quoted\"\" <b>\",boom,see ticket\n"));
+
+        let path = dir.join("out.jsonl");
+        export_logs(&executor, &path, &options(ExportFormat::Jsonl), &never, |_, _| {}).unwrap();
+        let records: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1]["bookmark"]["label"], "boom");
+        assert!(records[0].get("bookmark").is_none());
+
+        let path = dir.join("out.html");
+        export_logs(&executor, &path, &options(ExportFormat::Html), &never, |_, _| {}).unwrap();
+        let html = std::fs::read_to_string(&path).unwrap();
+        assert!(html.contains("<tr class=\"lv-E bookmarked\">"));
+        assert!(html.contains("a, &quot;quoted&quot; &lt;b&gt;"));
+        assert!(html.trim_end().ends_with("</html>"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_export_filters_range_and_cancel() {
+        let dir = temp_dir();
+        let executor = create_executor(&dir);
+        let never = AtomicBool::new(false);
+
+        let first_ms: f64 = executor
+            .connection()
+            .query_row("SELECT MIN(ts_unix) FROM logs", [], |r| r.get(0))
+            .unwrap();
+        let mut opts = options(ExportFormat::Threadtime);
+        opts.filters.tag = Some("Tag".to_string());
+        opts.start_ms = Some(first_ms + 500.0);
+
+        let path = dir.join("range.txt");
+        let mut progress = Vec::new();
+        let summary = export_logs(&executor, &path, &opts, &never, |done, total| progress.push((done, total))).unwrap();
+        assert_eq!(summary.rows, 1);
+        assert_eq!(progress.first(), Some(&(0, 1)));
+        assert_eq!(progress.last(), Some(&(1, 1)));
+
+        let cancelled = AtomicBool::new(true);
+        let path = dir.join("cancelled.txt");
+        let err = export_logs(&executor, &path, &opts, &cancelled, |_, _| {}).unwrap_err();
+        assert!(matches!(err, LogcatError::Cancelled));
+        assert!(!path.exists());
+        assert!(!dir.join("cancelled.txt.part").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -1,5 +1,8 @@
 use crate::error::{LogcatError, Result};
 use crate::index::sqlite::LogcatDatabase;
+use crate::index::fields::{default_field_tags, extract_fields};
+use crate::index::processes::track_processes;
+use crate::index::templates::{store_templates, TemplateMiner};
 use crate::parser::LOGCAT_RE_MULTILINE;
 use crate::time::{TimeAnchor, derive_time_anchor, to_iso_safe, iso_ts_key_ms};
 use crate::types::LogRow;
@@ -19,6 +22,7 @@ pub struct IndexSummary {
 pub struct IndexBuilder {
     db: LogcatDatabase,
     anchor: Option<TimeAnchor>,
+    field_tags: Vec<String>,
     summary: IndexSummary,
 }
 
@@ -29,6 +33,7 @@ impl IndexBuilder {
         Ok(Self {
             db,
             anchor: None,
+            field_tags: default_field_tags(),
             summary: IndexSummary::default(),
         })
     }
@@ -39,12 +44,19 @@ impl IndexBuilder {
         self
     }
 
+    /// Tags whose messages are scanned for `key=value` fields (`*` for all)
+    pub fn with_field_tags(mut self, tags: Vec<String>) -> Self {
+        self.field_tags = tags;
+        self
+    }
+
     /// Build index from text content
     pub fn build_from_text(mut self, text: &str) -> Result<IndexSummary> {
         // Derive time anchor if not set
         let anchor = self.anchor.take().unwrap_or_else(|| derive_time_anchor(text));
 
-        // Begin batch insert
+        // Begin batch insert; rows are clustered into templates as they go
+        let mut miner = TemplateMiner::default();
         let batch = self.db.begin_batch()?;
 
         for caps in LOGCAT_RE_MULTILINE.captures_iter(text) {
@@ -61,6 +73,7 @@ impl IndexBuilder {
                 .unwrap_or(0) as f64;
 
             let row = LogRow {
+                id: None,
                 ts: ts.clone(),
                 ts_iso,
                 level: level.clone(),
@@ -68,9 +81,13 @@ impl IndexBuilder {
                 pid: caps["pid"].parse().unwrap_or_default(),
                 tid: caps["tid"].parse().unwrap_or_default(),
                 msg: caps["msg"].to_string(),
+                matches: None,
+                repeat: None,
+                instance_id: None,
             };
 
-            batch.insert(&row, ts_unix_ms)?;
+            let template = miner.add(&row.tag, &row.msg);
+            batch.insert_with_template(&row, ts_unix_ms, template)?;
 
             // Update summary
             self.summary.total_rows += 1;
@@ -95,6 +112,10 @@ impl IndexBuilder {
         // Commit batch
         batch.commit()?;
 
+        store_templates(self.db.connection(), &miner)?;
+        extract_fields(self.db.connection(), &self.field_tags)?;
+        track_processes(self.db.connection())?;
+
         // Optimize database
         self.db.optimize()?;
 
@@ -157,6 +178,31 @@ persist.sys.timezone=Asia/Taipei
         std::fs::remove_file(&db_path).ok();
     }
 
+    #[test]
+    fn test_reused_pid_gets_new_instance() {
+        let sample = r#"
+08-24 14:22:33.000  1000  1100 I am_proc_start: [0,4242,10123,com.foo,activity,{com.foo/.Main}]
+08-24 14:22:33.100  4242  4242 I Foo: first launch
+08-24 14:22:34.000  1000  1100 I am_proc_died: [0,4242,com.foo,900,17]
+08-24 14:30:00.000  4242  4242 I Bar: reused pid
+"#;
+
+        let db_path = temp_db_path();
+        IndexBuilder::new(&db_path)
+            .unwrap()
+            .build_from_text(sample)
+            .unwrap();
+
+        let db = LogcatDatabase::open(&db_path).unwrap();
+        let instances: i64 = db.connection()
+            .query_row("SELECT COUNT(DISTINCT instance_id) FROM logs WHERE pid = 4242", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(instances, 2);
+
+        // Cleanup
+        std::fs::remove_file(&db_path).ok();
+    }
+
     #[test]
     fn test_fts_after_build() {
         let sample = r#"
@@ -0,0 +1,164 @@
+use crate::error::{LogcatError, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection};
+
+/// Tags whose messages are scanned for fields unless configured otherwise
+pub const DEFAULT_FIELD_TAGS: &[&str] = &[
+    "ActivityManager",
+    "ActivityTaskManager",
+    "PackageManager",
+    "WindowManager",
+    "InputDispatcher",
+    "PowerManagerService",
+    "JobScheduler",
+    "AlarmManager",
+    "ConnectivityService",
+    "BatteryService",
+];
+
+/// Tag allow-list entry matching every tag
+pub const ALL_TAGS: &str = "*";
+
+/// Fields kept per row; the rest of a long message is ignored
+const MAX_FIELDS_PER_ROW: usize = 32;
+
+/// `key=value` / `key: value` pairs extracted from messages
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS log_fields (
+        row_id INTEGER NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_fields_key_value ON log_fields(key, value);
+    CREATE INDEX IF NOT EXISTS idx_fields_row ON log_fields(row_id);";
+
+/// A key at a word boundary, `=` or `: `, then a quoted or bare value
+static FIELD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?:^|[\s,;({\[])([A-Za-z][\w.-]{0,31})(?:=|: )(?:"([^"]*)"|'([^']*)'|([^\s,;)}\]]+))"#).unwrap()
+});
+
+/// Default tag allow-list as owned strings (for configuration defaults)
+pub fn default_field_tags() -> Vec<String> {
+    DEFAULT_FIELD_TAGS.iter().map(|t| t.to_string()).collect()
+}
+
+/// Extract `(key, value)` pairs from a message
+///
+/// Keys are lowercased so `Reason=` and `reason=` query alike; bare
+/// values lose trailing sentence punctuation.
+pub fn parse_fields(msg: &str) -> Vec<(String, String)> {
+    FIELD_RE
+        .captures_iter(msg)
+        .filter_map(|caps| {
+            let value = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .map(|m| m.as_str())
+                .or_else(|| caps.get(4).map(|m| m.as_str().trim_end_matches(['.', ':'])))?;
+            (!value.is_empty()).then(|| (caps[1].to_ascii_lowercase(), value.to_string()))
+        })
+        .take(MAX_FIELDS_PER_ROW)
+        .collect()
+}
+
+/// Create the `log_fields` table if missing
+pub fn ensure_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+/// Replace the fields of every row whose tag is in `tags`
+///
+/// Tags are compared after trimming; `*` selects all rows. Returns the
+/// number of extracted fields.
+pub fn extract_fields(conn: &Connection, tags: &[String]) -> Result<usize> {
+    let db_err = |e: rusqlite::Error| LogcatError::Database(e.to_string());
+    ensure_schema(conn)?;
+
+    let tags: Vec<&str> = tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+    let tx = conn.unchecked_transaction().map_err(db_err)?;
+    tx.execute("DELETE FROM log_fields", []).map_err(db_err)?;
+    if tags.is_empty() {
+        tx.commit().map_err(db_err)?;
+        return Ok(0);
+    }
+
+    let (sql, tag_params): (String, Vec<Value>) = if tags.contains(&ALL_TAGS) {
+        ("SELECT id, msg FROM logs".to_string(), Vec::new())
+    } else {
+        (
+            format!("SELECT id, msg FROM logs WHERE trim(tag) IN ({})", vec!["?"; tags.len()].join(",")),
+            tags.iter().map(|t| Value::Text(t.to_string())).collect(),
+        )
+    };
+
+    let mut extracted = 0;
+    {
+        let mut select = tx.prepare(&sql).map_err(db_err)?;
+        let mut insert = tx
+            .prepare("INSERT INTO log_fields (row_id, key, value) VALUES (?1, ?2, ?3)")
+            .map_err(db_err)?;
+        let mut rows = select.query(rusqlite::params_from_iter(tag_params)).map_err(db_err)?;
+        while let Some(row) = rows.next().map_err(db_err)? {
+            let id: i64 = row.get(0).map_err(db_err)?;
+            let msg = row.get_ref(1).map_err(db_err)?.as_str().unwrap_or("");
+            for (key, value) in parse_fields(msg) {
+                insert.execute(params![id, key, value]).map_err(db_err)?;
+                extracted += 1;
+            }
+        }
+    }
+    tx.commit().map_err(db_err)?;
+
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(msg: &str) -> Vec<(String, String)> {
+        parse_fields(msg)
+    }
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(
+            pairs("Force stopping com.foo uid=10123 pkg=com.foo reason=\"user request\"."),
+            vec![pair("uid", "10123"), pair("pkg", "com.foo"), pair("reason", "user request")]
+        );
+        assert_eq!(
+            pairs("ProcessRecord{a1b2 1234:com.foo/u0a12} Reason: timeout, adj=900"),
+            vec![pair("reason", "timeout"), pair("adj", "900")]
+        );
+        // URLs and times are not fields
+        assert!(pairs("fetch http://example.com at 14:22:33").is_empty());
+    }
+
+    #[test]
+    fn test_extract_fields_honours_tags() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE logs (id INTEGER PRIMARY KEY, tag TEXT NOT NULL, msg TEXT NOT NULL);
+             INSERT INTO logs (tag, msg) VALUES
+                ('ActivityManager ', 'Killing pid=12 reason=anr'),
+                ('Other', 'state=idle');",
+        )
+        .unwrap();
+
+        assert_eq!(extract_fields(&conn, &default_field_tags()).unwrap(), 2);
+        let count = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM log_fields WHERE key = 'reason' AND value = 'anr'"), 1);
+
+        // Re-extraction replaces the previous fields
+        assert_eq!(extract_fields(&conn, &[ALL_TAGS.to_string()]).unwrap(), 3);
+        assert_eq!(extract_fields(&conn, &[]).unwrap(), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM log_fields"), 0);
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::Write;
+
+/// Tags used in generated fixtures, roughly weighted by how chatty they are
+const TAGS: &[&str] = &[
+    "ActivityManager", "WindowManager", "chatty", "Choreographer", "wpa_supplicant",
+    "BluetoothAdapter", "CameraService", "AudioFlinger", "NetworkMonitor", "PackageManager",
+    "am_proc_start", "SurfaceFlinger", "InputDispatcher", "PowerManagerService", "dex2oat",
+];
+
+/// Level distribution (mostly info/debug, a few errors)
+const LEVELS: &[u8] = b"VDDDIIIIIIWWEF";
+
+/// Summary of a generated fixture
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixtureSummary {
+    pub bytes: u64,
+    pub log_lines: u64,
+}
+
+/// Write a synthetic dumpstate-like file for ingestion benchmarks
+///
+/// Output is deterministic for a given seed: a dumpstate header, timezone
+/// property, and threadtime lines interleaved with occasional section
+/// headers, until at least `target_bytes` are written.
+pub fn write_logcat_fixture<W: Write>(mut writer: W, target_bytes: u64, seed: u64) -> std::io::Result<FixtureSummary> {
+    let mut rng = seed.max(1);
+    let mut next = move || {
+        // xorshift64
+        rng ^= rng << 13;
+        rng ^= rng >> 7;
+        rng ^= rng << 17;
+        rng
+    };
+
+    let mut summary = FixtureSummary::default();
+    let header = "== dumpstate: 2024-08-24 14:00:00\npersist.sys.timezone=Asia/Taipei\n------ SYSTEM LOG (logcat -v threadtime) ------\n";
+    writer.write_all(header.as_bytes())?;
+    summary.bytes += header.len() as u64;
+
+    let mut ts_ms: u64 = ((8 * 32 + 24) * 24 + 14) * 3_600_000;
+    let mut line = String::with_capacity(256);
+
+    while summary.bytes < target_bytes {
+        let r = next();
+        ts_ms += r % 7;
+
+        let day = ts_ms / 86_400_000 % 28 + 1;
+        let h = ts_ms / 3_600_000 % 24;
+        let m = ts_ms / 60_000 % 60;
+        let s = ts_ms / 1000 % 60;
+        let ms = ts_ms % 1000;
+
+        let pid = 1000 + (r >> 8) % 400;
+        let tid = pid + (r >> 16) % 8;
+        let level = LEVELS[((r >> 24) % LEVELS.len() as u64) as usize] as char;
+        let tag = TAGS[((r >> 32) % TAGS.len() as u64) as usize];
+
+        line.clear();
+        use std::fmt::Write as _;
+        let _ = write!(
+            line,
+            "08-{:02} {:02}:{:02}:{:02}.{:03} {:5} {:5} {} {}: ",
+            day, h, m, s, ms, pid, tid, level, tag
+        );
+        let _ = match (r >> 40) % 5 {
+            0 => writeln!(line, "Skipped {} frames!  The application may be doing too much work on its main thread.", r % 120),
+            1 => writeln!(line, "Start proc {}:com.example.app{}/u0a{} for activity", pid, r % 50, r % 300),
+            2 => writeln!(line, "uid={} pkg=com.example.app{} reason=timeout elapsed={}ms", 10000 + r % 500, r % 50, r % 10_000),
+            3 => writeln!(line, "Connection to 0x{:08x} failed, retry #{}", r as u32, r % 10),
+            _ => writeln!(line, "Scheduling job {} with token {:016x}", r % 1000, r),
+        };
+
+        // Occasional non-logcat lines, as found between dumpstate sections
+        if r % 997 == 0 {
+            line.push_str("------ DUMPSYS (dumpsys) ------\n");
+        }
+
+        writer.write_all(line.as_bytes())?;
+        summary.bytes += line.len() as u64;
+        summary.log_lines += 1;
+    }
+
+    writer.flush()?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::LOGCAT_RE;
+
+    #[test]
+    fn test_fixture_is_deterministic_and_parsable() {
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        let summary = write_logcat_fixture(&mut a, 64 * 1024, 42).unwrap();
+        write_logcat_fixture(&mut b, 64 * 1024, 42).unwrap();
+
+        assert_eq!(a, b);
+        assert!(summary.bytes >= 64 * 1024);
+
+        let text = String::from_utf8(a).unwrap();
+        let parsed = text.lines().filter(|l| LOGCAT_RE.is_match(l)).count() as u64;
+        assert_eq!(parsed, summary.log_lines);
+    }
+}
@@ -1,7 +1,13 @@
 mod sqlite;
 mod builder;
 mod streaming;
+mod fixture;
+pub mod templates;
+pub mod fields;
+pub mod processes;
+pub mod reanchor;
 
-pub use sqlite::LogcatDatabase;
+pub use sqlite::{LogcatDatabase, TRIGRAM_FTS_VERSION};
 pub use builder::{IndexBuilder, IndexSummary};
-pub use streaming::{StreamingIndexBuilder, IndexProgress, IndexPhase};
+pub use streaming::{StreamingIndexBuilder, IndexProgress, IndexPhase, read_prefix};
+pub use fixture::{write_logcat_fixture, FixtureSummary};
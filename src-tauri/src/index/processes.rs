@@ -0,0 +1,477 @@
+use crate::error::{LogcatError, Result};
+use crate::query::parse_process_start;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Start records this close to an instance's start belong to the same launch
+/// (Zygote fork line, `am_proc_start` and `Start proc` of one process)
+const START_MERGE_MS: f64 = 5_000.0;
+
+/// Rows of a pid this soon after its death record still belong to the dying
+/// instance (kill records are logged before the process is gone)
+const DEATH_GRACE_MS: f64 = 2_000.0;
+
+/// `started_by` of instances first seen through their own rows
+const FIRST_ROW: &str = "first_row";
+
+/// `ended_by` of instances whose pid was taken over without a death record
+const PID_REUSED: &str = "pid_reused";
+
+/// Rows read and assigned per transaction
+const TRACK_BATCH: usize = 50_000;
+
+/// Reconstructed process lifetimes; `logs.instance_id` points here
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS process_instances (
+        id INTEGER PRIMARY KEY,
+        pid INTEGER NOT NULL,
+        name TEXT,
+        start_ms REAL NOT NULL,
+        end_ms REAL NOT NULL,
+        started_by TEXT NOT NULL,
+        ended_by TEXT,
+        row_count INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_instances_pid ON process_instances(pid);";
+
+/// `am_proc_died` / `am_kill: [user,pid,process,...]` (events buffer)
+static AM_PROC_END_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[\d+,(\d+),([^,\]]+)").unwrap());
+
+/// `Process com.foo (pid 1234) has died` (ActivityManager)
+static PROCESS_DIED_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Process (\S+) \(pid (\d+)\) has died").unwrap());
+
+/// `Killing 1234:com.foo/u0a12 (adj 900): ...` (ActivityManager)
+static KILLING_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^Killing (\d+):([^/\s]+)").unwrap());
+
+/// `Forked child process 1234` (Zygote)
+static ZYGOTE_FORK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^Forked child process (\d+)").unwrap());
+
+/// `Process 1234 exited due to signal 9 (Killed)` (Zygote)
+static ZYGOTE_EXIT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^Process (\d+) exited").unwrap());
+
+/// One lifetime of a pid, from its start (or first row) to its death (or
+/// the pid's reuse)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessInstance {
+    pub id: i64,
+    pub pid: i32,
+    /// Process name, if a lifecycle record named it
+    pub name: Option<String>,
+    /// Start record or first row (Unix ms)
+    pub start_ms: f64,
+    /// Death record or last row (Unix ms)
+    pub end_ms: f64,
+    /// Record that started the instance (`am_proc_start`, `start_proc`,
+    /// `zygote_fork`, or `first_row`)
+    pub started_by: String,
+    /// Record that ended it (`am_proc_died`, `am_kill`, `process_died`,
+    /// `killing`, `zygote_exit`, `pid_reused`); `None` if alive at log end
+    pub ended_by: Option<String>,
+    pub row_count: usize,
+}
+
+/// Process start or death found in a log message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lifecycle {
+    Start { pid: i32, name: Option<String>, source: &'static str },
+    Death { pid: i32, name: Option<String>, source: &'static str },
+}
+
+/// Parse a lifecycle record from a row's tag and message
+pub fn parse_lifecycle(tag: &str, msg: &str) -> Option<Lifecycle> {
+    let start = |source, (pid, name): (i32, Option<String>)| Lifecycle::Start { pid, name, source };
+    let death = |source, (pid, name): (i32, Option<String>)| Lifecycle::Death { pid, name, source };
+
+    match tag.trim() {
+        "am_proc_start" => parse_process_start(msg).map(|(pid, name)| start("am_proc_start", (pid, Some(name)))),
+        "am_proc_died" => capture(&AM_PROC_END_RE, msg, 1, Some(2)).map(|p| death("am_proc_died", p)),
+        "am_kill" => capture(&AM_PROC_END_RE, msg, 1, Some(2)).map(|p| death("am_kill", p)),
+        "ActivityManager" if msg.starts_with("Start proc ") => {
+            parse_process_start(msg).map(|(pid, name)| start("start_proc", (pid, Some(name))))
+        }
+        "ActivityManager" => capture(&PROCESS_DIED_RE, msg, 2, Some(1))
+            .map(|p| death("process_died", p))
+            .or_else(|| capture(&KILLING_RE, msg, 1, Some(2)).map(|p| death("killing", p))),
+        "Zygote" => capture(&ZYGOTE_FORK_RE, msg, 1, None)
+            .map(|p| start("zygote_fork", p))
+            .or_else(|| capture(&ZYGOTE_EXIT_RE, msg, 1, None).map(|p| death("zygote_exit", p))),
+        _ => None,
+    }
+}
+
+/// Pid and optional name groups of a lifecycle regex
+fn capture(re: &Regex, msg: &str, pid_group: usize, name_group: Option<usize>) -> Option<(i32, Option<String>)> {
+    let caps = re.captures(msg)?;
+    let pid = caps[pid_group].parse().ok()?;
+    let name = name_group.and_then(|g| caps.get(g)).map(|m| m.as_str().trim().to_string());
+    Some((pid, name))
+}
+
+/// Instance being reconstructed
+struct Instance {
+    pid: i32,
+    name: Option<String>,
+    start_ms: f64,
+    end_ms: f64,
+    started_by: &'static str,
+    ended_by: Option<&'static str>,
+    /// Time of the death record, for the grace period
+    died_ms: Option<f64>,
+    row_count: usize,
+}
+
+/// Splits each pid's rows into instances, fed rows in time order
+#[derive(Default)]
+pub struct InstanceTracker {
+    instances: Vec<Instance>,
+    current: HashMap<i32, usize>,
+}
+
+impl InstanceTracker {
+    /// Apply a lifecycle record logged at `ts_unix`
+    pub fn record(&mut self, event: Lifecycle, ts_unix: f64) {
+        match event {
+            Lifecycle::Start { pid, name, source } => self.start(pid, name, source, ts_unix),
+            Lifecycle::Death { pid, name, source } => self.death(pid, name, source, ts_unix),
+        }
+    }
+
+    /// Assign a row of `pid` to its instance and return the instance index
+    pub fn row(&mut self, pid: i32, ts_unix: f64) -> usize {
+        let alive = self.current.get(&pid).map(|&index| &self.instances[index]).is_some_and(|inst| {
+            inst.died_ms.map_or(inst.ended_by.is_none(), |died| ts_unix - died <= DEATH_GRACE_MS)
+        });
+        if !alive {
+            self.open(pid, None, FIRST_ROW, ts_unix);
+        }
+
+        let index = self.current[&pid];
+        let inst = &mut self.instances[index];
+        inst.row_count += 1;
+        inst.end_ms = inst.end_ms.max(ts_unix);
+        index
+    }
+
+    fn start(&mut self, pid: i32, name: Option<String>, source: &'static str, ts_unix: f64) {
+        if let Some(&index) = self.current.get(&pid) {
+            let inst = &mut self.instances[index];
+            let same_name = match (&inst.name, &name) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            };
+            if inst.ended_by.is_none() && same_name && ts_unix - inst.start_ms <= START_MERGE_MS {
+                // Another record of the same launch
+                if inst.name.is_none() {
+                    inst.name = name;
+                }
+                if inst.started_by == FIRST_ROW {
+                    inst.started_by = source;
+                }
+                return;
+            }
+            // A new start (or a new name) means the pid was reused
+            if inst.ended_by.is_none() {
+                inst.ended_by = Some(PID_REUSED);
+            }
+        }
+        self.open(pid, name, source, ts_unix);
+    }
+
+    fn death(&mut self, pid: i32, name: Option<String>, source: &'static str, ts_unix: f64) {
+        // Later records of the same death (am_kill, am_proc_died, Zygote) are ignored
+        let Some(&index) = self.current.get(&pid) else { return };
+        let inst = &mut self.instances[index];
+        if inst.ended_by.is_some() {
+            return;
+        }
+        inst.ended_by = Some(source);
+        inst.died_ms = Some(ts_unix);
+        inst.end_ms = inst.end_ms.max(ts_unix);
+        if inst.name.is_none() {
+            inst.name = name;
+        }
+    }
+
+    fn open(&mut self, pid: i32, name: Option<String>, source: &'static str, ts_unix: f64) {
+        self.current.insert(pid, self.instances.len());
+        self.instances.push(Instance {
+            pid,
+            name,
+            start_ms: ts_unix,
+            end_ms: ts_unix,
+            started_by: source,
+            ended_by: None,
+            died_ms: None,
+            row_count: 0,
+        });
+    }
+
+    /// Reconstructed instances; ids are indices plus one
+    pub fn into_instances(self) -> Vec<ProcessInstance> {
+        self.instances
+            .into_iter()
+            .enumerate()
+            .map(|(index, inst)| ProcessInstance {
+                id: index as i64 + 1,
+                pid: inst.pid,
+                name: inst.name,
+                start_ms: inst.start_ms,
+                end_ms: inst.end_ms,
+                started_by: inst.started_by.to_string(),
+                ended_by: inst.ended_by.map(str::to_string),
+                row_count: inst.row_count,
+            })
+            .collect()
+    }
+}
+
+/// Reconstruct process instances and assign every row to one
+///
+/// Rows are walked in `(ts_unix, id)` order in batches, each assigned in its
+/// own transaction. The `process_instances` table is only created in the
+/// final transaction, so its presence marks a completed pass. Returns the
+/// number of instances.
+pub fn track_processes(conn: &Connection) -> Result<usize> {
+    let db_err = |e: rusqlite::Error| LogcatError::Database(e.to_string());
+
+    ensure_instance_column(conn)?;
+
+    let mut tracker = InstanceTracker::default();
+    let mut cursor = (f64::NEG_INFINITY, i64::MIN);
+    loop {
+        let batch: Vec<(i64, f64, i32, String, String)> = {
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT id, ts_unix, pid, tag, msg FROM logs
+                     WHERE (ts_unix, id) > (?1, ?2) ORDER BY ts_unix, id LIMIT ?3",
+                )
+                .map_err(db_err)?;
+            let rows = stmt
+                .query_map(params![cursor.0, cursor.1, TRACK_BATCH as i64], |r| {
+                    Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+                })
+                .map_err(db_err)?;
+            rows.collect::<rusqlite::Result<_>>().map_err(db_err)?
+        };
+        let last = match batch.last() {
+            Some((id, ts_unix, _, _, _)) => (*ts_unix, *id),
+            None => break,
+        };
+
+        let tx = conn.unchecked_transaction().map_err(db_err)?;
+        {
+            let mut update = tx.prepare_cached("UPDATE logs SET instance_id = ?1 WHERE id = ?2").map_err(db_err)?;
+            for (id, ts_unix, pid, tag, msg) in &batch {
+                // The record usually concerns another pid than the row's own
+                if let Some(event) = parse_lifecycle(tag, msg) {
+                    tracker.record(event, *ts_unix);
+                }
+                let index = tracker.row(*pid, *ts_unix);
+                update.execute(params![index as i64 + 1, id]).map_err(db_err)?;
+            }
+        }
+        tx.commit().map_err(db_err)?;
+        cursor = last;
+    }
+    let instances = tracker.into_instances();
+
+    let tx = conn.unchecked_transaction().map_err(db_err)?;
+    tx.execute_batch(SCHEMA).map_err(db_err)?;
+    tx.execute("DELETE FROM process_instances", []).map_err(db_err)?;
+    {
+        let mut insert = tx
+            .prepare(
+                "INSERT INTO process_instances (id, pid, name, start_ms, end_ms, started_by, ended_by, row_count)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )
+            .map_err(db_err)?;
+        for inst in &instances {
+            insert
+                .execute(params![
+                    inst.id,
+                    inst.pid,
+                    inst.name,
+                    inst.start_ms,
+                    inst.end_ms,
+                    inst.started_by,
+                    inst.ended_by,
+                    inst.row_count as i64
+                ])
+                .map_err(db_err)?;
+        }
+    }
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS idx_instance ON logs(instance_id);")
+        .map_err(db_err)?;
+    tx.commit().map_err(db_err)?;
+
+    Ok(instances.len())
+}
+
+/// Add `logs.instance_id` to caches that predate it
+///
+/// Row queries select the column, so this runs on open; the instances
+/// themselves are backfilled later by `track_processes`.
+pub fn ensure_instance_column(conn: &Connection) -> Result<()> {
+    if !has_instance_column(conn)? {
+        conn.execute_batch("ALTER TABLE logs ADD COLUMN instance_id INTEGER;")
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Whether the report's process instances have been tracked
+pub fn has_instances(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'process_instances'",
+        [],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+    .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+/// Instances in start order, optionally only those of `pid`
+///
+/// Fails with `IndexPending` while an older cache is still being backfilled.
+pub fn load_instances(conn: &Connection, pid: Option<i32>) -> Result<Vec<ProcessInstance>> {
+    if !has_instances(conn)? {
+        return Err(LogcatError::IndexPending("process instances".to_string()));
+    }
+    let (condition, params) = match pid {
+        Some(pid) => ("WHERE pid = ?", vec![Value::Integer(pid as i64)]),
+        None => ("", Vec::new()),
+    };
+    let sql = format!(
+        "SELECT id, pid, name, start_ms, end_ms, started_by, ended_by, row_count
+         FROM process_instances {} ORDER BY start_ms, id",
+        condition
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| LogcatError::Database(e.to_string()))?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params), |r| {
+            Ok(ProcessInstance {
+                id: r.get(0)?,
+                pid: r.get(1)?,
+                name: r.get(2)?,
+                start_ms: r.get(3)?,
+                end_ms: r.get(4)?,
+                started_by: r.get(5)?,
+                ended_by: r.get(6)?,
+                row_count: r.get(7)?,
+            })
+        })
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+    rows.collect::<rusqlite::Result<_>>()
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+fn has_instance_column(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('logs') WHERE name = 'instance_id'",
+        [],
+        |r| r.get::<_, i64>(0),
+    )
+    .map(|n| n > 0)
+    .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lifecycle() {
+        assert_eq!(
+            parse_lifecycle("am_kill", "[0,4567,com.foo,900,empty #17]"),
+            Some(Lifecycle::Death { pid: 4567, name: Some("com.foo".to_string()), source: "am_kill" })
+        );
+        assert_eq!(
+            parse_lifecycle("ActivityManager", "Process com.foo (pid 4567) has died: cch CEM"),
+            Some(Lifecycle::Death { pid: 4567, name: Some("com.foo".to_string()), source: "process_died" })
+        );
+        assert_eq!(
+            parse_lifecycle("Zygote  ", "Forked child process 4567"),
+            Some(Lifecycle::Start { pid: 4567, name: None, source: "zygote_fork" })
+        );
+        assert_eq!(
+            parse_lifecycle("Zygote", "Process 4567 exited due to signal 9 (Killed)"),
+            Some(Lifecycle::Death { pid: 4567, name: None, source: "zygote_exit" })
+        );
+        assert_eq!(parse_lifecycle("MyApp", "Killing 4567:com.foo/u0a1"), None);
+    }
+
+    #[test]
+    fn test_tracker_splits_reused_pid() {
+        let mut tracker = InstanceTracker::default();
+        let start = |name: &str, source| Lifecycle::Start { pid: 10, name: Some(name.to_string()), source };
+
+        // First launch: fork line, app row, am_proc_start of the same launch
+        tracker.record(Lifecycle::Start { pid: 10, name: None, source: "zygote_fork" }, 0.0);
+        let a = tracker.row(10, 1.0);
+        tracker.record(start("com.a", "am_proc_start"), 2.0);
+        assert_eq!(tracker.row(10, 100.0), a);
+
+        // Killed; a trailing row is still the dying instance
+        tracker.record(Lifecycle::Death { pid: 10, name: None, source: "am_kill" }, 200.0);
+        tracker.record(Lifecycle::Death { pid: 10, name: None, source: "am_proc_died" }, 210.0);
+        assert_eq!(tracker.row(10, 500.0), a);
+
+        // Rows long after the death belong to a new, unnamed instance
+        let b = tracker.row(10, 60_000.0);
+        assert_ne!(a, b);
+
+        // A start under another name without a death record: pid reuse
+        tracker.record(start("com.c", "start_proc"), 70_000.0);
+        let c = tracker.row(10, 70_001.0);
+        assert_ne!(b, c);
+
+        let instances = tracker.into_instances();
+        assert_eq!(instances.len(), 3);
+        assert_eq!(instances[0].name.as_deref(), Some("com.a"));
+        assert_eq!(instances[0].started_by, "zygote_fork");
+        assert_eq!(instances[0].ended_by.as_deref(), Some("am_kill"));
+        assert_eq!(instances[0].row_count, 3);
+        assert_eq!(instances[1].started_by, "first_row");
+        assert_eq!(instances[1].ended_by.as_deref(), Some("pid_reused"));
+        assert_eq!((instances[2].name.as_deref(), instances[2].ended_by.as_deref()), (Some("com.c"), None));
+    }
+
+    #[test]
+    fn test_track_processes_assigns_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE logs (id INTEGER PRIMARY KEY, ts_unix REAL NOT NULL, pid INTEGER NOT NULL,
+                                tag TEXT NOT NULL, msg TEXT NOT NULL);
+             INSERT INTO logs (ts_unix, pid, tag, msg) VALUES
+                (0, 1000, 'am_proc_start', '[0,42,10001,com.foo,activity,{com.foo/.Main}]'),
+                (1, 42, 'Foo', 'hello'),
+                (2, 1000, 'am_proc_died', '[0,42,com.foo,900,17]'),
+                (90000, 42, 'Bar', 'other process');",
+        )
+        .unwrap();
+
+        assert!(matches!(load_instances(&conn, None), Err(LogcatError::IndexPending(_))));
+        track_processes(&conn).unwrap();
+        let ids: Vec<i64> = conn
+            .prepare("SELECT instance_id FROM logs WHERE pid = 42 ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_ne!(ids[0], ids[1]);
+
+        let instances = load_instances(&conn, Some(42)).unwrap();
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].name.as_deref(), Some("com.foo"));
+        assert_eq!((instances[0].start_ms, instances[0].end_ms), (0.0, 2.0));
+        assert_eq!(instances[1].row_count, 1);
+    }
+}
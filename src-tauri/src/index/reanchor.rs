@@ -0,0 +1,238 @@
+use crate::error::{LogcatError, Result};
+use crate::index::processes::track_processes;
+use crate::time::{iso_ts_key_ms, to_iso_safe, AnchorInfo, TimeAnchor};
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// Small key/value settings stored with the report
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS report_meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );";
+
+/// `report_meta` key of the applied `TimeOverride` (JSON)
+const TIME_OVERRIDE_KEY: &str = "time_override";
+
+/// Rows re-anchored per transaction
+const REANCHOR_BATCH: usize = 50_000;
+
+/// Timezone and report date chosen by the user instead of the derived anchor
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeOverride {
+    /// IANA timezone of the device clock (e.g. `Asia/Taipei`)
+    pub timezone: String,
+    /// Day the report was taken, used to infer each row's year; `None`
+    /// keeps the dates the rows currently have
+    pub report_date: Option<NaiveDate>,
+    /// When the override was applied (Unix ms)
+    #[serde(default)]
+    pub applied_ms: i64,
+}
+
+/// Result of re-anchoring a report's timestamps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReanchorSummary {
+    pub rows: usize,
+    /// Rows whose timestamp could not be converted (`ts_unix` set to 0)
+    pub failed_rows: usize,
+    pub min_timestamp_ms: Option<u64>,
+    pub max_timestamp_ms: Option<u64>,
+    /// Anchor the timestamps now follow
+    pub anchor: AnchorInfo,
+}
+
+/// Recompute `ts_iso` / `ts_unix` of every row from its device timestamp
+///
+/// Works on the cache alone; the source file is not read again. Rows are
+/// rewritten in id batches, each in its own transaction, so `conn` should be
+/// a dedicated connection rather than one queries wait on. Process instances
+/// are rebuilt since they follow time order, and the override is recorded in
+/// `report_meta` once every row is done.
+pub fn apply_time_override(conn: &Connection, time: &TimeOverride) -> Result<ReanchorSummary> {
+    let db_err = |e: rusqlite::Error| LogcatError::Database(e.to_string());
+
+    let tz: Tz = time.timezone.parse().map_err(|_| LogcatError::TimeConversion {
+        input: time.timezone.clone(),
+        reason: "unknown timezone".to_string(),
+    })?;
+    let report_date = match time.report_date {
+        Some(date) => date,
+        None => current_reference_date(conn, tz)?,
+    };
+    let anchor = TimeAnchor::user_override(tz, report_date);
+
+    let mut summary = ReanchorSummary {
+        rows: 0,
+        failed_rows: 0,
+        min_timestamp_ms: None,
+        max_timestamp_ms: None,
+        anchor: anchor.info(),
+    };
+
+    let mut last_id = i64::MIN;
+    loop {
+        let batch: Vec<(i64, String)> = {
+            let mut stmt = conn
+                .prepare_cached("SELECT id, ts_display FROM logs WHERE id > ?1 ORDER BY id LIMIT ?2")
+                .map_err(db_err)?;
+            let rows = stmt
+                .query_map(params![last_id, REANCHOR_BATCH as i64], |r| Ok((r.get(0)?, r.get(1)?)))
+                .map_err(db_err)?;
+            rows.collect::<rusqlite::Result<_>>().map_err(db_err)?
+        };
+        let last = match batch.last() {
+            Some((id, _)) => *id,
+            None => break,
+        };
+
+        let tx = conn.unchecked_transaction().map_err(db_err)?;
+        {
+            let mut update = tx
+                .prepare_cached("UPDATE logs SET ts_iso = ?1, ts_unix = ?2 WHERE id = ?3")
+                .map_err(db_err)?;
+            for (id, ts) in &batch {
+                // Same conversion as the index builders
+                let ts_iso = to_iso_safe(ts, &anchor).ok();
+                let ts_unix_ms = ts_iso
+                    .as_ref()
+                    .and_then(|iso| iso_ts_key_ms(iso).ok())
+                    .unwrap_or(0);
+
+                if ts_unix_ms > 0 {
+                    summary.min_timestamp_ms = Some(summary.min_timestamp_ms.map_or(ts_unix_ms, |m| m.min(ts_unix_ms)));
+                    summary.max_timestamp_ms = Some(summary.max_timestamp_ms.map_or(ts_unix_ms, |m| m.max(ts_unix_ms)));
+                } else {
+                    summary.failed_rows += 1;
+                }
+                update.execute(params![ts_iso, ts_unix_ms as f64, id]).map_err(db_err)?;
+            }
+        }
+        tx.commit().map_err(db_err)?;
+        summary.rows += batch.len();
+        last_id = last;
+    }
+
+    let tx = conn.unchecked_transaction().map_err(db_err)?;
+    tx.execute_batch(SCHEMA).map_err(db_err)?;
+    let recorded = TimeOverride {
+        report_date: Some(report_date),
+        applied_ms: crate::cache::now_ms(),
+        ..time.clone()
+    };
+    tx.execute(
+        "INSERT OR REPLACE INTO report_meta (key, value) VALUES (?1, ?2)",
+        params![TIME_OVERRIDE_KEY, serde_json::to_string(&recorded)?],
+    )
+    .map_err(db_err)?;
+    tx.commit().map_err(db_err)?;
+
+    track_processes(conn)?;
+    Ok(summary)
+}
+
+/// The override applied to this report, if any
+pub fn load_time_override(conn: &Connection) -> Result<Option<TimeOverride>> {
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+    let json: Option<String> = conn
+        .query_row("SELECT value FROM report_meta WHERE key = ?1", [TIME_OVERRIDE_KEY], |r| r.get(0))
+        .optional()
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+    Ok(json.map(|j| serde_json::from_str(&j)).transpose()?)
+}
+
+/// Date in `tz` of the latest row as currently anchored (today for an empty log)
+fn current_reference_date(conn: &Connection, tz: Tz) -> Result<NaiveDate> {
+    let latest: Option<f64> = conn
+        .query_row("SELECT MAX(ts_unix) FROM logs WHERE ts_unix > 0", [], |r| r.get(0))
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+    Ok(latest
+        .and_then(|ms| DateTime::from_timestamp_millis(ms as i64))
+        .map(|dt| dt.with_timezone(&tz).date_naive())
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).date_naive()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(rows: &[&str]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE logs (id INTEGER PRIMARY KEY, ts_unix REAL NOT NULL, ts_display TEXT NOT NULL,
+                                ts_iso TEXT, pid INTEGER NOT NULL, tag TEXT NOT NULL, msg TEXT NOT NULL);",
+        )
+        .unwrap();
+        for ts in rows {
+            conn.execute(
+                "INSERT INTO logs (ts_unix, ts_display, pid, tag, msg) VALUES (0, ?1, 1, 'T', 'm')",
+                [ts],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn test_apply_time_override() {
+        let conn = logs(&["12-31 23:00:00.000", "01-01 01:00:00.000"]);
+        let time = TimeOverride {
+            timezone: "Asia/Taipei".to_string(),
+            report_date: NaiveDate::from_ymd_opt(2024, 1, 2),
+            applied_ms: 0,
+        };
+
+        let summary = apply_time_override(&conn, &time).unwrap();
+        assert_eq!((summary.rows, summary.failed_rows), (2, 0));
+
+        // Taipei is UTC+8, and December rows belong to the previous year
+        let iso: Vec<String> = conn
+            .prepare("SELECT ts_iso FROM logs ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(iso, vec!["2023-12-31T15:00:00+00:00", "2023-12-31T17:00:00+00:00"]);
+        assert_eq!(summary.max_timestamp_ms, Some(1704042000000));
+
+        let recorded = load_time_override(&conn).unwrap().unwrap();
+        assert_eq!(recorded.timezone, "Asia/Taipei");
+        assert!(recorded.applied_ms > 0);
+
+        // Timezone-only override keeps the dates
+        let utc = TimeOverride { timezone: "UTC".to_string(), report_date: None, applied_ms: 0 };
+        apply_time_override(&conn, &utc).unwrap();
+        let first: String = conn.query_row("SELECT ts_iso FROM logs WHERE id = 1", [], |r| r.get(0)).unwrap();
+        assert_eq!(first, "2023-12-31T23:00:00+00:00");
+    }
+
+    #[test]
+    fn test_reference_date_follows_timezone() {
+        let conn = logs(&["12-31 20:00:00.000"]);
+        let utc = TimeOverride {
+            timezone: "UTC".to_string(),
+            report_date: NaiveDate::from_ymd_opt(2023, 12, 31),
+            applied_ms: 0,
+        };
+        apply_time_override(&conn, &utc).unwrap();
+
+        // 20:00 UTC is already the next day in Taipei
+        let taipei: Tz = "Asia/Taipei".parse().unwrap();
+        assert_eq!(current_reference_date(&conn, Tz::UTC).unwrap(), NaiveDate::from_ymd_opt(2023, 12, 31).unwrap());
+        assert_eq!(current_reference_date(&conn, taipei).unwrap(), NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+    }
+
+    #[test]
+    fn test_unknown_timezone_is_rejected() {
+        let conn = logs(&["08-24 14:22:33.123"]);
+        let time = TimeOverride { timezone: "Mars/Olympus".to_string(), report_date: None, applied_ms: 0 };
+        assert!(apply_time_override(&conn, &time).is_err());
+        assert_eq!(load_time_override(&conn).unwrap(), None);
+    }
+}
@@ -1,8 +1,22 @@
 use crate::error::{LogcatError, Result};
 use crate::types::LogRow;
+use crate::index::templates::template_id;
 use rusqlite::{Connection, params};
 use std::path::Path;
 
+/// Schema version stored in `PRAGMA user_version`
+///
+/// - 0: original schema (unicode61 FTS tokenizer)
+/// - 1: trigram FTS tokenizer (substring search via MATCH)
+/// - 2: `idx_tid` index
+/// - 3: `template_id` column and `templates` table (see `templates`)
+/// - 4: `log_fields` table of extracted `key=value` pairs (see `fields`)
+/// - 5: `instance_id` column and `process_instances` table (see `processes`)
+pub const SCHEMA_VERSION: i32 = 5;
+
+/// First schema version with the trigram FTS tokenizer
+pub const TRIGRAM_FTS_VERSION: i32 = 1;
+
 /// SQLite-based logcat database
 pub struct LogcatDatabase {
     conn: Connection,
@@ -36,18 +50,23 @@ impl LogcatDatabase {
                 tag TEXT NOT NULL,
                 pid INTEGER NOT NULL,
                 tid INTEGER NOT NULL,
-                msg TEXT NOT NULL
+                msg TEXT NOT NULL,
+                template_id INTEGER,
+                instance_id INTEGER
             );
 
             CREATE INDEX idx_ts ON logs(ts_unix);
             CREATE INDEX idx_level ON logs(level);
             CREATE INDEX idx_tag ON logs(tag);
             CREATE INDEX idx_pid ON logs(pid);
+            CREATE INDEX idx_tid ON logs(tid);
 
+            -- Trigram tokenizer enables substring search through MATCH
             CREATE VIRTUAL TABLE logs_fts USING fts5(
                 msg,
                 content=logs,
-                content_rowid=id
+                content_rowid=id,
+                tokenize='trigram'
             );
 
             CREATE TRIGGER logs_ai AFTER INSERT ON logs BEGIN
@@ -57,6 +76,9 @@ impl LogcatDatabase {
         )
         .map_err(|e| LogcatError::Database(e.to_string()))?;
 
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
         Ok(Self { conn })
     }
 
@@ -85,22 +107,30 @@ impl LogcatDatabase {
 
     /// Insert a single log row
     pub fn insert(&self, row: &LogRow, ts_unix_ms: f64) -> Result<()> {
-        self.conn
-            .execute(
-                "INSERT INTO logs (ts_unix, ts_display, ts_iso, level, tag, pid, tid, msg) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-                params![
-                    ts_unix_ms,
-                    row.ts,
-                    row.ts_iso,
-                    row.level,
-                    row.tag,
-                    row.pid,
-                    row.tid,
-                    row.msg,
-                ],
+        self.insert_row(row, ts_unix_ms, None)
+    }
+
+    fn insert_row(&self, row: &LogRow, ts_unix_ms: f64, template_id: Option<i64>) -> Result<()> {
+        let mut stmt = self.conn
+            .prepare_cached(
+                "INSERT INTO logs (ts_unix, ts_display, ts_iso, level, tag, pid, tid, msg, template_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             )
             .map_err(|e| LogcatError::Database(e.to_string()))?;
 
+        stmt
+            .execute(params![
+                ts_unix_ms,
+                row.ts,
+                row.ts_iso,
+                row.level,
+                row.tag,
+                row.pid,
+                row.tid,
+                row.msg,
+                template_id,
+            ])
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
         Ok(())
     }
 
@@ -161,6 +191,11 @@ impl<'a> BatchInserter<'a> {
         self.db.insert(row, ts_unix_ms)
     }
 
+    /// Insert a row with its template index (see `templates::TemplateMiner`)
+    pub fn insert_with_template(&self, row: &LogRow, ts_unix_ms: f64, template: usize) -> Result<()> {
+        self.db.insert_row(row, ts_unix_ms, Some(template_id(template)))
+    }
+
     /// Commit the batch
     pub fn commit(mut self) -> Result<()> {
         self.db.commit()?;
@@ -197,6 +232,7 @@ mod tests {
         let db = LogcatDatabase::create(&path).unwrap();
 
         let row = LogRow {
+            id: None,
             ts: "08-24 14:22:33.123".to_string(),
             ts_iso: Some("2024-08-24T06:22:33.123+00:00".to_string()),
             level: "E".to_string(),
@@ -204,6 +240,9 @@ mod tests {
             pid: 1234,
             tid: 5678,
             msg: "ANR in com.example".to_string(),
+            matches: None,
+            repeat: None,
+            instance_id: None,
         };
 
         db.insert(&row, 1724487753123.0).unwrap();
@@ -223,6 +262,7 @@ mod tests {
             let batch = db.begin_batch().unwrap();
             for i in 0..100 {
                 let row = LogRow {
+                    id: None,
                     ts: format!("08-24 14:22:{:02}.000", i % 60),
                     ts_iso: None,
                     level: "I".to_string(),
@@ -230,6 +270,9 @@ mod tests {
                     pid: 1000,
                     tid: 1000,
                     msg: format!("Message {}", i),
+                    matches: None,
+                    repeat: None,
+                    instance_id: None,
                 };
                 batch.insert(&row, 1724487753000.0 + i as f64 * 1000.0).unwrap();
             }
@@ -248,6 +291,7 @@ mod tests {
         let db = LogcatDatabase::create(&path).unwrap();
 
         let row = LogRow {
+            id: None,
             ts: "08-24 14:22:33.123".to_string(),
             ts_iso: None,
             level: "E".to_string(),
@@ -255,6 +299,9 @@ mod tests {
             pid: 1000,
             tid: 1000,
             msg: "Hello world from Android".to_string(),
+            matches: None,
+            repeat: None,
+            instance_id: None,
         };
         db.insert(&row, 1724487753123.0).unwrap();
 
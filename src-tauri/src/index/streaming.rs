@@ -1,18 +1,25 @@
 use crate::error::{LogcatError, Result};
-use crate::index::sqlite::LogcatDatabase;
+use crate::index::sqlite::{LogcatDatabase, SCHEMA_VERSION};
+use crate::index::fields::{default_field_tags, extract_fields};
+use crate::index::processes::track_processes;
+use crate::index::templates::{mask_message, store_templates, template_id, TemplateMiner};
 use crate::parser::LOGCAT_RE;
 use crate::time::{TimeAnchor, derive_time_anchor, to_iso_safe, iso_ts_key_ms};
 use crate::types::LogRow;
-use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use rayon::prelude::*;
+use std::io::Read;
 use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
 
 /// Batch size for commits (rows per transaction)
 const BATCH_COMMIT_SIZE: usize = 50_000;
 
-/// Size of buffer for reading file
-const READ_BUFFER_SIZE: usize = 64 * 1024; // 64KB
+/// Size of each newline-aligned chunk handed to a parser thread
+const CHUNK_SIZE: usize = 4 * 1024 * 1024; // 4MB
+
+/// Parsed waves buffered between the parsers and the writer
+const WAVE_QUEUE_DEPTH: usize = 2;
 
 /// Size to sample for time anchor detection
 const ANCHOR_SAMPLE_SIZE: usize = 256 * 1024; // 256KB
@@ -32,6 +39,9 @@ pub struct IndexProgress {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IndexPhase {
     Parsing,
+    MiningTemplates,
+    ExtractingFields,
+    TrackingProcesses,
     BuildingFts,
     Optimizing,
     Complete,
@@ -47,12 +57,34 @@ pub struct IndexSummary {
     pub max_timestamp_ms: Option<u64>,
 }
 
+impl IndexSummary {
+    /// Account for one inserted row
+    fn record(&mut self, level: &str, ts_unix_ms: f64) {
+        self.total_rows += 1;
+
+        match level {
+            "E" => self.error_count += 1,
+            "F" => self.fatal_count += 1,
+            _ => {}
+        }
+
+        if ts_unix_ms > 0.0 {
+            let ms = ts_unix_ms as u64;
+            self.min_timestamp_ms = Some(self.min_timestamp_ms.map_or(ms, |m| m.min(ms)));
+            self.max_timestamp_ms = Some(self.max_timestamp_ms.map_or(ms, |m| m.max(ms)));
+        }
+    }
+}
+
 /// Streaming index builder for large files
 pub struct StreamingIndexBuilder {
     db_path: std::path::PathBuf,
     anchor: Option<TimeAnchor>,
     progress_callback: Option<ProgressCallback>,
     cancel_flag: Arc<AtomicBool>,
+    chunk_size: usize,
+    input_counter: Option<Arc<AtomicU64>>,
+    field_tags: Vec<String>,
 }
 
 impl StreamingIndexBuilder {
@@ -63,6 +95,9 @@ impl StreamingIndexBuilder {
             anchor: None,
             progress_callback: None,
             cancel_flag: Arc::new(AtomicBool::new(false)),
+            chunk_size: CHUNK_SIZE,
+            input_counter: None,
+            field_tags: default_field_tags(),
         }
     }
 
@@ -72,6 +107,12 @@ impl StreamingIndexBuilder {
         self
     }
 
+    /// Tags whose messages are scanned for `key=value` fields (`*` for all)
+    pub fn with_field_tags(mut self, tags: Vec<String>) -> Self {
+        self.field_tags = tags;
+        self
+    }
+
     /// Set progress callback
     pub fn with_progress<F>(mut self, callback: F) -> Self
     where
@@ -81,11 +122,30 @@ impl StreamingIndexBuilder {
         self
     }
 
+    /// Use an externally owned cancel flag (e.g. from a job manager)
+    pub fn with_cancel_flag(mut self, cancel_flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = cancel_flag;
+        self
+    }
+
     /// Get cancel flag for external cancellation
     pub fn cancel_flag(&self) -> Arc<AtomicBool> {
         Arc::clone(&self.cancel_flag)
     }
 
+    fn check_cancelled(&self) -> Result<()> {
+        if self.cancel_flag.load(Ordering::Relaxed) {
+            return Err(LogcatError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Override the parse chunk size (mainly for tests and benchmarks)
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
     /// Build index from a file path using streaming
     pub fn build_from_file(self, file_path: &Path) -> Result<IndexSummary> {
         let file = std::fs::File::open(file_path)
@@ -98,118 +158,100 @@ impl StreamingIndexBuilder {
         self.build_from_reader(file, total_bytes)
     }
 
+    /// Report progress from an external byte counter instead of the bytes
+    /// consumed by the parser (e.g. compressed bytes read from a zip entry)
+    pub fn with_input_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+        self.input_counter = Some(counter);
+        self
+    }
+
     /// Build index from any reader
-    pub fn build_from_reader<R: Read + Seek>(mut self, mut reader: R, total_bytes: u64) -> Result<IndexSummary> {
+    ///
+    /// The reader does not need to be seekable: the time anchor is derived
+    /// from a buffered prefix that is then replayed ahead of the stream.
+    pub fn build_from_reader<R: Read>(mut self, mut reader: R, total_bytes: u64) -> Result<IndexSummary> {
         // Step 1: Sample beginning for time anchor
-        let anchor = match self.anchor.take() {
-            Some(a) => a,
+        let (anchor, prefix) = match self.anchor.take() {
+            Some(a) => (a, Vec::new()),
             None => {
-                let mut sample = vec![0u8; ANCHOR_SAMPLE_SIZE.min(total_bytes as usize)];
-                let bytes_read = reader.read(&mut sample)
-                    .map_err(|e| LogcatError::Io(e))?;
-                sample.truncate(bytes_read);
-                reader.seek(SeekFrom::Start(0))
-                    .map_err(|e| LogcatError::Io(e))?;
-
-                let sample_str = String::from_utf8_lossy(&sample);
-                derive_time_anchor(&sample_str)
+                let sample = read_prefix(&mut reader, ANCHOR_SAMPLE_SIZE)?;
+                let anchor = derive_time_anchor(&String::from_utf8_lossy(&sample));
+                (anchor, sample)
             }
         };
+        let reader = std::io::Cursor::new(prefix).chain(reader);
 
         // Step 2: Create database (disable FTS trigger for bulk loading)
-        let mut db = self.create_db_without_fts_trigger()?;
-
-        let mut summary = IndexSummary::default();
-        let mut bytes_read: u64 = 0;
-        let mut batch_count = 0;
-
-        // Step 3: Stream parse with batched commits
-        let buf_reader = BufReader::with_capacity(READ_BUFFER_SIZE, reader);
-
-        db.begin_transaction()?;
-
-        for line_result in buf_reader.lines() {
-            // Check cancellation
-            if self.cancel_flag.load(Ordering::Relaxed) {
-                db.rollback()?;
-                return Err(LogcatError::InvalidFilter("Index building cancelled".to_string()));
-            }
-
-            let line = match line_result {
-                Ok(l) => l,
-                Err(_) => continue, // Skip invalid UTF-8 lines
-            };
+        let db = self.create_db_without_fts_trigger()?;
+
+        // Step 3: Pipeline - read newline-aligned chunks, parse them in
+        // parallel, and feed an ordered single writer on its own thread
+        let (tx, rx) = mpsc::sync_channel::<Vec<ParsedChunk>>(WAVE_QUEUE_DEPTH);
+        let wave_size = rayon::current_num_threads().max(1);
+
+        let builder = &self;
+        let (read_result, writer_result) = std::thread::scope(|scope| {
+            // The writer owns the connection and hands it back when done
+            let writer = scope.spawn(move || {
+                let result = builder.write_waves(&db, rx, total_bytes);
+                (db, result)
+            });
 
-            bytes_read += line.len() as u64 + 1; // +1 for newline
+            let read_result = builder.read_waves(reader, &anchor, wave_size, tx);
+            (read_result, writer.join())
+        });
 
-            // Try to parse as logcat line
-            if let Some(caps) = LOGCAT_RE.captures(&line) {
-                let ts = format!("{} {}", &caps["date"], &caps["time"]);
-                let level = caps["level"].to_string();
+        let (db, write_result) = writer_result
+            .map_err(|_| LogcatError::Database("index writer panicked".to_string()))?;
 
-                let ts_iso = to_iso_safe(&ts, &anchor).ok();
-                let ts_unix_ms = ts_iso
-                    .as_ref()
-                    .and_then(|iso| iso_ts_key_ms(iso).ok())
-                    .unwrap_or(0) as f64;
+        // A failed writer closes the channel, so report its error first
+        let (summary, miner) = write_result?;
+        read_result?;
 
-                let row = LogRow {
-                    ts: ts.clone(),
-                    ts_iso,
-                    level: level.clone(),
-                    tag: caps["tag"].to_string(),
-                    pid: caps["pid"].parse().unwrap_or_default(),
-                    tid: caps["tid"].parse().unwrap_or_default(),
-                    msg: caps["msg"].to_string(),
-                };
+        // Index the loaded rows before the passes below scan them by time and pid
+        db.create_indexes()?;
 
-                db.insert_row(&row, ts_unix_ms)?;
+        // Step 4: Store the templates mined while writing
+        self.check_cancelled()?;
+        if let Some(ref cb) = self.progress_callback {
+            cb(IndexProgress {
+                bytes_read: total_bytes,
+                total_bytes,
+                rows_processed: summary.total_rows,
+                phase: IndexPhase::MiningTemplates,
+            });
+        }
 
-                // Update summary
-                summary.total_rows += 1;
-                batch_count += 1;
+        store_templates(&db.conn, &miner)?;
 
-                match level.as_str() {
-                    "E" => summary.error_count += 1,
-                    "F" => summary.fatal_count += 1,
-                    _ => {}
-                }
+        // Step 5: Pull key=value fields into their side table
+        self.check_cancelled()?;
+        if let Some(ref cb) = self.progress_callback {
+            cb(IndexProgress {
+                bytes_read: total_bytes,
+                total_bytes,
+                rows_processed: summary.total_rows,
+                phase: IndexPhase::ExtractingFields,
+            });
+        }
 
-                if ts_unix_ms > 0.0 {
-                    let ms = ts_unix_ms as u64;
-                    summary.min_timestamp_ms = Some(
-                        summary.min_timestamp_ms.map_or(ms, |m| m.min(ms))
-                    );
-                    summary.max_timestamp_ms = Some(
-                        summary.max_timestamp_ms.map_or(ms, |m| m.max(ms))
-                    );
-                }
+        extract_fields(&db.conn, &self.field_tags)?;
 
-                // Commit batch periodically
-                if batch_count >= BATCH_COMMIT_SIZE {
-                    db.commit()?;
-                    db.begin_transaction()?;
-                    batch_count = 0;
-
-                    // Report progress
-                    if let Some(ref cb) = self.progress_callback {
-                        cb(IndexProgress {
-                            bytes_read,
-                            total_bytes,
-                            rows_processed: summary.total_rows,
-                            phase: IndexPhase::Parsing,
-                        });
-                    }
-                }
-            }
+        // Step 6: Split pids into process instances
+        self.check_cancelled()?;
+        if let Some(ref cb) = self.progress_callback {
+            cb(IndexProgress {
+                bytes_read: total_bytes,
+                total_bytes,
+                rows_processed: summary.total_rows,
+                phase: IndexPhase::TrackingProcesses,
+            });
         }
 
-        // Commit final batch
-        if batch_count > 0 {
-            db.commit()?;
-        }
+        track_processes(&db.conn)?;
 
-        // Step 4: Build FTS index in batch
+        // Step 7: Build FTS index in batch
+        self.check_cancelled()?;
         if let Some(ref cb) = self.progress_callback {
             cb(IndexProgress {
                 bytes_read: total_bytes,
@@ -221,7 +263,8 @@ impl StreamingIndexBuilder {
 
         db.rebuild_fts_index()?;
 
-        // Step 5: Optimize
+        // Step 8: Optimize
+        self.check_cancelled()?;
         if let Some(ref cb) = self.progress_callback {
             cb(IndexProgress {
                 bytes_read: total_bytes,
@@ -246,6 +289,109 @@ impl StreamingIndexBuilder {
         Ok(summary)
     }
 
+    /// Producer: read waves of chunks and parse each wave in parallel
+    fn read_waves<R: Read>(
+        &self,
+        reader: R,
+        anchor: &TimeAnchor,
+        wave_size: usize,
+        tx: mpsc::SyncSender<Vec<ParsedChunk>>,
+    ) -> Result<()> {
+        let mut chunks = ChunkReader::new(reader, self.chunk_size);
+
+        loop {
+            self.check_cancelled()?;
+
+            let mut wave = Vec::with_capacity(wave_size);
+            while wave.len() < wave_size {
+                match chunks.next_chunk()? {
+                    Some(chunk) => wave.push(chunk),
+                    None => break,
+                }
+            }
+
+            if wave.is_empty() {
+                return Ok(());
+            }
+
+            let bytes_read = match self.input_counter {
+                Some(ref counter) => counter.load(Ordering::Relaxed),
+                None => chunks.bytes_read(),
+            };
+            let mut parsed: Vec<ParsedChunk> = wave
+                .par_iter()
+                .map(|chunk| parse_chunk(chunk, anchor))
+                .collect();
+            if let Some(last) = parsed.last_mut() {
+                last.bytes_read = bytes_read;
+            }
+
+            // Writer hung up (error or cancellation); it reports the cause
+            if tx.send(parsed).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Consumer: insert parsed rows in order with batched commits
+    ///
+    /// Rows are clustered into templates as they are written, in file order.
+    fn write_waves(
+        &self,
+        db: &StreamingDatabase,
+        rx: mpsc::Receiver<Vec<ParsedChunk>>,
+        total_bytes: u64,
+    ) -> Result<(IndexSummary, TemplateMiner)> {
+        let mut summary = IndexSummary::default();
+        let mut miner = TemplateMiner::default();
+        let mut batch_count = 0;
+
+        db.begin_transaction()?;
+
+        for wave in rx {
+            if self.cancel_flag.load(Ordering::Relaxed) {
+                db.rollback()?;
+                return Err(LogcatError::Cancelled);
+            }
+
+            let mut bytes_read = 0;
+            for chunk in wave {
+                for parsed in &chunk.rows {
+                    let template = template_id(miner.add_masked(&parsed.row.tag, &parsed.masked));
+                    if let Err(e) = db.insert_row(&parsed.row, parsed.ts_unix_ms, template) {
+                        db.rollback()?;
+                        return Err(e);
+                    }
+                    summary.record(&parsed.row.level, parsed.ts_unix_ms);
+                }
+                batch_count += chunk.rows.len();
+                bytes_read = bytes_read.max(chunk.bytes_read);
+            }
+
+            // Commit batch periodically
+            if batch_count >= BATCH_COMMIT_SIZE {
+                db.commit()?;
+                db.begin_transaction()?;
+                batch_count = 0;
+            }
+
+            // Report progress once per wave
+            if let Some(ref cb) = self.progress_callback {
+                cb(IndexProgress {
+                    bytes_read,
+                    total_bytes,
+                    rows_processed: summary.total_rows,
+                    phase: IndexPhase::Parsing,
+                });
+            }
+        }
+
+        // Commit final batch
+        db.commit()?;
+
+        Ok((summary, miner))
+    }
+
     fn create_db_without_fts_trigger(&self) -> Result<StreamingDatabase> {
         // Remove existing database
         if self.db_path.exists() {
@@ -274,7 +420,9 @@ impl StreamingIndexBuilder {
                 tag TEXT NOT NULL,
                 pid INTEGER NOT NULL,
                 tid INTEGER NOT NULL,
-                msg TEXT NOT NULL
+                msg TEXT NOT NULL,
+                template_id INTEGER,
+                instance_id INTEGER
             );
 
             -- Defer index creation for faster inserts
@@ -310,10 +458,16 @@ impl StreamingDatabase {
         Ok(())
     }
 
-    fn insert_row(&self, row: &LogRow, ts_unix_ms: f64) -> Result<()> {
-        self.conn
+    fn insert_row(&self, row: &LogRow, ts_unix_ms: f64, template_id: i64) -> Result<()> {
+        // Reuse one prepared statement for the whole load
+        let mut stmt = self.conn
+            .prepare_cached(
+                "INSERT INTO logs (ts_unix, ts_display, ts_iso, level, tag, pid, tid, msg, template_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+        stmt
             .execute(
-                "INSERT INTO logs (ts_unix, ts_display, ts_iso, level, tag, pid, tid, msg) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
                 rusqlite::params![
                     ts_unix_ms,
                     row.ts,
@@ -323,13 +477,14 @@ impl StreamingDatabase {
                     row.pid,
                     row.tid,
                     row.msg,
+                    template_id,
                 ],
             )
             .map_err(|e| LogcatError::Database(e.to_string()))?;
         Ok(())
     }
 
-    fn rebuild_fts_index(&self) -> Result<()> {
+    fn create_indexes(&self) -> Result<()> {
         // Create indexes now that data is loaded
         self.conn.execute_batch(
             r#"
@@ -337,18 +492,32 @@ impl StreamingDatabase {
             CREATE INDEX idx_level ON logs(level);
             CREATE INDEX idx_tag ON logs(tag);
             CREATE INDEX idx_pid ON logs(pid);
+            CREATE INDEX idx_tid ON logs(tid);
+            "#,
+        )
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+        Ok(())
+    }
 
+    fn rebuild_fts_index(&self) -> Result<()> {
+        self.conn.execute_batch(
+            r#"
             -- Create FTS table and populate in one go
+            -- Trigram tokenizer enables substring search through MATCH
             CREATE VIRTUAL TABLE logs_fts USING fts5(
                 msg,
                 content=logs,
-                content_rowid=id
+                content_rowid=id,
+                tokenize='trigram'
             );
 
             INSERT INTO logs_fts(logs_fts) VALUES('rebuild');
             "#,
         )
         .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+        self.conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
         Ok(())
     }
 
@@ -365,6 +534,126 @@ impl StreamingDatabase {
     }
 }
 
+/// Read up to `limit` bytes from the start of a stream
+pub fn read_prefix<R: Read>(reader: &mut R, limit: usize) -> Result<Vec<u8>> {
+    let mut prefix = Vec::with_capacity(limit);
+    reader.take(limit as u64).read_to_end(&mut prefix)?;
+    Ok(prefix)
+}
+
+/// A parsed row ready for insertion
+struct ParsedRow {
+    row: LogRow,
+    ts_unix_ms: f64,
+    /// `mask_message` of the message, for template mining in the writer
+    masked: String,
+}
+
+/// Rows parsed from one chunk, in file order
+struct ParsedChunk {
+    rows: Vec<ParsedRow>,
+    /// Total input bytes consumed up to the end of this wave
+    bytes_read: u64,
+}
+
+/// Parse one newline-aligned chunk (runs on the rayon pool)
+///
+/// Invalid UTF-8 is replaced rather than dropping the whole line.
+fn parse_chunk(chunk: &[u8], anchor: &TimeAnchor) -> ParsedChunk {
+    let mut rows = Vec::new();
+
+    for line in chunk.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        if let Some(caps) = LOGCAT_RE.captures(&line) {
+            let ts = format!("{} {}", &caps["date"], &caps["time"]);
+
+            let ts_iso = to_iso_safe(&ts, anchor).ok();
+            let ts_unix_ms = ts_iso
+                .as_ref()
+                .and_then(|iso| iso_ts_key_ms(iso).ok())
+                .unwrap_or(0) as f64;
+
+            let msg = caps["msg"].to_string();
+            rows.push(ParsedRow {
+                masked: mask_message(&msg),
+                row: LogRow {
+                    id: None,
+                    ts,
+                    ts_iso,
+                    level: caps["level"].to_string(),
+                    tag: caps["tag"].to_string(),
+                    pid: caps["pid"].parse().unwrap_or_default(),
+                    tid: caps["tid"].parse().unwrap_or_default(),
+                    msg,
+                    matches: None,
+                    repeat: None,
+                    instance_id: None,
+                },
+                ts_unix_ms,
+            });
+        }
+    }
+
+    ParsedChunk { rows, bytes_read: 0 }
+}
+
+/// Splits a reader into chunks that always end on a line boundary
+struct ChunkReader<R: Read> {
+    reader: R,
+    chunk_size: usize,
+    carry: Vec<u8>,
+    bytes_read: u64,
+}
+
+impl<R: Read> ChunkReader<R> {
+    fn new(reader: R, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            chunk_size,
+            carry: Vec::new(),
+            bytes_read: 0,
+        }
+    }
+
+    /// Bytes handed out in chunks so far
+    fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Read the next chunk; a trailing partial line is carried over
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut buf = std::mem::take(&mut self.carry);
+
+        loop {
+            let before = buf.len();
+            let n = (&mut self.reader)
+                .take(self.chunk_size as u64)
+                .read_to_end(&mut buf)?;
+
+            if n == 0 {
+                // EOF: flush whatever is left (last line without newline)
+                if buf.is_empty() {
+                    return Ok(None);
+                }
+                self.bytes_read += buf.len() as u64;
+                return Ok(Some(buf));
+            }
+
+            // Lines longer than a chunk keep growing the buffer
+            if let Some(pos) = buf[before..].iter().rposition(|&b| b == b'\n') {
+                self.carry = buf.split_off(before + pos + 1);
+                self.bytes_read += buf.len() as u64;
+                return Ok(Some(buf));
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -430,6 +719,82 @@ mod tests {
         std::fs::remove_file(&db_path).ok();
     }
 
+    #[test]
+    fn test_streaming_small_chunks_keep_order() {
+        // Last line has no trailing newline and chunks split lines mid-way
+        let mut sample = String::from("persist.sys.timezone=Asia/Taipei\n");
+        for i in 0..200 {
+            sample.push_str(&format!("08-24 14:22:{:02}.{:03}  1234  5678 I Seq: line {}\n", i % 60, i, i));
+        }
+        sample.push_str("08-24 14:23:00.000  1234  5678 E Seq: last");
+
+        let db_path = temp_db_path();
+        let summary = StreamingIndexBuilder::new(&db_path)
+            .with_chunk_size(100)
+            .build_from_reader(Cursor::new(sample.as_bytes().to_vec()), sample.len() as u64)
+            .unwrap();
+
+        assert_eq!(summary.total_rows, 201);
+        assert_eq!(summary.error_count, 1);
+
+        let db = LogcatDatabase::open(&db_path).unwrap();
+        let msgs: Vec<String> = db.connection()
+            .prepare("SELECT msg FROM logs ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(msgs[0], "line 0");
+        assert_eq!(msgs[199], "line 199");
+        assert_eq!(msgs[200], "last");
+
+        std::fs::remove_file(&db_path).ok();
+    }
+
+    #[test]
+    fn test_streaming_assigns_templates_while_writing() {
+        let sample = "08-24 14:22:00.000  1234  5678 I Seq: line 1\n\
+                      08-24 14:22:01.000  1234  5678 I Seq: line 2\n\
+                      08-24 14:22:02.000  1234  5678 E Seq: last\n";
+
+        let db_path = temp_db_path();
+        StreamingIndexBuilder::new(&db_path)
+            .with_chunk_size(50)
+            .build_from_reader(Cursor::new(sample.as_bytes().to_vec()), sample.len() as u64)
+            .unwrap();
+
+        let db = LogcatDatabase::open(&db_path).unwrap();
+        let rows: Vec<(i64, String)> = db.connection()
+            .prepare("SELECT l.template_id, t.template FROM logs l JOIN templates t ON t.id = l.template_id ORDER BY l.id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], rows[1]);
+        assert_eq!(rows[0].1, "line <*>");
+        assert_ne!(rows[0].0, rows[2].0);
+
+        std::fs::remove_file(&db_path).ok();
+    }
+
+    #[test]
+    fn test_chunk_reader_aligns_to_lines() {
+        let data = b"aaaa\nbbbbbbbbbbbb\ncc\nd".to_vec();
+        let mut reader = ChunkReader::new(Cursor::new(data), 6);
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = reader.next_chunk().unwrap() {
+            chunks.push(String::from_utf8(chunk).unwrap());
+        }
+
+        assert!(chunks.iter().all(|c| c.ends_with('\n') || c == "d"));
+        assert_eq!(chunks.concat(), "aaaa\nbbbbbbbbbbbb\ncc\nd");
+        assert_eq!(reader.bytes_read(), 22);
+    }
+
     #[test]
     fn test_streaming_cancellation() {
         let sample = "08-24 14:22:33.123  1234  5678 E Test: error\n".repeat(100);
@@ -444,7 +809,7 @@ mod tests {
         cancel_flag.store(true, Ordering::Relaxed);
 
         let result = builder.build_from_reader(cursor, sample.len() as u64);
-        assert!(result.is_err());
+        assert!(matches!(result, Err(LogcatError::Cancelled)));
 
         std::fs::remove_file(&db_path).ok();
     }
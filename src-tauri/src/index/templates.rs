@@ -0,0 +1,337 @@
+use crate::error::{LogcatError, Result};
+use crate::query::{message_template, TEMPLATE_WILDCARD};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rayon::prelude::*;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+/// Minimum share of matching tokens for a message to join a template
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// Leading tokens that route a message to its leaf (Drain's tree depth)
+const PREFIX_DEPTH: usize = 2;
+
+/// Distinct prefixes per (tag, token count) before new ones share a
+/// wildcard leaf, which bounds the tree for highly variable messages
+const MAX_CHILDREN: usize = 100;
+
+/// Rows read and updated per transaction when mining an existing cache
+const BACKFILL_BATCH: usize = 50_000;
+
+/// Mined templates; `logs.template_id` points here
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS templates (
+        id INTEGER PRIMARY KEY,
+        tag TEXT NOT NULL,
+        template TEXT NOT NULL,
+        row_count INTEGER NOT NULL
+    );";
+
+/// Variable message parts, masked in this order before clustering
+static MASKS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // UUIDs
+        r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+        // Hex: 0x-prefixed or long bare runs (hashes, addresses)
+        r"\b0[xX][0-9a-fA-F]+\b|\b[0-9a-fA-F]{8,}\b",
+        // Paths with at least two segments
+        r"(?:/[\w.@:+-]+){2,}/?",
+        // Package / class names
+        r"\b[a-z][a-z0-9_]*(?:\.[a-zA-Z_][\w$]*)+\b",
+    ]
+    .iter()
+    .map(|p| Regex::new(p).unwrap())
+    .collect()
+});
+
+/// Mask uuids, hex, paths, package names and numbers in a message
+pub fn mask_message(msg: &str) -> String {
+    let masked = MASKS.iter().fold(msg.to_string(), |text, re| {
+        re.replace_all(&text, TEMPLATE_WILDCARD).into_owned()
+    });
+    message_template(&masked)
+}
+
+/// A template: tokens with `<*>` where member messages differ
+struct Cluster {
+    tag: String,
+    tokens: Vec<String>,
+    count: usize,
+}
+
+/// Leaves of one (tag, token count) node, keyed by leading tokens
+#[derive(Default)]
+struct LengthNode {
+    leaves: HashMap<Vec<String>, Vec<usize>>,
+}
+
+/// Drain-style online clustering of masked messages
+///
+/// Messages are routed through a fixed-depth prefix tree (tag, token count,
+/// leading tokens); within the leaf a message joins the most similar
+/// template, turning differing tokens into wildcards. Only the leaf's few
+/// templates are compared, so mining keeps up with the index writer.
+#[derive(Default)]
+pub struct TemplateMiner {
+    clusters: Vec<Cluster>,
+    nodes: HashMap<(String, usize), LengthNode>,
+}
+
+impl TemplateMiner {
+    /// Add a message and return its template index
+    pub fn add(&mut self, tag: &str, msg: &str) -> usize {
+        self.add_masked(tag, &mask_message(msg))
+    }
+
+    /// Add a message already passed through `mask_message`
+    ///
+    /// Masking is the expensive part, so the index writers do it on the
+    /// parse workers and only route here.
+    pub fn add_masked(&mut self, tag: &str, masked: &str) -> usize {
+        let tokens: Vec<&str> = masked.split_whitespace().collect();
+        let node = self.nodes.entry((tag.to_string(), tokens.len())).or_default();
+
+        let mut prefix: Vec<String> = tokens.iter().take(PREFIX_DEPTH).map(|t| t.to_string()).collect();
+        if !node.leaves.contains_key(&prefix) && node.leaves.len() >= MAX_CHILDREN {
+            prefix.iter_mut().for_each(|t| *t = TEMPLATE_WILDCARD.to_string());
+        }
+        let members = node.leaves.entry(prefix).or_default();
+
+        let best = members
+            .iter()
+            .map(|&index| (index, similarity(&self.clusters[index].tokens, &tokens)))
+            .filter(|(_, score)| *score >= SIMILARITY_THRESHOLD)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match best {
+            Some((index, _)) => {
+                let cluster = &mut self.clusters[index];
+                for (token, new) in cluster.tokens.iter_mut().zip(&tokens) {
+                    if token != new {
+                        *token = TEMPLATE_WILDCARD.to_string();
+                    }
+                }
+                cluster.count += 1;
+                index
+            }
+            None => {
+                let index = self.clusters.len();
+                self.clusters.push(Cluster {
+                    tag: tag.to_string(),
+                    tokens: tokens.iter().map(|t| t.to_string()).collect(),
+                    count: 1,
+                });
+                members.push(index);
+                index
+            }
+        }
+    }
+
+    /// Tag, template text and row count per template index
+    pub fn templates(&self) -> impl Iterator<Item = (&str, String, usize)> {
+        self.clusters
+            .iter()
+            .map(|c| (c.tag.as_str(), c.tokens.join(" "), c.count))
+    }
+}
+
+/// Share of positions where the template has the same concrete token
+fn similarity(template: &[String], tokens: &[&str]) -> f64 {
+    if tokens.is_empty() {
+        return 1.0;
+    }
+    let same = template
+        .iter()
+        .zip(tokens)
+        .filter(|(t, new)| t.as_str() != TEMPLATE_WILDCARD && t == *new)
+        .count();
+    same as f64 / tokens.len() as f64
+}
+
+/// Template id stored in `logs.template_id` for a miner index
+pub fn template_id(index: usize) -> i64 {
+    index as i64 + 1
+}
+
+/// Store the templates of `miner` once its rows carry their `template_id`
+///
+/// Returns the number of templates.
+pub fn store_templates(conn: &Connection, miner: &TemplateMiner) -> Result<usize> {
+    let db_err = |e: rusqlite::Error| LogcatError::Database(e.to_string());
+
+    let tx = conn.unchecked_transaction().map_err(db_err)?;
+    tx.execute_batch(SCHEMA).map_err(db_err)?;
+    tx.execute("DELETE FROM templates", []).map_err(db_err)?;
+    {
+        let mut insert = tx
+            .prepare("INSERT INTO templates (id, tag, template, row_count) VALUES (?1, ?2, ?3, ?4)")
+            .map_err(db_err)?;
+        for (index, (tag, template, count)) in miner.templates().enumerate() {
+            insert.execute(params![template_id(index), tag, template, count as i64]).map_err(db_err)?;
+        }
+    }
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS idx_template ON logs(template_id);")
+        .map_err(db_err)?;
+    tx.commit().map_err(db_err)?;
+
+    Ok(miner.clusters.len())
+}
+
+/// Cluster every row of an existing cache and store the templates
+///
+/// For caches built before mining moved into the index writers. Adds
+/// `logs.template_id` if missing and works in id batches, masking each
+/// batch in parallel. Returns the number of templates.
+pub fn mine_templates(conn: &Connection) -> Result<usize> {
+    let db_err = |e: rusqlite::Error| LogcatError::Database(e.to_string());
+
+    if !has_template_column(conn)? {
+        conn.execute_batch("ALTER TABLE logs ADD COLUMN template_id INTEGER;")
+            .map_err(db_err)?;
+    }
+
+    let mut miner = TemplateMiner::default();
+    let mut last_id = i64::MIN;
+    loop {
+        let batch: Vec<(i64, String, String)> = {
+            let mut stmt = conn
+                .prepare_cached("SELECT id, tag, msg FROM logs WHERE id > ?1 ORDER BY id LIMIT ?2")
+                .map_err(db_err)?;
+            let rows = stmt
+                .query_map(params![last_id, BACKFILL_BATCH as i64], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+                .map_err(db_err)?;
+            rows.collect::<rusqlite::Result<_>>().map_err(db_err)?
+        };
+        let last = match batch.last() {
+            Some((id, _, _)) => *id,
+            None => break,
+        };
+
+        let masked: Vec<String> = batch.par_iter().map(|(_, _, msg)| mask_message(msg)).collect();
+        let tx = conn.unchecked_transaction().map_err(db_err)?;
+        {
+            let mut update = tx.prepare_cached("UPDATE logs SET template_id = ?1 WHERE id = ?2").map_err(db_err)?;
+            for ((id, tag, _), masked) in batch.iter().zip(&masked) {
+                update.execute(params![template_id(miner.add_masked(tag, masked)), id]).map_err(db_err)?;
+            }
+        }
+        tx.commit().map_err(db_err)?;
+        last_id = last;
+    }
+
+    store_templates(conn, &miner)
+}
+
+/// Whether the report's templates have been stored
+pub fn has_templates(conn: &Connection) -> Result<bool> {
+    conn.query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'templates'", [], |_| Ok(()))
+        .optional()
+        .map(|found| found.is_some())
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+/// Fail with `IndexPending` until the report's templates are stored
+pub fn require_templates(conn: &Connection) -> Result<()> {
+    if has_templates(conn)? {
+        Ok(())
+    } else {
+        Err(LogcatError::IndexPending("message templates".to_string()))
+    }
+}
+
+/// Tag and template text by template id
+pub fn load_templates(conn: &Connection) -> Result<HashMap<i64, (String, String)>> {
+    let mut stmt = conn
+        .prepare("SELECT id, tag, template FROM templates")
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get(0)?, (r.get(1)?, r.get(2)?))))
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+    rows.collect::<rusqlite::Result<_>>()
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+fn has_template_column(conn: &Connection) -> Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('logs') WHERE name = 'template_id'",
+        [],
+        |r| r.get::<_, i64>(0),
+    )
+    .map(|n| n > 0)
+    .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_message() {
+        assert_eq!(
+            mask_message("Killing 1234:com.foo.bar/u0a12 (adj 900): empty #17"),
+            "Killing <*>:<*>/<*> (adj <*>): empty #<*>"
+        );
+        assert_eq!(
+            mask_message("open /data/app/base.apk id 550e8400-e29b-41d4-a716-446655440000"),
+            "open <*> id <*>"
+        );
+        assert_eq!(mask_message("addr 0xdeadbeef hash cafebabe01"), "addr <*> hash <*>");
+        assert_eq!(mask_message("Skipped frames! The application"), "Skipped frames! The application");
+    }
+
+    #[test]
+    fn test_miner_merges_similar_messages() {
+        let mut miner = TemplateMiner::default();
+        let a = miner.add("Wifi", "Connected to network home signal good");
+        let b = miner.add("Wifi", "Connected to network office signal weak");
+        let c = miner.add("Wifi", "Scan failed");
+        let d = miner.add("Other", "Connected to network home signal good");
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, d);
+
+        let templates: Vec<_> = miner.templates().collect();
+        assert_eq!(templates[a].1, "Connected to network <*> signal <*>");
+        assert_eq!(templates[a].2, 2);
+    }
+
+    #[test]
+    fn test_miner_caps_prefix_leaves() {
+        let mut miner = TemplateMiner::default();
+        for i in 0..MAX_CHILDREN {
+            miner.add("T", &format!("{} alpha beta gamma", "x".repeat(i + 1)));
+        }
+        // Past the cap, new prefixes share the wildcard leaf and still merge
+        let a = miner.add("T", "first alpha beta gamma");
+        let b = miner.add("T", "second alpha beta gamma");
+        assert_eq!(a, b);
+        assert_eq!(miner.nodes[&("T".to_string(), 4)].leaves.len(), MAX_CHILDREN + 1);
+    }
+
+    #[test]
+    fn test_mine_templates_assigns_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE logs (id INTEGER PRIMARY KEY, tag TEXT NOT NULL, msg TEXT NOT NULL);
+             INSERT INTO logs (tag, msg) VALUES
+                ('Net', 'retry 1 of 5'), ('Net', 'retry 2 of 5'), ('Net', 'giving up');",
+        )
+        .unwrap();
+
+        assert!(require_templates(&conn).is_err());
+        assert_eq!(mine_templates(&conn).unwrap(), 2);
+        require_templates(&conn).unwrap();
+        let ids: Vec<i64> = conn
+            .prepare("SELECT template_id FROM logs ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(ids, vec![1, 1, 2]);
+
+        let templates = load_templates(&conn).unwrap();
+        assert_eq!(templates[&1], ("Net".to_string(), "retry <*> of <*>".to_string()));
+    }
+}
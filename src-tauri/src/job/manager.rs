@@ -0,0 +1,328 @@
+use crate::error::{LogcatError, Result};
+use crate::index::{IndexPhase, IndexProgress};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Finished jobs kept for status polling after their finished event
+const MAX_FINISHED_JOBS: usize = 64;
+
+/// Lifecycle state of a background job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+/// Snapshot of a background job for the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub job_id: String,
+    pub state: JobState,
+    pub phase: String,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub rows_processed: usize,
+    pub percent: f32,
+    /// Error message when the job failed
+    pub error: Option<String>,
+    /// Job result (e.g. parse summary) once completed
+    pub result: Option<serde_json::Value>,
+}
+
+/// Shared handle to a running job
+pub struct JobHandle {
+    cancel_flag: Arc<AtomicBool>,
+    /// Cache directory the job reads or writes, protected from eviction and deletion
+    cache_id: String,
+    status: Mutex<JobStatus>,
+}
+
+impl JobHandle {
+    fn new(job_id: &str, cache_id: String) -> Self {
+        Self {
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+            cache_id,
+            status: Mutex::new(JobStatus {
+                job_id: job_id.to_string(),
+                state: JobState::Running,
+                phase: "starting".to_string(),
+                bytes_read: 0,
+                total_bytes: 0,
+                rows_processed: 0,
+                percent: 0.0,
+                error: None,
+                result: None,
+            }),
+        }
+    }
+
+    /// Flag checked by the worker to stop early
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel_flag)
+    }
+
+    /// Current status snapshot
+    pub fn status(&self) -> JobStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_else(|p| p.into_inner().clone())
+    }
+
+    /// Record indexing progress and return the updated snapshot
+    pub fn update_progress(&self, progress: &IndexProgress) -> JobStatus {
+        let phase = match progress.phase {
+            IndexPhase::Parsing => "parsing",
+            IndexPhase::MiningTemplates => "mining_templates",
+            IndexPhase::ExtractingFields => "extracting_fields",
+            IndexPhase::TrackingProcesses => "tracking_processes",
+            IndexPhase::BuildingFts => "building_fts",
+            IndexPhase::Optimizing => "optimizing",
+            IndexPhase::Complete => "complete",
+        };
+
+        let percent = if progress.total_bytes > 0 {
+            (progress.bytes_read as f32 / progress.total_bytes as f32) * 100.0
+        } else {
+            0.0
+        };
+
+        self.with_status(|s| {
+            s.phase = phase.to_string();
+            s.bytes_read = progress.bytes_read;
+            s.total_bytes = progress.total_bytes;
+            s.rows_processed = progress.rows_processed;
+            s.percent = percent;
+        })
+    }
+
+    /// Record row-based progress (e.g. an export) and return the updated snapshot
+    pub fn update_rows(&self, phase: &str, rows_processed: usize, total_rows: usize) -> JobStatus {
+        let percent = if total_rows > 0 {
+            (rows_processed as f32 / total_rows as f32) * 100.0
+        } else {
+            0.0
+        };
+
+        self.with_status(|s| {
+            s.phase = phase.to_string();
+            s.rows_processed = rows_processed;
+            s.percent = percent;
+        })
+    }
+
+    /// Mark the job as completed with a result
+    pub fn complete(&self, result: serde_json::Value) -> JobStatus {
+        self.with_status(|s| {
+            s.state = JobState::Completed;
+            s.phase = "complete".to_string();
+            s.percent = 100.0;
+            s.result = Some(result);
+        })
+    }
+
+    /// Mark the job as failed, or cancelled if the error is a cancellation
+    pub fn fail(&self, error: &LogcatError) -> JobStatus {
+        let cancelled = matches!(error, LogcatError::Cancelled);
+        self.with_status(|s| {
+            s.state = if cancelled { JobState::Cancelled } else { JobState::Failed };
+            s.phase = if cancelled { "cancelled" } else { "failed" }.to_string();
+            s.error = Some(error.to_string());
+        })
+    }
+
+    fn with_status<F: FnOnce(&mut JobStatus)>(&self, f: F) -> JobStatus {
+        let mut guard = self.status.lock().unwrap_or_else(|p| p.into_inner());
+        f(&mut guard);
+        guard.clone()
+    }
+}
+
+/// Registry of background jobs (parse, export, ...)
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Mutex<HashMap<String, Arc<JobHandle>>>,
+    /// Ids of finished jobs, oldest first
+    finished: Mutex<VecDeque<String>>,
+    next_id: AtomicU64,
+}
+
+impl JobManager {
+    /// Register a new running job that reads or writes the cache `cache_id`
+    pub fn start_for_cache(&self, prefix: &str, cache_id: &str) -> Result<Arc<JobHandle>> {
+        let n = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let job_id = format!("{}-{}", prefix, n);
+        let handle = Arc::new(JobHandle::new(&job_id, cache_id.to_string()));
+
+        self.jobs
+            .lock()
+            .map_err(|_| LogcatError::StatePoisoned)?
+            .insert(job_id, Arc::clone(&handle));
+
+        Ok(handle)
+    }
+
+    /// Look up a job by id
+    ///
+    /// Finished jobs stay available until `MAX_FINISHED_JOBS` newer ones
+    /// finished; older ids fail with `JobExpired` rather than `JobNotFound`.
+    pub fn get(&self, job_id: &str) -> Result<Arc<JobHandle>> {
+        let found = self
+            .jobs
+            .lock()
+            .map_err(|_| LogcatError::StatePoisoned)?
+            .get(job_id)
+            .cloned();
+        match found {
+            Some(handle) => Ok(handle),
+            None if self.was_issued(job_id) => Err(LogcatError::JobExpired(job_id.to_string())),
+            None => Err(LogcatError::JobNotFound(job_id.to_string())),
+        }
+    }
+
+    /// Mark a job as finished once its outcome has been delivered
+    ///
+    /// Called by workers after emitting the finished event. Its status stays
+    /// readable, and the oldest finished jobs beyond `MAX_FINISHED_JOBS` are
+    /// pruned so the registry does not grow for the whole session.
+    pub fn retire(&self, job_id: &str) {
+        let mut finished = match self.finished.lock() {
+            Ok(finished) => finished,
+            Err(_) => return,
+        };
+        if finished.iter().any(|id| id == job_id) {
+            return;
+        }
+        finished.push_back(job_id.to_string());
+
+        if let Ok(mut jobs) = self.jobs.lock() {
+            while finished.len() > MAX_FINISHED_JOBS {
+                if let Some(oldest) = finished.pop_front() {
+                    jobs.remove(&oldest);
+                }
+            }
+        }
+    }
+
+    /// Whether `job_id` was handed out by this manager
+    fn was_issued(&self, job_id: &str) -> bool {
+        let issued = self.next_id.load(Ordering::Relaxed);
+        job_id
+            .rsplit_once('-')
+            .and_then(|(_, n)| n.parse::<u64>().ok())
+            .is_some_and(|n| n >= 1 && n <= issued)
+    }
+
+    /// Cache ids used by jobs that are still running
+    pub fn busy_cache_ids(&self) -> Vec<String> {
+        let jobs = match self.jobs.lock() {
+            Ok(jobs) => jobs,
+            Err(_) => return Vec::new(),
+        };
+        jobs.values()
+            .filter(|job| job.status().state == JobState::Running)
+            .map(|job| job.cache_id.clone())
+            .collect()
+    }
+
+    /// Request cancellation of a running job
+    ///
+    /// Returns false if the job already finished.
+    pub fn cancel(&self, job_id: &str) -> Result<bool> {
+        let handle = self.get(job_id)?;
+        if handle.status().state != JobState::Running {
+            return Ok(false);
+        }
+        handle.cancel_flag.store(true, Ordering::Relaxed);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle() {
+        let manager = JobManager::default();
+        let job = manager.start_for_cache("parse", "report").unwrap();
+        let job_id = job.status().job_id;
+        assert!(job_id.starts_with("parse-"));
+
+        job.update_progress(&IndexProgress {
+            bytes_read: 50,
+            total_bytes: 200,
+            rows_processed: 10,
+            phase: IndexPhase::Parsing,
+        });
+        let status = manager.get(&job_id).unwrap().status();
+        assert_eq!(status.state, JobState::Running);
+        assert_eq!(status.percent, 25.0);
+
+        job.complete(serde_json::json!({ "ok": true }));
+        let status = manager.get(&job_id).unwrap().status();
+        assert_eq!(status.state, JobState::Completed);
+        assert!(status.result.is_some());
+
+        let status = job.update_rows("exporting", 30, 40);
+        assert_eq!(status.phase, "exporting");
+        assert_eq!(status.percent, 75.0);
+
+        // Finished jobs cannot be cancelled
+        assert!(!manager.cancel(&job_id).unwrap());
+
+        // Finished jobs stay readable after their outcome was delivered
+        manager.retire(&job_id);
+        let status = manager.get(&job_id).unwrap().status();
+        assert_eq!(status.state, JobState::Completed);
+        assert_eq!(status.result, Some(serde_json::json!({ "ok": true })));
+    }
+
+    #[test]
+    fn test_finished_jobs_are_pruned() {
+        let manager = JobManager::default();
+        let first = manager.start_for_cache("export", "report").unwrap().status().job_id;
+        manager.retire(&first);
+
+        for _ in 0..MAX_FINISHED_JOBS {
+            let job = manager.start_for_cache("export", "report").unwrap();
+            job.complete(serde_json::Value::Null);
+            manager.retire(&job.status().job_id);
+        }
+        assert!(matches!(manager.get(&first), Err(LogcatError::JobExpired(_))));
+        assert!(matches!(manager.get("export-999"), Err(LogcatError::JobNotFound(_))));
+        assert_eq!(manager.jobs.lock().unwrap().len(), MAX_FINISHED_JOBS);
+    }
+
+    #[test]
+    fn test_cancel_running_job() {
+        let manager = JobManager::default();
+        let job = manager.start_for_cache("parse", "report").unwrap();
+        let job_id = job.status().job_id;
+
+        assert!(manager.cancel(&job_id).unwrap());
+        assert!(job.cancel_flag().load(Ordering::Relaxed));
+
+        let status = job.fail(&LogcatError::Cancelled);
+        assert_eq!(status.state, JobState::Cancelled);
+        assert!(manager.get("missing").is_err());
+    }
+
+    #[test]
+    fn test_busy_cache_ids() {
+        let manager = JobManager::default();
+        let parse = manager.start_for_cache("parse", "a").unwrap();
+        let export = manager.start_for_cache("export", "b").unwrap();
+        let mut busy = manager.busy_cache_ids();
+        busy.sort();
+        assert_eq!(busy, vec!["a".to_string(), "b".to_string()]);
+
+        parse.fail(&LogcatError::Cancelled);
+        assert_eq!(manager.busy_cache_ids(), vec!["b".to_string()]);
+        export.complete(serde_json::Value::Null);
+        assert!(manager.busy_cache_ids().is_empty());
+    }
+}
@@ -0,0 +1,3 @@
+mod manager;
+
+pub use manager::{JobManager, JobState, JobStatus};
@@ -1,57 +1,120 @@
 use serde::Serialize;
-use std::{path::PathBuf, sync::Mutex};
+use std::sync::{Arc, Mutex};
 use tauri::{State, Emitter};
 use tauri::menu::{MenuItemBuilder, SubmenuBuilder, MenuBuilder};
 
 mod error;
 mod types;
+mod cache;
+mod report;
+mod job;
 mod time;
 mod parser;
 mod index;
 mod query;
+mod export;
 
 // Re-export for backward compatibility
 pub use error::{LogcatError, Result};
 pub use types::{DeviceInfo, LogRow, LogFilters};
 pub use query::{QueryCursor, CursorDirection, QueryResponse, LogcatStats};
+pub use cache::{CacheConfig, CachedReport};
+pub use report::ReportInfo;
+pub use index::{StreamingIndexBuilder, write_logcat_fixture, FixtureSummary};
 
 #[derive(Default)]
 struct AppState {
-    last_cache_dir: Option<PathBuf>,
+    reports: report::ReportRegistry,
+}
+
+/// Look up an open report by id, or the active report when no id is given
+fn get_report(
+    state: &State<'_, Mutex<AppState>>,
+    report_id: Option<&str>,
+) -> std::result::Result<Arc<report::OpenReport>, String> {
+    state
+        .lock()
+        .map_err(|_| "State poisoned".to_string())?
+        .reports
+        .get(report_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Register a freshly parsed or reopened cache directory
+fn open_report(
+    state: &State<'_, Mutex<AppState>>,
+    cache_dir: &std::path::Path,
+) -> std::result::Result<String, String> {
+    state
+        .lock()
+        .map_err(|_| "State poisoned".to_string())?
+        .reports
+        .open(cache_dir)
+        .map_err(|e| e.to_string())
+}
+
+/// Close any open report backed by the cache a bugreport will be parsed into
+fn close_report_for_path(state: &State<'_, Mutex<AppState>>, path: &str) {
+    let cache_dir = cache::CacheManager::open_default()
+        .map(|m| m.root().join(cache::CacheManager::id_for_report(path)));
+
+    if let (Ok(dir), Ok(mut guard)) = (cache_dir, state.lock()) {
+        guard.reports.close_dir(&dir);
+    }
 }
 
 // ============================================================================
 // V1 API (kept for backward compatibility)
 // ============================================================================
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ParseSummary {
+    report_id: String,
+    cache_id: String,
     device: types::DeviceInfo,
     events: usize,
     anrs: usize,
     crashes: usize,
     ef_total: usize,
     ef_recent: usize,
+    /// Where the timestamps' timezone and year came from; `None` for caches
+    /// built before this was recorded
+    time_anchor: Option<time::AnchorInfo>,
+}
+
+impl ParseSummary {
+    fn from_result(report_id: String, result: parser::ParseResult) -> Self {
+        Self {
+            report_id,
+            cache_id: result.cache_id,
+            device: result.device,
+            events: result.anr_count + result.crash_count,
+            anrs: result.anr_count,
+            crashes: result.crash_count,
+            ef_total: result.index_summary.error_count + result.index_summary.fatal_count,
+            ef_recent: result.index_summary.fatal_count,
+            time_anchor: Some(result.time_anchor),
+        }
+    }
 }
 
 #[tauri::command]
-async fn parse_bugreport(path: String, state: State<'_, Mutex<AppState>>) -> std::result::Result<ParseSummary, String> {
+async fn parse_bugreport(
+    path: String,
+    state: State<'_, Mutex<AppState>>,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<ParseSummary, String> {
+    close_report_for_path(&state, &path);
+
     let result = parser::parse_bugreport(&path).map_err(|e| e.to_string())?;
 
-    // Update state with cache directory
-    if let Ok(mut guard) = state.lock() {
-        guard.last_cache_dir.replace(result.cache_dir.clone());
-    }
+    // Register the report so queries can reach it
+    let report_id = open_report(&state, &result.cache_dir)?;
 
-    Ok(ParseSummary {
-        device: result.device,
-        events: result.anr_count + result.crash_count,
-        anrs: result.anr_count,
-        crashes: result.crash_count,
-        ef_total: result.index_summary.error_count + result.index_summary.fatal_count,
-        ef_recent: result.index_summary.fatal_count,
-    })
+    evict_cache(&state, &jobs);
+
+    Ok(ParseSummary::from_result(report_id, result))
 }
 
 // Legacy query function (kept for compatibility)
@@ -62,29 +125,15 @@ async fn query_logcat(
     page: u32,
     page_size: u32,
 ) -> std::result::Result<Vec<types::LogRow>, String> {
-    let cache_dir = state
-        .lock()
-        .map_err(|_| "State poisoned".to_string())?
-        .last_cache_dir
-        .clone()
-        .ok_or_else(|| "No cache yet. Please parse a bugreport first.".to_string())?;
-
-    let db_path = cache_dir.join("logcat.db");
-    let executor = query::QueryExecutor::open(&db_path).map_err(|e| e.to_string())?;
+    let report = get_report(&state, None)?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
 
     // Calculate offset based on page number (0-indexed)
-    let offset = (page as i64) * (page_size as i64);
-    let cursor = if offset > 0 {
-        Some(QueryCursor::new(offset, CursorDirection::Forward, 0))
-    } else {
-        None
-    };
-
-    let response = executor
-        .query(&filters, cursor.as_ref(), page_size as usize, CursorDirection::Forward)
-        .map_err(|e| e.to_string())?;
+    let offset = page as usize * page_size as usize;
 
-    Ok(response.rows)
+    executor
+        .query_page(&filters, offset, page_size as usize)
+        .map_err(|e| e.to_string())
 }
 
 // Legacy stream function (kept for compatibility)
@@ -107,29 +156,22 @@ async fn query_logcat_stream(
     cursor: Option<u64>,
     limit: u32,
 ) -> std::result::Result<StreamResp, String> {
-    let cache_dir = state
-        .lock()
-        .map_err(|_| "State poisoned".to_string())?
-        .last_cache_dir
-        .clone()
-        .ok_or_else(|| "No cache yet. Please parse a bugreport first.".to_string())?;
+    let report = get_report(&state, None)?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
 
-    let db_path = cache_dir.join("logcat.db");
-    let executor = query::QueryExecutor::open(&db_path).map_err(|e| e.to_string())?;
-
-    // Convert legacy cursor to new format
-    let query_cursor = cursor.map(|pos| QueryCursor::new(pos as i64, CursorDirection::Forward, 0));
-
-    let response = executor
-        .query(&filters, query_cursor.as_ref(), limit as usize, CursorDirection::Forward)
+    // Legacy cursor is a row offset into the filtered result
+    let offset = cursor.unwrap_or(0) as usize;
+    let rows = executor
+        .query_page(&filters, offset, limit as usize)
         .map_err(|e| e.to_string())?;
+    let exhausted = rows.len() < limit as usize;
 
     let stats = executor.get_stats(&filters).map_err(|e| e.to_string())?;
 
     Ok(StreamResp {
-        rows: response.rows,
-        next_cursor: response.next_cursor.map(|c| c.position as u64).unwrap_or(0),
-        exhausted: !response.has_more_next,
+        next_cursor: (offset + rows.len()) as u64,
+        rows,
+        exhausted,
         file_size: 0, // Not applicable for SQLite
         total_rows: Some(stats.total_rows),
         min_iso_ms: stats.min_timestamp_ms.map(|t| t as u64),
@@ -142,72 +184,418 @@ async fn query_logcat_stream(
 // ============================================================================
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn query_logcat_v2(
     state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
     filters: types::LogFilters,
     cursor: Option<QueryCursor>,
     limit: u32,
     direction: Option<CursorDirection>,
+    highlight: Option<bool>,
+    collapse: Option<query::CollapseMode>,
 ) -> std::result::Result<QueryResponse, String> {
-    let cache_dir = state
-        .lock()
-        .map_err(|_| "State poisoned".to_string())?
-        .last_cache_dir
-        .clone()
-        .ok_or_else(|| "No cache yet. Please parse a bugreport first.".to_string())?;
-
-    let db_path = cache_dir.join("logcat.db");
-    let executor = query::QueryExecutor::open(&db_path).map_err(|e| e.to_string())?;
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
 
     let dir = direction.unwrap_or(CursorDirection::Forward);
 
-    executor
-        .query(&filters, cursor.as_ref(), limit as usize, dir)
-        .map_err(|e| e.to_string())
+    // A query without a cursor starts a new view
+    if cursor.is_none() {
+        if let Err(e) = crate::report::history::record_query(executor.connection(), &filters) {
+            log::warn!("Failed to record query history: {}", e);
+        }
+    }
+
+    let mut response = match collapse {
+        Some(mode) => executor.query_collapsed(&filters, cursor.as_ref(), limit as usize, dir, mode),
+        None => executor.query(&filters, cursor.as_ref(), limit as usize, dir),
+    }
+    .map_err(|e| e.to_string())?;
+    if highlight.unwrap_or(false) {
+        query::highlight_rows(&filters, &mut response.rows).map_err(|e| e.to_string())?;
+    }
+    Ok(response)
 }
 
 #[tauri::command]
 async fn jump_to_time(
     state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
     filters: types::LogFilters,
     target_time: String,
     limit: u32,
-) -> std::result::Result<QueryResponse, String> {
-    let cache_dir = state
-        .lock()
-        .map_err(|_| "State poisoned".to_string())?
-        .last_cache_dir
-        .clone()
-        .ok_or_else(|| "No cache yet. Please parse a bugreport first.".to_string())?;
+    highlight: Option<bool>,
+) -> std::result::Result<query::JumpResponse, String> {
+    let target = query::JumpTarget::Time { time: target_time };
+    jump_to(state, report_id, filters, target, limit, highlight).await
+}
+
+/// Window centred on a time, row, or event-relative target
+#[tauri::command]
+async fn jump_to(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    filters: types::LogFilters,
+    target: query::JumpTarget,
+    limit: u32,
+    highlight: Option<bool>,
+) -> std::result::Result<query::JumpResponse, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
 
-    let db_path = cache_dir.join("logcat.db");
-    let executor = query::QueryExecutor::open(&db_path).map_err(|e| e.to_string())?;
+    let mut response = executor
+        .jump(&filters, &target, limit as usize)
+        .map_err(|e| e.to_string())?;
+    if highlight.unwrap_or(false) {
+        query::highlight_rows(&filters, &mut response.page.rows).map_err(|e| e.to_string())?;
+    }
+    Ok(response)
+}
 
-    // Convert target time to filters
-    let mut time_filters = filters.clone();
-    time_filters.ts_from = Some(target_time);
+#[tauri::command]
+async fn get_context(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    row_id: i64,
+    before: u32,
+    after: u32,
+    scope: Option<query::ContextScope>,
+) -> std::result::Result<query::ContextResponse, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
 
     executor
-        .query(&time_filters, None, limit as usize, CursorDirection::Forward)
+        .get_context(row_id, before as usize, after as usize, scope.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn find_match(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    filters: LogFilters,
+    search: LogFilters,
+    from_row_id: Option<i64>,
+    direction: Option<CursorDirection>,
+    limit: u32,
+    highlight: Option<bool>,
+) -> std::result::Result<query::FindResponse, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
+
+    let mut response = executor
+        .find_match(
+            &filters,
+            &search,
+            from_row_id,
+            direction.unwrap_or(CursorDirection::Forward),
+            limit as usize,
+        )
+        .map_err(|e| e.to_string())?;
+    if highlight == Some(true) {
+        query::highlight_find(&search, &mut response).map_err(|e| e.to_string())?;
+    }
+    Ok(response)
+}
+
+#[tauri::command]
+async fn get_histogram(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    filters: LogFilters,
+    start_ms: Option<f64>,
+    end_ms: Option<f64>,
+    buckets: u32,
+) -> std::result::Result<query::Histogram, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
+
+    executor
+        .get_histogram(&filters, start_ms, end_ms, buckets as usize)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn get_top(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    filters: LogFilters,
+    by: query::AggregateBy,
+    start_ms: Option<f64>,
+    end_ms: Option<f64>,
+    sort: Option<query::AggregateSort>,
+    limit: Option<u32>,
+) -> std::result::Result<query::AggregateResponse, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
+
+    executor
+        .get_top(&filters, by, start_ms, end_ms, sort.unwrap_or_default(), limit.unwrap_or(20) as usize)
+        .map_err(|e| e.to_string())
+}
+
+/// Mined message templates of the rows matching `filters`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn get_templates(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    filters: LogFilters,
+    start_ms: Option<f64>,
+    end_ms: Option<f64>,
+    limit: Option<u32>,
+    samples: Option<u32>,
+) -> std::result::Result<query::TemplateResponse, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = report.executor().map_err(|e| e.to_string())?;
+
+    executor
+        .get_templates(&filters, start_ms, end_ms, limit.unwrap_or(50) as usize, samples.unwrap_or(3) as usize)
+        .map_err(|e| e.to_string())
+}
+
+/// Lifetimes of a pid (or of all pids), in start order
+#[tauri::command]
+async fn get_process_instances(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    pid: Option<i32>,
+) -> std::result::Result<Vec<index::processes::ProcessInstance>, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    report.process_instances(pid).map_err(|e| e.to_string())
+}
+
+/// Re-anchor a report's timestamps to a timezone and report date
+///
+/// For logs without `persist.sys.timezone` or a dumpstate date, whose
+/// derived anchor falls back to UTC and the current year.
+#[tauri::command]
+async fn set_time_override(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    timezone: String,
+    report_date: Option<chrono::NaiveDate>,
+) -> std::result::Result<index::reanchor::ReanchorSummary, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let time = index::reanchor::TimeOverride { timezone, report_date, applied_ms: 0 };
+    report.apply_time_override(&time).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_time_override(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+) -> std::result::Result<Option<index::reanchor::TimeOverride>, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    report.time_override().map_err(|e| e.to_string())
+}
+
+/// Re-extract `key=value` fields with the configured tag allow-list
+///
+/// Needed after changing `fieldTags` and for caches built before extraction.
+#[tauri::command]
+async fn reindex_fields(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+) -> std::result::Result<usize, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+
+    report.reindex_fields(&manager.config().field_tags).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn set_bookmark(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    row_id: i64,
+    bookmark: report::bookmarks::BookmarkInput,
+) -> std::result::Result<report::bookmarks::Bookmark, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    report.set_bookmark(row_id, &bookmark).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_bookmark(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    row_id: i64,
+) -> std::result::Result<bool, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    report.delete_bookmark(row_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_bookmarks(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+) -> std::result::Result<Vec<report::bookmarks::Bookmark>, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    report.list_bookmarks().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_query_history(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    limit: Option<u32>,
+) -> std::result::Result<Vec<report::history::HistoryEntry>, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    report.query_history(limit.unwrap_or(50) as usize).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn clear_query_history(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+) -> std::result::Result<(), String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    report.clear_query_history().map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Filter presets (global, stored in the cache root)
+// ============================================================================
+
+#[tauri::command]
+async fn list_presets() -> std::result::Result<Vec<cache::FilterPreset>, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    manager.presets().list().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn save_preset(
+    name: String,
+    description: Option<String>,
+    filters: LogFilters,
+) -> std::result::Result<cache::FilterPreset, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    manager.presets().save(&name, description, filters).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn delete_preset(name: String) -> std::result::Result<bool, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    manager.presets().delete(&name).map_err(|e| e.to_string())
+}
+
+/// Filters of a preset, recorded in the report's query history
+#[tauri::command]
+async fn apply_preset(
+    state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
+    name: String,
+) -> std::result::Result<LogFilters, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    let preset = manager.presets().get(&name).map_err(|e| e.to_string())?;
+
+    let report = get_report(&state, report_id.as_deref())?;
+    report.record_query(&preset.filters).map_err(|e| e.to_string())?;
+    Ok(preset.filters)
+}
+
+#[tauri::command]
+async fn export_presets(path: String, names: Option<Vec<String>>) -> std::result::Result<usize, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    manager
+        .presets()
+        .export_to(std::path::Path::new(&path), names.as_deref())
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn import_presets(path: String, overwrite: Option<bool>) -> std::result::Result<cache::PresetImport, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    manager
+        .presets()
+        .import_from(std::path::Path::new(&path), overwrite.unwrap_or(false))
+        .map_err(|e| e.to_string())
+}
+
+/// Exact stats event payload, sent after an estimated `get_logcat_stats`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExactStats {
+    report_id: String,
+    stats: LogcatStats,
+}
+
 #[tauri::command]
 async fn get_logcat_stats(
+    app: tauri::AppHandle,
     state: State<'_, Mutex<AppState>>,
+    report_id: Option<String>,
     filters: types::LogFilters,
 ) -> std::result::Result<LogcatStats, String> {
-    let cache_dir = state
-        .lock()
-        .map_err(|_| "State poisoned".to_string())?
-        .last_cache_dir
-        .clone()
-        .ok_or_else(|| "No cache yet. Please parse a bugreport first.".to_string())?;
+    let report = get_report(&state, report_id.as_deref())?;
+    let stats = report
+        .executor()
+        .and_then(|executor| executor.get_stats(&filters))
+        .map_err(|e| e.to_string())?;
+
+    // Estimated stats: compute the exact figures on a second connection
+    if stats.is_estimate {
+        tauri::async_runtime::spawn_blocking(move || {
+            let exact = report
+                .open_stats_executor()
+                .and_then(|executor| executor.get_stats_exact(&filters));
+
+            // Interrupted by newer filters or a failed count: keep the estimate
+            if let Ok(stats) = exact {
+                let payload = ExactStats { report_id: report.id.clone(), stats };
+                let _ = app.emit("stats://exact", payload);
+            }
+        });
+    }
+
+    Ok(stats)
+}
+
+/// Result of validating a query language string
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryValidation {
+    valid: bool,
+    /// Character position of the first syntax error
+    position: Option<usize>,
+    message: Option<String>,
+}
+
+#[tauri::command]
+async fn validate_query(query: String) -> std::result::Result<QueryValidation, String> {
+    match query::parse_query(&query) {
+        Ok(_) => Ok(QueryValidation { valid: true, position: None, message: None }),
+        Err(LogcatError::QuerySyntax { position, message }) => Ok(QueryValidation {
+            valid: false,
+            position: Some(position),
+            message: Some(message),
+        }),
+        Err(e) => Err(e.to_string()),
+    }
+}
 
-    let db_path = cache_dir.join("logcat.db");
-    let executor = query::QueryExecutor::open(&db_path).map_err(|e| e.to_string())?;
+// ============================================================================
+// Report Lifecycle API
+// ============================================================================
 
-    executor.get_stats(&filters).map_err(|e| e.to_string())
+#[tauri::command]
+async fn list_open_reports(
+    state: State<'_, Mutex<AppState>>,
+) -> std::result::Result<Vec<ReportInfo>, String> {
+    let guard = state.lock().map_err(|_| "State poisoned".to_string())?;
+    Ok(guard.reports.list())
+}
+
+#[tauri::command]
+async fn close_report(
+    state: State<'_, Mutex<AppState>>,
+    report_id: String,
+) -> std::result::Result<bool, String> {
+    let mut guard = state.lock().map_err(|_| "State poisoned".to_string())?;
+    Ok(guard.reports.close(&report_id))
 }
 
 // ============================================================================
@@ -218,6 +606,7 @@ async fn get_logcat_stats(
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ParseProgress {
+    job_id: String,
     bytes_read: u64,
     total_bytes: u64,
     rows_processed: usize,
@@ -225,56 +614,373 @@ struct ParseProgress {
     percent: f32,
 }
 
+/// Completion event payload for frontend
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ParseFinished {
+    job_id: String,
+    state: job::JobState,
+    summary: Option<ParseSummary>,
+    error: Option<String>,
+}
+
+/// Start parsing a bugreport in the background
+///
+/// Returns a job id immediately. Progress is emitted as `parse://progress`
+/// and the outcome as `parse://finished`, both tagged with the job id.
 #[tauri::command]
 async fn parse_bugreport_streaming(
     app: tauri::AppHandle,
     path: String,
     state: State<'_, Mutex<AppState>>,
-) -> std::result::Result<ParseSummary, String> {
-    use index::IndexPhase;
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<String, String> {
+    close_report_for_path(&state, &path);
 
-    let app_clone = app.clone();
+    let cache_id = cache::CacheManager::id_for_report(&path);
+    let handle = jobs.start_for_cache("parse", &cache_id).map_err(|e| e.to_string())?;
+    let job_id = handle.status().job_id;
 
-    let result = parser::parse_bugreport_streaming(&path, move |progress| {
-        let phase = match progress.phase {
-            IndexPhase::Parsing => "parsing",
-            IndexPhase::BuildingFts => "building_fts",
-            IndexPhase::Optimizing => "optimizing",
-            IndexPhase::Complete => "complete",
-        };
+    let worker_job_id = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let job_id = worker_job_id;
+        let progress_app = app.clone();
+        let progress_handle = Arc::clone(&handle);
+
+        let result = parser::parse_bugreport_streaming(&path, handle.cancel_flag(), move |progress| {
+            let status = progress_handle.update_progress(&progress);
+
+            let payload = ParseProgress {
+                job_id: status.job_id,
+                bytes_read: status.bytes_read,
+                total_bytes: status.total_bytes,
+                rows_processed: status.rows_processed,
+                phase: status.phase,
+                percent: status.percent,
+            };
+
+            let _ = progress_app.emit("parse://progress", payload);
+        });
 
-        let percent = if progress.total_bytes > 0 {
-            (progress.bytes_read as f32 / progress.total_bytes as f32) * 100.0
-        } else {
-            0.0
+        let finished = match result.and_then(|r| register_parsed_report(&app, r)) {
+            Ok(summary) => {
+                let status = handle.complete(serde_json::to_value(&summary).unwrap_or_default());
+                ParseFinished { job_id, state: status.state, summary: Some(summary), error: None }
+            }
+            Err(e) => {
+                let status = handle.fail(&e);
+                ParseFinished { job_id, state: status.state, summary: None, error: status.error }
+            }
         };
 
-        let payload = ParseProgress {
-            bytes_read: progress.bytes_read,
-            total_bytes: progress.total_bytes,
-            rows_processed: progress.rows_processed,
-            phase: phase.to_string(),
-            percent,
+        let _ = app.emit("parse://finished", &finished);
+        retire_job(&app, &finished.job_id);
+    });
+
+    Ok(job_id)
+}
+
+/// Mark a job finished once its outcome was emitted; its status stays pollable
+fn retire_job(app: &tauri::AppHandle, job_id: &str) {
+    use tauri::Manager;
+    app.state::<job::JobManager>().retire(job_id);
+}
+
+/// Register a finished parse in the report registry and build its summary
+fn register_parsed_report(app: &tauri::AppHandle, result: parser::ParseResult) -> Result<ParseSummary> {
+    use tauri::Manager;
+
+    let state = app.state::<Mutex<AppState>>();
+    let report_id = state
+        .lock()
+        .map_err(|_| LogcatError::StatePoisoned)?
+        .reports
+        .open(&result.cache_dir)?;
+
+    evict_cache(&state, &app.state::<job::JobManager>());
+
+    Ok(ParseSummary::from_result(report_id, result))
+}
+
+#[tauri::command]
+async fn cancel_parse(
+    job_id: String,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<bool, String> {
+    jobs.cancel(&job_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_parse_status(
+    job_id: String,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<job::JobStatus, String> {
+    Ok(jobs.get(&job_id).map_err(|e| e.to_string())?.status())
+}
+
+// ============================================================================
+// Export API
+// ============================================================================
+
+/// Export progress event payload
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportProgress {
+    job_id: String,
+    rows_written: usize,
+    total_rows: usize,
+    percent: f32,
+}
+
+/// Export completion event payload
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportFinished {
+    job_id: String,
+    state: job::JobState,
+    summary: Option<export::ExportSummary>,
+    error: Option<String>,
+}
+
+/// Start exporting the rows matching `options` to `path` in the background
+///
+/// Returns a job id immediately. Progress is emitted as `export://progress`
+/// and the outcome as `export://finished`. The export reads through its own
+/// connection so queries stay responsive.
+#[tauri::command]
+async fn export_logs(
+    app: tauri::AppHandle,
+    state: State<'_, Mutex<AppState>>,
+    jobs: State<'_, job::JobManager>,
+    report_id: Option<String>,
+    path: String,
+    options: export::ExportOptions,
+) -> std::result::Result<String, String> {
+    let report = get_report(&state, report_id.as_deref())?;
+    let executor = query::QueryExecutor::open(&report.db_path()).map_err(|e| e.to_string())?;
+
+    // Keeps the cache from being evicted or deleted while the export reads it
+    let handle = jobs.start_for_cache("export", &report.cache_id).map_err(|e| e.to_string())?;
+    let job_id = handle.status().job_id;
+
+    let worker_job_id = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let job_id = worker_job_id;
+        let cancel_flag = handle.cancel_flag();
+
+        let result = export::export_logs(
+            &executor,
+            std::path::Path::new(&path),
+            &options,
+            &cancel_flag,
+            |rows_written, total_rows| {
+                let status = handle.update_rows("exporting", rows_written, total_rows);
+                let payload = ExportProgress {
+                    job_id: status.job_id,
+                    rows_written,
+                    total_rows,
+                    percent: status.percent,
+                };
+                let _ = app.emit("export://progress", payload);
+            },
+        );
+
+        let finished = match result {
+            Ok(summary) => {
+                let status = handle.complete(serde_json::to_value(&summary).unwrap_or_default());
+                ExportFinished { job_id, state: status.state, summary: Some(summary), error: None }
+            }
+            Err(e) => {
+                let status = handle.fail(&e);
+                ExportFinished { job_id, state: status.state, summary: None, error: status.error }
+            }
         };
 
-        let _ = app_clone.emit("parse://progress", payload);
-    }).map_err(|e| e.to_string())?;
+        let _ = app.emit("export://finished", &finished);
+        retire_job(&app, &finished.job_id);
+    });
 
-    // Update state with cache directory
-    if let Ok(mut guard) = state.lock() {
-        guard.last_cache_dir.replace(result.cache_dir.clone());
+    Ok(job_id)
+}
+
+#[tauri::command]
+async fn cancel_export(
+    job_id: String,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<bool, String> {
+    jobs.cancel(&job_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_export_status(
+    job_id: String,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<job::JobStatus, String> {
+    Ok(jobs.get(&job_id).map_err(|e| e.to_string())?.status())
+}
+
+// ============================================================================
+// Cache Management API
+// ============================================================================
+
+/// Cache ids that must not be removed: open reports and caches being written
+fn protected_cache_ids(
+    state: &State<'_, Mutex<AppState>>,
+    jobs: &job::JobManager,
+) -> std::result::Result<Vec<String>, String> {
+    let mut ids = state
+        .lock()
+        .map_err(|_| "State poisoned".to_string())?
+        .reports
+        .cache_ids();
+    ids.extend(jobs.busy_cache_ids());
+    Ok(ids)
+}
+
+/// Evict old cached reports, keeping open reports and those a job is writing
+fn evict_cache(state: &State<'_, Mutex<AppState>>, jobs: &job::JobManager) {
+    let protected = match protected_cache_ids(state, jobs) {
+        Ok(ids) => ids,
+        Err(_) => return,
+    };
+    let keep: Vec<&str> = protected.iter().map(|s| s.as_str()).collect();
+
+    let evicted = cache::CacheManager::open_default()
+        .and_then(|m| m.enforce_quota(&keep));
+    if let Err(e) = evicted {
+        log::warn!("Cache eviction failed: {}", e);
+    }
+}
+
+#[tauri::command]
+async fn list_cached_reports() -> std::result::Result<Vec<CachedReport>, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    manager.list().map_err(|e| e.to_string())
+}
+
+/// Build the side tables an older cache lacks in the background
+///
+/// Runs outside the app state lock; the job keeps the cache from being
+/// evicted or deleted while it writes.
+fn start_backfill(
+    app: &tauri::AppHandle,
+    jobs: &job::JobManager,
+    report: Arc<report::OpenReport>,
+    cache_id: &str,
+) -> std::result::Result<(), String> {
+    let handle = jobs.start_for_cache("backfill", cache_id).map_err(|e| e.to_string())?;
+    let app = app.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        match report.backfill() {
+            Ok(_) => {
+                handle.complete(serde_json::Value::Null);
+            }
+            Err(e) => {
+                log::warn!("Backfill of {} failed: {}", report.cache_id, e);
+                handle.fail(&e);
+            }
+        }
+        retire_job(&app, &handle.status().job_id);
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn open_cached_report(
+    app: tauri::AppHandle,
+    id: String,
+    state: State<'_, Mutex<AppState>>,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<ParseSummary, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    let cache_dir = manager.report_dir(&id).map_err(|e| e.to_string())?;
+
+    if !cache_dir.join(cache::DB_FILE).exists() {
+        return Err(LogcatError::CacheNotFound(id).to_string());
+    }
+
+    let meta = manager.touch(&id).map_err(|e| e.to_string())?;
+    let report_id = open_report(&state, &cache_dir)?;
+
+    let report = get_report(&state, Some(&report_id))?;
+    if report.needs_backfill().unwrap_or(false) {
+        start_backfill(&app, &jobs, report, &id)?;
     }
 
     Ok(ParseSummary {
-        device: result.device,
-        events: result.anr_count + result.crash_count,
-        anrs: result.anr_count,
-        crashes: result.crash_count,
-        ef_total: result.index_summary.error_count + result.index_summary.fatal_count,
-        ef_recent: result.index_summary.fatal_count,
+        report_id,
+        cache_id: id,
+        device: meta.device,
+        events: meta.anr_count + meta.crash_count,
+        anrs: meta.anr_count,
+        crashes: meta.crash_count,
+        ef_total: meta.error_count + meta.fatal_count,
+        ef_recent: meta.fatal_count,
+        time_anchor: meta.time_anchor,
     })
 }
 
+#[tauri::command]
+async fn delete_cached_report(
+    id: String,
+    state: State<'_, Mutex<AppState>>,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<u64, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    let cache_dir = manager.report_dir(&id).map_err(|e| e.to_string())?;
+
+    if jobs.busy_cache_ids().contains(&id) {
+        return Err(LogcatError::CacheBusy(id).to_string());
+    }
+
+    // Close the report first so its connection does not outlive the files
+    if let Ok(mut guard) = state.lock() {
+        guard.reports.close_dir(&cache_dir);
+    }
+
+    manager.delete(&id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn clear_cache(
+    state: State<'_, Mutex<AppState>>,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<u64, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+
+    if let Ok(mut guard) = state.lock() {
+        guard.reports.close_all();
+    }
+
+    // Caches still being written by a parse are left alone
+    let busy = jobs.busy_cache_ids();
+    let keep: Vec<&str> = busy.iter().map(|s| s.as_str()).collect();
+    manager.clear(&keep).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_cache_config() -> std::result::Result<CacheConfig, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    Ok(manager.config())
+}
+
+#[tauri::command]
+async fn set_cache_config(
+    config: CacheConfig,
+    state: State<'_, Mutex<AppState>>,
+    jobs: State<'_, job::JobManager>,
+) -> std::result::Result<Vec<String>, String> {
+    let manager = cache::CacheManager::open_default().map_err(|e| e.to_string())?;
+    manager.set_config(&config).map_err(|e| e.to_string())?;
+
+    // Apply the new quota right away, keeping open and busy reports
+    let protected = protected_cache_ids(&state, &jobs)?;
+    let keep: Vec<&str> = protected.iter().map(|s| s.as_str()).collect();
+    manager.enforce_quota(&keep).map_err(|e| e.to_string())
+}
+
 // ============================================================================
 // Application Entry Point
 // ============================================================================
@@ -306,6 +1012,15 @@ pub fn run() {
             }
         })
         .manage(Mutex::new(AppState::default()))
+        .manage(job::JobManager::default())
+        .setup(|_app| {
+            // Staging dirs of parses interrupted by a previous exit
+            let removed = cache::CacheManager::open_default().and_then(|m| m.remove_stale_staging(&[]));
+            if let Err(e) = removed {
+                log::warn!("Failed to remove stale staging dirs: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // V1 API (backward compatible)
             parse_bugreport,
@@ -314,9 +1029,49 @@ pub fn run() {
             // V2 API (new)
             query_logcat_v2,
             jump_to_time,
+            jump_to,
+            get_context,
+            find_match,
+            get_histogram,
+            get_top,
+            get_templates,
+            reindex_fields,
+            get_process_instances,
+            set_time_override,
+            get_time_override,
             get_logcat_stats,
+            validate_query,
+            // Bookmarks
+            set_bookmark,
+            delete_bookmark,
+            list_bookmarks,
+            // Query history and presets
+            list_query_history,
+            clear_query_history,
+            list_presets,
+            save_preset,
+            delete_preset,
+            apply_preset,
+            export_presets,
+            import_presets,
+            // Report lifecycle
+            list_open_reports,
+            close_report,
             // Streaming API (for large files)
             parse_bugreport_streaming,
+            cancel_parse,
+            get_parse_status,
+            // Export
+            export_logs,
+            cancel_export,
+            get_export_status,
+            // Cache management
+            list_cached_reports,
+            open_cached_report,
+            delete_cached_report,
+            clear_cache,
+            get_cache_config,
+            set_cache_config,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
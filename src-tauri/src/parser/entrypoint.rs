@@ -1,13 +1,19 @@
+use crate::cache::{CacheManager, CacheMeta, DB_FILE};
 use crate::error::{LogcatError, Result};
 use crate::parser::device::extract_device_info;
-use crate::index::{IndexBuilder, IndexSummary, StreamingIndexBuilder, IndexProgress};
+use crate::index::fields::default_field_tags;
+use crate::index::{IndexBuilder, IndexSummary, StreamingIndexBuilder, IndexProgress, read_prefix};
+use crate::time::{AnchorInfo, derive_time_anchor};
 use crate::types::DeviceInfo;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use zip::read::ZipArchive;
-use dirs::home_dir;
+
+/// Bytes sampled from the start of a report for device info and time anchor
+const SAMPLE_SIZE: usize = 256 * 1024; // 256KB
 
 /// Result of parsing a bugreport
 #[derive(Debug)]
@@ -16,38 +22,78 @@ pub struct ParseResult {
     pub anr_count: usize,
     pub crash_count: usize,
     pub index_summary: IndexSummary,
+    /// How the report's timestamps were anchored
+    pub time_anchor: AnchorInfo,
     pub cache_dir: std::path::PathBuf,
+    pub cache_id: String,
 }
 
 /// Parse a bugreport file (zip or txt)
 pub fn parse_bugreport(path: &str) -> Result<ParseResult> {
-    let cache_dir = prepare_cache_dir(path)?;
-    let db_path = cache_dir.join("logcat.db");
+    build_cache(path, |cache_dir, db_path| {
+        if is_zip(path) {
+            parse_zip(path, cache_dir, db_path)
+        } else {
+            parse_txt(path, cache_dir, db_path)
+        }
+    })
+}
 
-    if is_zip(path) {
-        parse_zip(path, &cache_dir, &db_path)
-    } else {
-        parse_txt(path, &cache_dir, &db_path)
+/// Build a report's cache in a staging directory and swap it in on success
+///
+/// On cancellation or error only the staging directory is removed; an
+/// existing cache for the report (with its bookmarks, history and time
+/// override) is left as it was.
+fn build_cache<F>(path: &str, build: F) -> Result<ParseResult>
+where
+    F: FnOnce(&Path, &Path) -> Result<ParseResult>,
+{
+    let manager = CacheManager::open_default()?;
+    let staging = manager.prepare_staging_dir(path)?;
+
+    let result = build(&staging, &staging.join(DB_FILE)).and_then(|mut result| {
+        write_cache_meta(path, &result)?;
+        result.cache_dir = manager.commit_staging_dir(&staging, path)?;
+        result.cache_id = cache_id_of(&result.cache_dir);
+        Ok(result)
+    });
+
+    if result.is_err() {
+        remove_partial_cache(&staging);
     }
+
+    result
 }
 
 fn is_zip(path: &str) -> bool {
     path.to_ascii_lowercase().ends_with(".zip")
 }
 
-/// Prepare cache directory for parsed data
-fn prepare_cache_dir(report_path: &str) -> Result<std::path::PathBuf> {
-    let home = home_dir()
-        .ok_or_else(|| LogcatError::CacheNotFound("cannot find home dir".to_string()))?;
-
-    let name = Path::new(report_path)
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("report");
+/// Field extraction tag allow-list from the cache configuration
+fn configured_field_tags() -> Vec<String> {
+    CacheManager::open_default()
+        .map(|manager| manager.config().field_tags)
+        .unwrap_or_else(|_| default_field_tags())
+}
 
-    let dir = home.join(".lazy_milktea_cache").join(name);
-    std::fs::create_dir_all(&dir)?;
-    Ok(dir)
+/// Record cache metadata so the report can be listed and reopened later
+fn write_cache_meta(report_path: &str, result: &ParseResult) -> Result<()> {
+    let now = crate::cache::now_ms();
+    CacheMeta {
+        source_path: report_path.to_string(),
+        device: result.device.clone(),
+        anr_count: result.anr_count,
+        crash_count: result.crash_count,
+        total_rows: result.index_summary.total_rows,
+        error_count: result.index_summary.error_count,
+        fatal_count: result.index_summary.fatal_count,
+        min_timestamp_ms: result.index_summary.min_timestamp_ms,
+        max_timestamp_ms: result.index_summary.max_timestamp_ms,
+        time_anchor: Some(result.time_anchor.clone()),
+        created_ms: now,
+        last_opened_ms: now,
+    }
+    .save(&result.cache_dir)
 }
 
 fn parse_zip(path: &str, cache_dir: &Path, db_path: &Path) -> Result<ParseResult> {
@@ -55,28 +101,7 @@ fn parse_zip(path: &str, cache_dir: &Path, db_path: &Path) -> Result<ParseResult
     let mut archive = ZipArchive::new(file)
         .map_err(|e| LogcatError::Zip(e))?;
 
-    // Find the main bugreport txt
-    let mut chosen_index: Option<usize> = None;
-    let mut chosen_size: u64 = 0;
-
-    for i in 0..archive.len() {
-        let file = archive.by_index(i)
-            .map_err(|e| LogcatError::Zip(e))?;
-        let name = file.name().to_string();
-        let lower = name.to_ascii_lowercase();
-
-        if lower.ends_with(".txt")
-            && (lower.contains("bugreport") || lower.contains("main_entry"))
-        {
-            let size = file.size();
-            if size > chosen_size {
-                chosen_index = Some(i);
-                chosen_size = size;
-            }
-        }
-    }
-
-    let idx = chosen_index.ok_or(LogcatError::NoBugreportFound)?;
+    let idx = find_bugreport_entry(&mut archive)?;
     let mut file = archive.by_index(idx)
         .map_err(|e| LogcatError::Zip(e))?;
 
@@ -90,7 +115,11 @@ fn parse_zip(path: &str, cache_dir: &Path, db_path: &Path) -> Result<ParseResult
     let (device, anr_count, crash_count) = extract_device_info(&content);
 
     // Build logcat index
+    let anchor = derive_time_anchor(&content);
+    let time_anchor = anchor.info();
     let index_summary = IndexBuilder::new(db_path)?
+        .with_anchor(anchor)
+        .with_field_tags(configured_field_tags())
         .build_from_text(&content)?;
 
     Ok(ParseResult {
@@ -98,7 +127,9 @@ fn parse_zip(path: &str, cache_dir: &Path, db_path: &Path) -> Result<ParseResult
         anr_count,
         crash_count,
         index_summary,
+        time_anchor,
         cache_dir: cache_dir.to_path_buf(),
+        cache_id: cache_id_of(cache_dir),
     })
 }
 
@@ -111,7 +142,11 @@ fn parse_txt(path: &str, cache_dir: &Path, db_path: &Path) -> Result<ParseResult
     let (device, anr_count, crash_count) = extract_device_info(&content);
 
     // Build logcat index
+    let anchor = derive_time_anchor(&content);
+    let time_anchor = anchor.info();
     let index_summary = IndexBuilder::new(db_path)?
+        .with_anchor(anchor)
+        .with_field_tags(configured_field_tags())
         .build_from_text(&content)?;
 
     Ok(ParseResult {
@@ -119,13 +154,24 @@ fn parse_txt(path: &str, cache_dir: &Path, db_path: &Path) -> Result<ParseResult
         anr_count,
         crash_count,
         index_summary,
+        time_anchor,
         cache_dir: cache_dir.to_path_buf(),
+        cache_id: cache_id_of(cache_dir),
     })
 }
 
+/// Cache id (directory name) of a report cache directory
+fn cache_id_of(cache_dir: &Path) -> String {
+    cache_dir
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
 /// Get the cache directory path for a report
 pub fn get_cache_dir(report_path: &str) -> Result<std::path::PathBuf> {
-    prepare_cache_dir(report_path)
+    CacheManager::open_default()?.prepare_report_dir(report_path)
 }
 
 // ============================================================================
@@ -136,20 +182,31 @@ pub fn get_cache_dir(report_path: &str) -> Result<std::path::PathBuf> {
 pub type ProgressCallback = Arc<dyn Fn(IndexProgress) + Send + Sync>;
 
 /// Parse a bugreport with streaming (for large files)
+///
+/// Setting `cancel_flag` aborts the parse with `LogcatError::Cancelled`.
+/// On cancellation or error the partially built cache is removed and any
+/// previous cache of the report is kept.
 pub fn parse_bugreport_streaming<F>(
     path: &str,
+    cancel_flag: Arc<AtomicBool>,
     progress: F,
 ) -> Result<ParseResult>
 where
     F: Fn(IndexProgress) + Send + Sync + 'static,
 {
-    let cache_dir = prepare_cache_dir(path)?;
-    let db_path = cache_dir.join("logcat.db");
+    build_cache(path, |cache_dir, db_path| {
+        if is_zip(path) {
+            parse_zip_streaming(path, cache_dir, db_path, cancel_flag, progress)
+        } else {
+            parse_txt_streaming(path, cache_dir, db_path, cancel_flag, progress)
+        }
+    })
+}
 
-    if is_zip(path) {
-        parse_zip_streaming(path, &cache_dir, &db_path, progress)
-    } else {
-        parse_txt_streaming(path, &cache_dir, &db_path, progress)
+/// Remove a staging directory left incomplete by a failed or cancelled parse
+fn remove_partial_cache(cache_dir: &Path) {
+    if let Err(e) = std::fs::remove_dir_all(cache_dir) {
+        log::warn!("Failed to remove partial cache {}: {}", cache_dir.display(), e);
     }
 }
 
@@ -157,6 +214,7 @@ fn parse_txt_streaming<F>(
     path: &str,
     cache_dir: &Path,
     db_path: &Path,
+    cancel_flag: Arc<AtomicBool>,
     progress: F,
 ) -> Result<ParseResult>
 where
@@ -165,19 +223,64 @@ where
     let file = File::open(path)?;
     let file_size = file.metadata()?.len();
 
-    // Read a sample for device info (first 256KB)
-    let mut sample_reader = BufReader::new(File::open(path)?);
-    let mut sample = vec![0u8; (256 * 1024).min(file_size as usize)];
-    let bytes_read = sample_reader.read(&mut sample)?;
-    sample.truncate(bytes_read);
-    let sample_str = String::from_utf8_lossy(&sample);
-    let (device, anr_count, crash_count) = extract_device_info(&sample_str);
-    drop(sample_reader);
+    let builder = StreamingIndexBuilder::new(db_path)
+        .with_field_tags(configured_field_tags())
+        .with_cancel_flag(cancel_flag)
+        .with_progress(progress);
+
+    index_stream(builder, file, file_size, cache_dir)
+}
+
+fn parse_zip_streaming<F>(
+    path: &str,
+    cache_dir: &Path,
+    db_path: &Path,
+    cancel_flag: Arc<AtomicBool>,
+    progress: F,
+) -> Result<ParseResult>
+where
+    F: Fn(IndexProgress) + Send + Sync + 'static,
+{
+    // Count compressed bytes pulled from the archive for progress reporting
+    let counter = Arc::new(AtomicU64::new(0));
+    let file = CountingReader::new(File::open(path)?, Arc::clone(&counter));
+    let mut archive = ZipArchive::new(file).map_err(LogcatError::Zip)?;
 
-    // Build index with streaming
-    let index_summary = StreamingIndexBuilder::new(db_path)
+    let idx = find_bugreport_entry(&mut archive)?;
+
+    // Stream the entry straight into the indexer; no temp copy on disk
+    counter.store(0, Ordering::Relaxed);
+    let entry = archive.by_index(idx).map_err(LogcatError::Zip)?;
+    let compressed_size = entry.compressed_size();
+
+    let builder = StreamingIndexBuilder::new(db_path)
+        .with_field_tags(configured_field_tags())
+        .with_cancel_flag(cancel_flag)
         .with_progress(progress)
-        .build_from_file(Path::new(path))?;
+        .with_input_counter(counter);
+
+    index_stream(builder, entry, compressed_size, cache_dir)
+}
+
+/// Index a bugreport stream
+///
+/// Device info and the time anchor are taken from a buffered prefix, which
+/// is then replayed ahead of the rest of the stream.
+fn index_stream<R: Read>(
+    builder: StreamingIndexBuilder,
+    mut reader: R,
+    total_bytes: u64,
+    cache_dir: &Path,
+) -> Result<ParseResult> {
+    let prefix = read_prefix(&mut reader, SAMPLE_SIZE)?;
+    let sample_str = String::from_utf8_lossy(&prefix);
+    let (device, anr_count, crash_count) = extract_device_info(&sample_str);
+    let anchor = derive_time_anchor(&sample_str);
+    let time_anchor = anchor.info();
+
+    let index_summary = builder
+        .with_anchor(anchor)
+        .build_from_reader(std::io::Cursor::new(prefix).chain(reader), total_bytes)?;
 
     Ok(ParseResult {
         device,
@@ -190,23 +293,14 @@ where
             min_timestamp_ms: index_summary.min_timestamp_ms,
             max_timestamp_ms: index_summary.max_timestamp_ms,
         },
+        time_anchor,
         cache_dir: cache_dir.to_path_buf(),
+        cache_id: cache_id_of(cache_dir),
     })
 }
 
-fn parse_zip_streaming<F>(
-    path: &str,
-    cache_dir: &Path,
-    db_path: &Path,
-    progress: F,
-) -> Result<ParseResult>
-where
-    F: Fn(IndexProgress) + Send + Sync + 'static,
-{
-    let file = File::open(path)?;
-    let mut archive = ZipArchive::new(file).map_err(|e| LogcatError::Zip(e))?;
-
-    // Find the main bugreport txt
+/// Find the main bugreport text entry (largest matching `.txt`)
+fn find_bugreport_entry<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<usize> {
     let mut chosen_index: Option<usize> = None;
     let mut chosen_size: u64 = 0;
 
@@ -226,53 +320,105 @@ where
         }
     }
 
-    let idx = chosen_index.ok_or(LogcatError::NoBugreportFound)?;
-
-    // For zip files, we need to extract to a temp file first
-    // because ZipFile doesn't support Seek well
-    let temp_path = cache_dir.join("_temp_bugreport.txt");
-    {
-        let mut zip_file = archive.by_index(idx).map_err(|e| LogcatError::Zip(e))?;
-        let mut temp_file = File::create(&temp_path)?;
-        std::io::copy(&mut zip_file, &mut temp_file)?;
-    }
+    chosen_index.ok_or(LogcatError::NoBugreportFound)
+}
 
-    // Read sample for device info
-    let mut sample_reader = BufReader::new(File::open(&temp_path)?);
-    let mut sample = vec![0u8; (256 * 1024).min(chosen_size as usize)];
-    let bytes_read = sample_reader.read(&mut sample)?;
-    sample.truncate(bytes_read);
-    let sample_str = String::from_utf8_lossy(&sample);
-    let (device, anr_count, crash_count) = extract_device_info(&sample_str);
-    drop(sample_reader);
+/// Reader wrapper that counts bytes read from the underlying source
+struct CountingReader<R> {
+    inner: R,
+    counter: Arc<AtomicU64>,
+}
 
-    // Build index with streaming
-    let index_summary = StreamingIndexBuilder::new(db_path)
-        .with_progress(progress)
-        .build_from_file(&temp_path)?;
+impl<R> CountingReader<R> {
+    fn new(inner: R, counter: Arc<AtomicU64>) -> Self {
+        Self { inner, counter }
+    }
+}
 
-    // Clean up temp file
-    let _ = std::fs::remove_file(&temp_path);
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.counter.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
 
-    Ok(ParseResult {
-        device,
-        anr_count,
-        crash_count,
-        index_summary: IndexSummary {
-            total_rows: index_summary.total_rows,
-            error_count: index_summary.error_count,
-            fatal_count: index_summary.fatal_count,
-            min_timestamp_ms: index_summary.min_timestamp_ms,
-            max_timestamp_ms: index_summary.max_timestamp_ms,
-        },
-        cache_dir: cache_dir.to_path_buf(),
-    })
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_zip_streaming_without_temp_file() {
+        use crate::index::IndexPhase;
+        use std::io::Write;
+        use std::sync::Mutex;
+        use std::time::{SystemTime, UNIX_EPOCH};
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("test_zip_stream_{}", nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut text = String::from("== dumpstate: 2024-08-24 14:00:00\npersist.sys.timezone=Asia/Taipei\n");
+        for i in 0..5_000 {
+            text.push_str(&format!("08-24 14:22:33.{:03}  1234  5678 I Tag: message {}\n", i % 1000, i));
+        }
+
+        let zip_path = dir.join("report.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        writer
+            .start_file("bugreport-test.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(text.as_bytes()).unwrap();
+        writer.finish().unwrap();
+        let compressed_size = ZipArchive::new(File::open(&zip_path).unwrap())
+            .unwrap()
+            .by_index(0)
+            .unwrap()
+            .compressed_size();
+
+        // (bytes_read, total_bytes) of every Parsing-phase callback
+        let parsing = Arc::new(Mutex::new(Vec::new()));
+        let parsing_clone = Arc::clone(&parsing);
+
+        let cache_dir = dir.join("cache");
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        let result = parse_zip_streaming(
+            zip_path.to_str().unwrap(),
+            &cache_dir,
+            &cache_dir.join("logcat.db"),
+            Arc::new(AtomicBool::new(false)),
+            move |p| {
+                if p.phase == IndexPhase::Parsing {
+                    parsing_clone.lock().unwrap().push((p.bytes_read, p.total_bytes));
+                }
+            },
+        )
+        .unwrap();
+
+        assert_eq!(result.index_summary.total_rows, 5_000);
+        assert!(!cache_dir.join("_temp_bugreport.txt").exists());
+
+        // Parsing progress is measured in compressed bytes and reaches the
+        // entry's compressed size before the Complete event
+        let parsing = parsing.lock().unwrap();
+        assert!(compressed_size < text.len() as u64 / 2);
+        assert!(!parsing.is_empty());
+        assert!(parsing.iter().all(|&(_, total)| total == compressed_size));
+        assert!(parsing.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert_eq!(parsing.last().unwrap().0, compressed_size);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_is_zip() {
         assert!(is_zip("bugreport.zip"));
@@ -26,6 +26,7 @@ pub fn parse_logcat_line(line: &str) -> Option<LogRow> {
     let caps = LOGCAT_RE.captures(line)?;
 
     Some(LogRow {
+        id: None,
         ts: format!("{} {}", &caps["date"], &caps["time"]),
         ts_iso: None, // To be filled by caller with proper time anchor
         level: caps["level"].to_string(),
@@ -33,6 +34,9 @@ pub fn parse_logcat_line(line: &str) -> Option<LogRow> {
         pid: caps["pid"].parse().unwrap_or_default(),
         tid: caps["tid"].parse().unwrap_or_default(),
         msg: caps["msg"].to_string(),
+        matches: None,
+        repeat: None,
+        instance_id: None,
     })
 }
 
@@ -0,0 +1,160 @@
+use crate::query::process::ProcessStart;
+use crate::types::LogRow;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Words containing a digit (numbers, hex ids, hashes, uids)
+static VARIABLE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\w*\d\w*\b").unwrap());
+
+/// Placeholder for masked message parts
+pub const TEMPLATE_WILDCARD: &str = "<*>";
+
+/// Column to aggregate on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateBy {
+    Tag,
+    Pid,
+    Process,
+    Level,
+    Template,
+    /// Values of an extracted `key=value` field (`{"field": "reason"}`)
+    Field(String),
+}
+
+/// Ordering of aggregate entries
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateSort {
+    #[default]
+    Count,
+    /// Rows per minute while the entry was active
+    Rate,
+    Key,
+}
+
+/// One row of a top-N aggregation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateEntry {
+    pub key: String,
+    pub count: usize,
+    /// Fraction of all matching rows
+    pub share: f64,
+    /// First / last matching row (Unix ms)
+    pub first_ms: f64,
+    pub last_ms: f64,
+    /// `count` over the entry's active span (at least one minute)
+    pub rate_per_minute: f64,
+}
+
+/// Top-N aggregation result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateResponse {
+    pub by: AggregateBy,
+    pub entries: Vec<AggregateEntry>,
+    /// Rows matching the filters and time range
+    pub total_rows: usize,
+    /// Number of distinct keys before truncation to the top N
+    pub distinct_keys: usize,
+    /// `total_rows` over the matched time span (at least one minute)
+    pub rate_per_minute: f64,
+}
+
+/// A mined message template with its rows under the current filters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateEntry {
+    pub template_id: i64,
+    pub tag: String,
+    pub template: String,
+    /// Matching rows of this template
+    pub count: usize,
+    /// Fraction of all matching rows
+    pub share: f64,
+    pub first_ms: f64,
+    pub last_ms: f64,
+    /// Earliest matching rows
+    pub samples: Vec<LogRow>,
+}
+
+/// Templates of the rows matching the filters, most frequent first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateResponse {
+    pub entries: Vec<TemplateEntry>,
+    pub total_rows: usize,
+    /// Number of templates before truncation
+    pub distinct_templates: usize,
+}
+
+/// Mask the variable parts of a message (numbers, hex ids)
+pub fn message_template(msg: &str) -> String {
+    VARIABLE_RE.replace_all(msg, TEMPLATE_WILDCARD).into_owned()
+}
+
+/// Rows per minute over `[first_ms, last_ms]`, with spans under a minute counted as one
+pub fn rate_per_minute(count: usize, first_ms: f64, last_ms: f64) -> f64 {
+    count as f64 * 60_000.0 / (last_ms - first_ms).max(60_000.0)
+}
+
+/// Name of the process holding `pid` at `ts_unix`
+///
+/// Falls back to the first start of the pid, then to `pid <n>`.
+pub fn process_name(processes: &[ProcessStart], pid: i32, ts_unix: f64) -> String {
+    let mut starts = processes.iter().filter(|p| p.pid == pid);
+    let first = starts.clone().next();
+    starts
+        .rfind(|p| p.ts_unix <= ts_unix)
+        .or(first)
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| format!("pid {}", pid))
+}
+
+/// Sort entries in place (descending for count and rate, ascending for key)
+pub fn sort_entries(entries: &mut [AggregateEntry], sort: AggregateSort) {
+    match sort {
+        AggregateSort::Count => entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key))),
+        AggregateSort::Rate => entries.sort_by(|a, b| {
+            b.rate_per_minute
+                .total_cmp(&a.rate_per_minute)
+                .then_with(|| a.key.cmp(&b.key))
+        }),
+        AggregateSort::Key => entries.sort_by(|a, b| a.key.cmp(&b.key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_template() {
+        assert_eq!(
+            message_template("Killing 1234:com.foo/u0a56 (adj 900): empty #17"),
+            "Killing <*>:com.foo/<*> (adj <*>): empty #<*>"
+        );
+        assert_eq!(message_template("obj@1a2b3c freed 0x7f00"), "obj@<*> freed <*>");
+        assert_eq!(message_template("no numbers here"), "no numbers here");
+    }
+
+    #[test]
+    fn test_process_name_by_time() {
+        let processes = vec![
+            ProcessStart { pid: 10, name: "com.a".to_string(), ts_unix: 100.0 },
+            ProcessStart { pid: 10, name: "com.b".to_string(), ts_unix: 500.0 },
+        ];
+        assert_eq!(process_name(&processes, 10, 50.0), "com.a");
+        assert_eq!(process_name(&processes, 10, 300.0), "com.a");
+        assert_eq!(process_name(&processes, 10, 900.0), "com.b");
+        assert_eq!(process_name(&processes, 11, 900.0), "pid 11");
+    }
+
+    #[test]
+    fn test_rate_per_minute() {
+        assert_eq!(rate_per_minute(30, 0.0, 1000.0), 30.0);
+        assert_eq!(rate_per_minute(30, 0.0, 120_000.0), 15.0);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::query::aggregate::message_template;
+use crate::query::cursor::{CursorDirection, QueryCursor};
+use crate::types::{LogRow, RowRepeat};
+use serde::{Deserialize, Serialize};
+
+/// How messages are compared when folding consecutive rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollapseMode {
+    /// Identical message text
+    Exact,
+    /// Same message template (numbers and ids masked)
+    Template,
+}
+
+/// Keyset position `(id, ts_unix)` of a row
+pub type RowPosition = (i64, f64);
+
+/// Run of consecutive similar rows
+struct Run {
+    key: String,
+    /// Earliest row of the run
+    row: LogRow,
+    first: RowPosition,
+    last: RowPosition,
+    last_ts: String,
+    count: usize,
+}
+
+/// Folds rows, fed in query order, into runs of consecutive rows with the
+/// same level, tag, pid and message
+pub struct Collapser {
+    mode: CollapseMode,
+    direction: CursorDirection,
+    limit: usize,
+    runs: Vec<Run>,
+}
+
+impl Collapser {
+    pub fn new(mode: CollapseMode, direction: CursorDirection, limit: usize) -> Self {
+        Self { mode, direction, limit: limit.max(1), runs: Vec::new() }
+    }
+
+    /// Add the next row
+    ///
+    /// Returns false, dropping the row, once it would start a run past
+    /// `limit`: the last run is then known to be complete.
+    pub fn push(&mut self, row: LogRow, ts_unix: f64) -> bool {
+        let key = self.key(&row);
+        let position = (row.id.unwrap_or(0), ts_unix);
+
+        if let Some(run) = self.runs.last_mut().filter(|run| run.key == key) {
+            run.count += 1;
+            match self.direction {
+                CursorDirection::Forward => {
+                    run.last = position;
+                    run.last_ts = row.ts;
+                }
+                // Scanning back in time: the new row is the earliest so far
+                CursorDirection::Backward => {
+                    run.first = position;
+                    run.row = row;
+                }
+            }
+            return true;
+        }
+
+        if self.runs.len() == self.limit {
+            return false;
+        }
+        self.runs.push(Run {
+            key,
+            first: position,
+            last: position,
+            last_ts: row.ts.clone(),
+            row,
+            count: 1,
+        });
+        true
+    }
+
+    /// Earliest and latest positions covered by the runs, in time order
+    pub fn span(&self) -> Option<(RowPosition, RowPosition)> {
+        let (earliest, latest) = match self.direction {
+            CursorDirection::Forward => (self.runs.first()?, self.runs.last()?),
+            CursorDirection::Backward => (self.runs.last()?, self.runs.first()?),
+        };
+        Some((earliest.first, latest.last))
+    }
+
+    /// Number of rows folded into the runs
+    pub fn spanned_rows(&self) -> usize {
+        self.runs.iter().map(|run| run.count).sum()
+    }
+
+    /// One row per run, in query order; runs of several rows carry a `repeat`
+    pub fn into_rows(self, filter_hash: u64) -> Vec<LogRow> {
+        self.runs
+            .into_iter()
+            .map(|run| {
+                let mut row = run.row;
+                if run.count > 1 {
+                    let (first_id, first_ms) = run.first;
+                    row.repeat = Some(RowRepeat {
+                        count: run.count,
+                        first_ms,
+                        last_ms: run.last.1,
+                        last_id: run.last.0,
+                        last_ts: run.last_ts,
+                        // Keyset cursors are exclusive: start just before the first row
+                        expand_cursor: QueryCursor::new(
+                            first_id.saturating_sub(1),
+                            first_ms,
+                            CursorDirection::Forward,
+                            filter_hash,
+                        ),
+                    });
+                }
+                row
+            })
+            .collect()
+    }
+
+    fn key(&self, row: &LogRow) -> String {
+        let msg = match self.mode {
+            CollapseMode::Exact => row.msg.clone(),
+            CollapseMode::Template => message_template(&row.msg),
+        };
+        // Level is part of the key so a repeated line never hides an error
+        format!("{}\u{0}{}\u{0}{}\u{0}{}", row.level, row.tag, row.pid, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: i64, tag: &str, msg: &str) -> LogRow {
+        LogRow {
+            id: Some(id),
+            ts: format!("08-24 14:22:{:02}.000", id),
+            ts_iso: None,
+            level: "I".to_string(),
+            tag: tag.to_string(),
+            pid: 100,
+            tid: 100,
+            msg: msg.to_string(),
+            matches: None,
+            repeat: None,
+            instance_id: None,
+        }
+    }
+
+    #[test]
+    fn test_collapse_runs_forward() {
+        let mut collapser = Collapser::new(CollapseMode::Exact, CursorDirection::Forward, 2);
+        assert!(collapser.push(row(1, "A", "retry"), 1000.0));
+        assert!(collapser.push(row(2, "A", "retry"), 2000.0));
+        assert!(collapser.push(row(3, "A", "retry"), 3000.0));
+        assert!(collapser.push(row(4, "B", "retry"), 4000.0));
+        assert!(collapser.push(row(5, "B", "retry"), 5000.0));
+        // Third run would exceed the limit
+        assert!(!collapser.push(row(6, "A", "retry"), 6000.0));
+
+        assert_eq!(collapser.span(), Some(((1, 1000.0), (5, 5000.0))));
+        assert_eq!(collapser.spanned_rows(), 5);
+
+        let rows = collapser.into_rows(7);
+        assert_eq!(rows.len(), 2);
+        let repeat = rows[0].repeat.as_ref().unwrap();
+        assert_eq!((rows[0].id, repeat.count, repeat.last_id), (Some(1), 3, 3));
+        assert_eq!(repeat.last_ts, "08-24 14:22:03.000");
+        assert_eq!((repeat.expand_cursor.position, repeat.expand_cursor.ts_unix), (0, 1000.0));
+    }
+
+    #[test]
+    fn test_collapse_template_and_backward() {
+        let mut collapser = Collapser::new(CollapseMode::Template, CursorDirection::Backward, 10);
+        collapser.push(row(3, "Choreographer", "Skipped 31 frames"), 3000.0);
+        collapser.push(row(2, "Choreographer", "Skipped 45 frames"), 2000.0);
+        collapser.push(row(1, "Other", "Skipped 45 frames"), 1000.0);
+
+        assert_eq!(collapser.span(), Some(((1, 1000.0), (3, 3000.0))));
+        let rows = collapser.into_rows(0);
+        // The run's row is its earliest, whichever way it was scanned
+        assert_eq!(rows[0].id, Some(2));
+        assert_eq!(rows[0].repeat.as_ref().unwrap().last_id, 3);
+        assert!(rows[1].repeat.is_none());
+
+        let mut exact = Collapser::new(CollapseMode::Exact, CursorDirection::Forward, 10);
+        exact.push(row(1, "Choreographer", "Skipped 31 frames"), 1000.0);
+        exact.push(row(2, "Choreographer", "Skipped 45 frames"), 2000.0);
+        assert_eq!(exact.into_rows(0).len(), 2);
+    }
+}
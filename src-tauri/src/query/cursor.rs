@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use crate::types::LogRow;
+use crate::types::{LogFilters, LogRow};
 
 /// Direction for cursor-based queries
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
@@ -11,11 +11,17 @@ pub enum CursorDirection {
 }
 
 /// Cursor for paginated queries
+///
+/// Keyset position on `(ts_unix, id)`, matching the `ORDER BY` of queries,
+/// so pages stay stable under any filter.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryCursor {
-    /// Row ID (SQLite rowid) at current position
+    /// Row ID (SQLite rowid) of the boundary row
     pub position: i64,
+    /// Timestamp (Unix ms) of the boundary row
+    #[serde(default)]
+    pub ts_unix: f64,
     /// Query direction
     pub direction: CursorDirection,
     /// Hash of filter conditions (to validate cursor)
@@ -23,9 +29,10 @@ pub struct QueryCursor {
 }
 
 impl QueryCursor {
-    pub fn new(position: i64, direction: CursorDirection, filter_hash: u64) -> Self {
+    pub fn new(position: i64, ts_unix: f64, direction: CursorDirection, filter_hash: u64) -> Self {
         Self {
             position,
+            ts_unix,
             direction,
             filter_hash,
         }
@@ -35,6 +42,7 @@ impl QueryCursor {
     pub fn start(filter_hash: u64) -> Self {
         Self {
             position: 0,
+            ts_unix: f64::MIN,
             direction: CursorDirection::Forward,
             filter_hash,
         }
@@ -75,7 +83,96 @@ impl Default for QueryResponse {
     }
 }
 
+/// Rows surrounding an anchor row for `get_context`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextScope {
+    /// All rows
+    #[default]
+    All,
+    /// Rows of the anchor's pid
+    Pid,
+    /// Rows of the anchor's tid
+    Tid,
+}
+
+/// Response for context queries (`grep -C` style, filters off)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextResponse {
+    /// Rows before the anchor, the anchor, then rows after it
+    pub rows: Vec<LogRow>,
+    /// Index of the anchor row in `rows`
+    pub anchor_index: usize,
+    /// Cursor for more rows after the context (forward)
+    pub next_cursor: Option<QueryCursor>,
+    /// Cursor for more rows before the context (backward)
+    pub prev_cursor: Option<QueryCursor>,
+    pub has_more_next: bool,
+    pub has_more_prev: bool,
+    /// Filters selecting the scope; page further with these and the cursors
+    pub filters: LogFilters,
+}
+
+/// Window of the filtered view centred on a jump target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JumpResponse {
+    #[serde(flatten)]
+    pub page: QueryResponse,
+    /// Index of the target row in `rows`
+    pub target_index: usize,
+    /// Resolved target time (Unix ms)
+    pub target_ms: f64,
+}
+
+/// Result of a find next / previous over the whole filtered view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindResponse {
+    /// Id of the matching row (`None` when nothing matches)
+    pub row_id: Option<i64>,
+    /// 1-based position of the match among all matches
+    pub ordinal: usize,
+    pub total_matches: usize,
+    /// Search wrapped around the end (or start) of the view
+    pub wrapped: bool,
+    /// Page of the filtered view starting at the matching row
+    pub page: Option<QueryResponse>,
+}
+
+/// One time bucket of a histogram
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramBucket {
+    /// Bucket start (Unix ms, inclusive)
+    pub start_ms: f64,
+    /// Rows matching the filters in this bucket
+    pub total: usize,
+    pub level_counts: LevelCounts,
+    /// Crash / ANR markers (independent of filters)
+    pub crashes: usize,
+    pub anrs: usize,
+}
+
+/// Time-bucketed row counts for density plots and the minimap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Histogram {
+    /// Range start (Unix ms)
+    pub start_ms: f64,
+    /// Range end (Unix ms, inclusive)
+    pub end_ms: f64,
+    /// Width of each bucket (ms)
+    pub bucket_ms: f64,
+    pub buckets: Vec<HistogramBucket>,
+    /// Largest bucket total, for scaling
+    pub max_total: usize,
+}
+
 /// Statistics about logcat data
+///
+/// Everything except `total_rows` is computed under the active filters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LogcatStats {
@@ -83,16 +180,20 @@ pub struct LogcatStats {
     pub total_rows: usize,
     /// Rows matching current filter
     pub filtered_rows: Option<usize>,
-    /// Earliest timestamp (Unix ms)
+    /// Earliest matching timestamp (Unix ms)
     pub min_timestamp_ms: Option<i64>,
-    /// Latest timestamp (Unix ms)
+    /// Latest matching timestamp (Unix ms)
     pub max_timestamp_ms: Option<i64>,
-    /// Earliest timestamp display string (device local time)
+    /// Earliest matching timestamp display string (device local time)
     pub min_ts_display: Option<String>,
-    /// Latest timestamp display string (device local time)
+    /// Latest matching timestamp display string (device local time)
     pub max_ts_display: Option<String>,
     /// Count by log level
     pub level_counts: LevelCounts,
+    /// Whether counts are extrapolated from a sample (exact figures follow)
+    pub is_estimate: bool,
+    /// Hash of the filters these stats were computed for
+    pub filter_hash: u64,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -106,13 +207,41 @@ pub struct LevelCounts {
     pub fatal: usize,
 }
 
+impl LevelCounts {
+    /// Add `count` rows of a level letter
+    pub fn add(&mut self, level: &str, count: usize) {
+        match level {
+            "V" => self.verbose += count,
+            "D" => self.debug += count,
+            "I" => self.info += count,
+            "W" => self.warning += count,
+            "E" => self.error += count,
+            "F" => self.fatal += count,
+            _ => {}
+        }
+    }
+
+    /// Counts multiplied by `factor` (for sample extrapolation)
+    pub fn scaled(&self, factor: f64) -> Self {
+        let scale = |n: usize| (n as f64 * factor).round() as usize;
+        Self {
+            verbose: scale(self.verbose),
+            debug: scale(self.debug),
+            info: scale(self.info),
+            warning: scale(self.warning),
+            error: scale(self.error),
+            fatal: scale(self.fatal),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_cursor_serialization() {
-        let cursor = QueryCursor::new(100, CursorDirection::Forward, 12345);
+        let cursor = QueryCursor::new(100, 1724487753000.0, CursorDirection::Forward, 12345);
         let json = serde_json::to_string(&cursor).unwrap();
         assert!(json.contains("\"position\":100"));
         assert!(json.contains("\"direction\":\"forward\""));
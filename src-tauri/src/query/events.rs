@@ -0,0 +1,56 @@
+use crate::error::{LogcatError, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// Kind of a notable log event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Crash,
+    Anr,
+}
+
+/// A crash or ANR found in the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEvent {
+    pub kind: EventKind,
+    pub row_id: i64,
+    /// Timestamp (Unix ms)
+    pub ts_unix: f64,
+    pub ts: String,
+    pub pid: i32,
+    pub msg: String,
+}
+
+/// Crash / ANR header rows (all matched through `idx_tag`)
+const EVENTS_SQL: &str = "
+    SELECT id, ts_unix, ts_display, pid, tag, msg FROM logs
+    WHERE (tag = 'AndroidRuntime' AND msg LIKE 'FATAL EXCEPTION%')
+       OR (tag = 'libc' AND msg LIKE 'Fatal signal%')
+       OR (tag = 'ActivityManager' AND msg LIKE 'ANR in %')
+    ORDER BY ts_unix, id";
+
+/// Load crash and ANR events in time order
+pub fn load_events(conn: &Connection) -> Result<Vec<LogEvent>> {
+    let mut stmt = conn
+        .prepare(EVENTS_SQL)
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let tag: String = row.get(4)?;
+            Ok(LogEvent {
+                kind: if tag == "ActivityManager" { EventKind::Anr } else { EventKind::Crash },
+                row_id: row.get(0)?,
+                ts_unix: row.get(1)?,
+                ts: row.get(2)?,
+                pid: row.get(3)?,
+                msg: row.get(5)?,
+            })
+        })
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
@@ -1,13 +1,79 @@
 use crate::error::{LogcatError, Result};
-use crate::query::cursor::{QueryCursor, QueryResponse, CursorDirection, LogcatStats, LevelCounts};
-use crate::query::filter::{compile_user_regex, plain_text_contains};
+use crate::query::aggregate::{
+    process_name, rate_per_minute, sort_entries, AggregateBy, AggregateEntry, AggregateResponse, AggregateSort,
+    TemplateEntry, TemplateResponse,
+};
+use crate::query::cursor::{
+    ContextResponse, ContextScope, CursorDirection, FindResponse, Histogram, HistogramBucket, JumpResponse, LevelCounts,
+    LogcatStats, QueryCursor, QueryResponse,
+};
+use crate::query::collapse::{CollapseMode, Collapser, RowPosition};
+use crate::query::events::{load_events, EventKind, LogEvent};
+use crate::query::jump::{parse_jump_expr, JumpTarget};
+use crate::query::filter::register_sql_functions;
+use crate::query::lang::{compile_expr, parse_query, QueryContext};
+use crate::query::process::load_process_starts;
+use crate::query::sql::{build_filter_sql, FilterSql, SqlOptions};
+use crate::index::templates::{load_templates, require_templates};
+use crate::index::TRIGRAM_FTS_VERSION;
 use crate::types::{LogFilters, LogRow};
-use rusqlite::Connection;
+use rusqlite::types::Value;
+use once_cell::unsync::OnceCell;
+use rusqlite::{Connection, InterruptHandle, OptionalExtension};
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::path::Path;
 
+/// Columns selected for `LogRow` results (plus `ts_unix` for cursors)
+const ROW_COLUMNS: &str = "id, ts_display, ts_iso, level, tag, pid, tid, msg, ts_unix, instance_id";
+
+/// Maximum rows returned on each side of a context anchor
+const MAX_CONTEXT_ROWS: usize = 5000;
+
+/// Rows sampled when estimating filtered stats
+const SAMPLE_ROWS: usize = 64 * 1024;
+
+/// Number of id windows the sample is spread over
+const SAMPLE_WINDOWS: usize = 32;
+
+/// Databases up to this size always get exact filtered stats
+const ESTIMATE_MIN_ROWS: usize = 1_000_000;
+
+/// Upper bound on histogram buckets per request
+const MAX_HISTOGRAM_BUCKETS: usize = 10_000;
+
+/// Upper bound on entries returned by `get_top`
+const MAX_TOP_ENTRIES: usize = 1000;
+
+/// Upper bound on sample rows per template in `get_templates`
+const MAX_TEMPLATE_SAMPLES: usize = 20;
+
+/// Rows fetched per batch while folding a collapsed page
+const COLLAPSE_BATCH_ROWS: usize = 2000;
+
+/// Where the last page served sits in its filtered view
+#[derive(Clone, Copy)]
+struct PagePosition {
+    filter_hash: u64,
+    first: RowPosition,
+    last: RowPosition,
+    /// Matching rows before `first`
+    page_start: usize,
+    spanned_rows: usize,
+}
+
 /// Query executor for SQLite-based logcat index
 pub struct QueryExecutor {
     conn: Connection,
+    sql_options: SqlOptions,
+    /// Filtered row count for the last filter hash
+    count_cache: Cell<Option<(u64, usize)>>,
+    /// Position of the last page, so the adjacent page needs no count
+    position_cache: Cell<Option<PagePosition>>,
+    /// Report data for query language compilation, loaded on first use
+    query_context: OnceCell<QueryContext>,
+    /// Crash / ANR events, loaded on first use
+    events: OnceCell<Vec<LogEvent>>,
 }
 
 impl QueryExecutor {
@@ -15,10 +81,81 @@ impl QueryExecutor {
     pub fn open(db_path: &Path) -> Result<Self> {
         let conn = Connection::open(db_path)
             .map_err(|e| LogcatError::Database(e.to_string()))?;
-        Ok(Self { conn })
+        register_sql_functions(&conn)?;
+
+        // Caches built before the trigram tokenizer only support token MATCH
+        let schema_version: i32 = conn
+            .query_row("PRAGMA user_version", [], |r| r.get(0))
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+        let sql_options = SqlOptions {
+            trigram_fts: schema_version >= TRIGRAM_FTS_VERSION,
+        };
+
+        Ok(Self {
+            conn,
+            sql_options,
+            count_cache: Cell::new(None),
+            position_cache: Cell::new(None),
+            query_context: OnceCell::new(),
+            events: OnceCell::new(),
+        })
+    }
+
+    /// Compile `filters` (including the query language) to SQL conditions
+    fn filter_sql(&self, filters: &LogFilters) -> Result<FilterSql> {
+        let mut filter_sql = build_filter_sql(filters, self.sql_options)?;
+
+        if let Some(expr) = filters.query.as_deref().map(parse_query).transpose()?.flatten() {
+            let (condition, params) = compile_expr(&expr, self.query_context()?);
+            filter_sql.push(condition, params);
+        }
+
+        Ok(filter_sql)
+    }
+
+    fn query_context(&self) -> Result<&QueryContext> {
+        self.query_context.get_or_try_init(|| {
+            let log_end_ms: Option<f64> = self.conn
+                .query_row("SELECT MAX(ts_unix) FROM logs", [], |r| r.get(0))
+                .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+            Ok(QueryContext {
+                log_end_ms,
+                processes: load_process_starts(&self.conn)?,
+                trigram_fts: self.sql_options.trigram_fts,
+            })
+        })
+    }
+
+    /// Underlying connection, for report data stored next to `logs`
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Drop cached counts after report data used by filters changed
+    pub fn invalidate_counts(&self) {
+        self.count_cache.set(None);
+        self.position_cache.set(None);
+    }
+
+    /// Drop everything derived from timestamps after rows were re-anchored
+    pub fn invalidate_timestamps(&mut self) {
+        self.count_cache.set(None);
+        self.position_cache.set(None);
+        self.query_context.take();
+        self.events.take();
+    }
+
+    fn events(&self) -> Result<&[LogEvent]> {
+        self.events
+            .get_or_try_init(|| load_events(&self.conn))
+            .map(Vec::as_slice)
     }
 
     /// Execute cursor-based query
+    ///
+    /// Forward pages are returned in ascending `(ts_unix, id)` order, backward
+    /// pages in descending order starting just before the cursor row.
     pub fn query(
         &self,
         filters: &LogFilters,
@@ -37,125 +174,319 @@ impl QueryExecutor {
             }
         }
 
+        let filter_sql = self.filter_sql(filters)?;
+
         // Build and execute query
-        let rows = self.execute_query(filters, cursor, limit, direction)?;
+        let page = self.execute_query(&filter_sql, cursor, limit, direction)?;
+        self.page_response(&filter_sql, filter_hash, cursor, page, direction)
+    }
+
+    /// Cursor-based query folding runs of similar consecutive rows
+    ///
+    /// Returns up to `limit` rows, each standing for a run; runs of several
+    /// rows carry a `repeat` with the run's extent and a cursor to expand it.
+    /// Page cursors sit on run boundaries, so runs never straddle pages.
+    pub fn query_collapsed(
+        &self,
+        filters: &LogFilters,
+        cursor: Option<&QueryCursor>,
+        limit: usize,
+        direction: CursorDirection,
+        mode: CollapseMode,
+    ) -> Result<QueryResponse> {
+        let filter_hash = compute_filter_hash(filters);
+        let filter_sql = self.filter_sql(filters)?;
+
+        // A run only ends at the first row of the next one, so keep reading
+        // until a row past the last run turns up
+        let mut collapser = Collapser::new(mode, direction, limit);
+        let mut batch_cursor = cursor.cloned();
+        loop {
+            let batch = self.execute_query(&filter_sql, batch_cursor.as_ref(), COLLAPSE_BATCH_ROWS, direction)?;
+            let exhausted = batch.len() < COLLAPSE_BATCH_ROWS;
+            batch_cursor = batch
+                .last()
+                .map(|(row, ts_unix)| QueryCursor::new(row.id.unwrap_or(0), *ts_unix, direction, filter_hash));
+
+            let complete = !batch.into_iter().all(|(row, ts_unix)| collapser.push(row, ts_unix));
+            if complete || exhausted {
+                break;
+            }
+        }
 
-        // Build response
-        let has_more = rows.len() >= limit;
+        let span = collapser.span();
+        let spanned_rows = collapser.spanned_rows();
+        let rows = collapser.into_rows(filter_hash);
+        self.window_response(&filter_sql, filter_hash, cursor, rows, span, spanned_rows, direction)
+    }
 
-        // Get last row id for next cursor
-        let last_id = if !rows.is_empty() {
-            // Query the max id from our result set
-            cursor.map(|c| c.position).unwrap_or(0) + rows.len() as i64
-        } else {
-            cursor.map(|c| c.position).unwrap_or(0)
+    /// Wrap a page (in `direction` order) with cursors and position info
+    fn page_response(
+        &self,
+        filter_sql: &FilterSql,
+        filter_hash: u64,
+        cursor: Option<&QueryCursor>,
+        page: Vec<(LogRow, f64)>,
+        direction: CursorDirection,
+    ) -> Result<QueryResponse> {
+        // Earliest / latest rows of the page in time order
+        let (earliest, latest) = match direction {
+            CursorDirection::Forward => (page.first(), page.last()),
+            CursorDirection::Backward => (page.last(), page.first()),
         };
+        let span = earliest.zip(latest).map(|((first, first_ts), (last, last_ts))| {
+            ((first.id.unwrap_or(0), *first_ts), (last.id.unwrap_or(0), *last_ts))
+        });
 
-        let first_id = cursor.map(|c| c.position).unwrap_or(0);
+        let rows: Vec<LogRow> = page.into_iter().map(|(row, _)| row).collect();
+        let spanned_rows = rows.len();
+        self.window_response(filter_sql, filter_hash, cursor, rows, span, spanned_rows, direction)
+    }
 
-        Ok(QueryResponse {
-            rows,
-            next_cursor: if has_more {
-                Some(QueryCursor::new(last_id, CursorDirection::Forward, filter_hash))
-            } else {
-                None
+    /// Wrap rows covering the `span` positions (`spanned_rows` matching rows)
+    ///
+    /// `cursor` is the one the page was read from; when it continues the
+    /// previous page the page start follows from that page's position,
+    /// otherwise it costs a filtered count.
+    #[allow(clippy::too_many_arguments)]
+    fn window_response(
+        &self,
+        filter_sql: &FilterSql,
+        filter_hash: u64,
+        cursor: Option<&QueryCursor>,
+        rows: Vec<LogRow>,
+        span: Option<(RowPosition, RowPosition)>,
+        spanned_rows: usize,
+        direction: CursorDirection,
+    ) -> Result<QueryResponse> {
+        let total = self.filtered_count(filter_sql, filter_hash)?;
+
+        // Number of matching rows before this page
+        let page_start = match span {
+            Some(((id, ts_unix), _)) => match self.adjacent_page_start(filter_hash, cursor, spanned_rows) {
+                Some(start) => start,
+                None => self.count_before(filter_sql, ts_unix, id)?,
+            },
+            None => match direction {
+                CursorDirection::Forward => total,
+                CursorDirection::Backward => 0,
             },
-            prev_cursor: if first_id > 0 {
-                Some(QueryCursor::new(first_id, CursorDirection::Backward, filter_hash))
+        };
+        if let Some((first, last)) = span {
+            self.position_cache.set(Some(PagePosition {
+                filter_hash,
+                first,
+                last,
+                page_start,
+                spanned_rows,
+            }));
+        }
+
+        let next_cursor = span.map(|(_, (id, ts_unix))| {
+            QueryCursor::new(id, ts_unix, CursorDirection::Forward, filter_hash)
+        });
+        let prev_cursor = span.map(|((id, ts_unix), _)| {
+            QueryCursor::new(id, ts_unix, CursorDirection::Backward, filter_hash)
+        });
+
+        Ok(QueryResponse {
+            has_more_next: page_start + spanned_rows < total,
+            has_more_prev: page_start > 0,
+            estimated_total: Some(total),
+            position_ratio: if total == 0 {
+                0.0
             } else {
-                None
+                page_start as f32 / total as f32
             },
-            has_more_next: has_more && matches!(direction, CursorDirection::Forward),
-            has_more_prev: cursor.is_some() && first_id > 0,
-            estimated_total: None,
-            position_ratio: 0.0,
+            rows,
+            next_cursor,
+            prev_cursor,
         })
     }
 
-    fn execute_query(
-        &self,
-        filters: &LogFilters,
-        cursor: Option<&QueryCursor>,
-        limit: usize,
-        direction: CursorDirection,
-    ) -> Result<Vec<LogRow>> {
-        // Build WHERE conditions with parameterized queries
-        let mut conditions: Vec<String> = Vec::new();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        // Level filter (parameterized)
-        if let Some(ref levels) = filters.levels {
-            if !levels.is_empty() {
-                let placeholders: Vec<&str> = levels.iter().map(|_| "?").collect();
-                conditions.push(format!("level IN ({})", placeholders.join(",")));
-                for level in levels {
-                    params.push(Box::new(level.clone()));
-                }
-            }
+    /// Start of a page read from `cursor` if it continues the last page
+    fn adjacent_page_start(&self, filter_hash: u64, cursor: Option<&QueryCursor>, spanned_rows: usize) -> Option<usize> {
+        let last = self.position_cache.get().filter(|p| p.filter_hash == filter_hash)?;
+        let cursor = cursor?;
+        let at = (cursor.position, cursor.ts_unix);
+        match cursor.direction {
+            CursorDirection::Forward if at == last.last => Some(last.page_start + last.spanned_rows),
+            CursorDirection::Backward if at == last.first => last.page_start.checked_sub(spanned_rows),
+            _ => None,
         }
+    }
 
-        // Tag filter (parameterized substring match, supports OR with |)
-        if let Some(ref tag) = filters.tag {
-            let tags: Vec<&str> = tag.split('|').map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
-            if tags.len() == 1 {
-                conditions.push("tag LIKE ?".to_string());
-                params.push(Box::new(format!("%{}%", tags[0])));
-            } else if tags.len() > 1 {
-                let placeholders: Vec<&str> = tags.iter().map(|_| "tag LIKE ?").collect();
-                conditions.push(format!("({})", placeholders.join(" OR ")));
-                for t in tags {
-                    params.push(Box::new(format!("%{}%", t)));
-                }
-            }
-        }
+    /// Window of the `filters` view centred on `target`
+    ///
+    /// About half of `limit` rows come before the target. The target row is
+    /// the first matching row at or after the target position, so a row
+    /// hidden by `filters` lands on the next visible one.
+    pub fn jump(&self, filters: &LogFilters, target: &JumpTarget, limit: usize) -> Result<JumpResponse> {
+        let (ts_unix, row_id) = self.resolve_jump_target(target)?;
+        let filter_hash = compute_filter_hash(filters);
+        let filter_sql = self.filter_sql(filters)?;
 
-        // PID filter (parameterized)
-        if let Some(pid) = filters.pid {
-            conditions.push("pid = ?".to_string());
-            params.push(Box::new(pid));
-        }
+        // Keyset cursors are exclusive: the backward page ends just before
+        // the target and the forward page starts at it
+        let before = limit / 2;
+        let backward = QueryCursor::new(row_id, ts_unix, CursorDirection::Backward, filter_hash);
+        let forward = QueryCursor::new(row_id.saturating_sub(1), ts_unix, CursorDirection::Forward, filter_hash);
 
-        // TID filter (parameterized)
-        if let Some(tid) = filters.tid {
-            conditions.push("tid = ?".to_string());
-            params.push(Box::new(tid));
-        }
+        let mut page = self.execute_query(&filter_sql, Some(&backward), before, CursorDirection::Backward)?;
+        page.reverse();
+        let target_index = page.len();
+        page.extend(self.execute_query(&filter_sql, Some(&forward), (limit - before).max(1), CursorDirection::Forward)?);
 
-        // Time range filter (parameterized)
-        if let Some(ref ts_from) = filters.ts_from {
-            if let Ok(ms) = crate::time::iso_ts_key_ms(ts_from) {
-                conditions.push("ts_unix >= ?".to_string());
-                params.push(Box::new(ms as f64));
-            }
-        }
+        Ok(JumpResponse {
+            target_index: target_index.min(page.len().saturating_sub(1)),
+            target_ms: ts_unix,
+            page: self.page_response(&filter_sql, filter_hash, None, page, CursorDirection::Forward)?,
+        })
+    }
 
-        if let Some(ref ts_to) = filters.ts_to {
-            if let Ok(ms) = crate::time::iso_ts_key_ms(ts_to) {
-                conditions.push("ts_unix <= ?".to_string());
-                params.push(Box::new(ms as f64));
+    /// Keyset position `(ts_unix, id)` of a jump target
+    ///
+    /// Time targets use `i64::MIN` as id so they sort before every row at
+    /// that time.
+    fn resolve_jump_target(&self, target: &JumpTarget) -> Result<(f64, i64)> {
+        match target {
+            JumpTarget::Time { time } => {
+                let ms = crate::time::iso_ts_key_ms(time).map_err(|e| LogcatError::TimeConversion {
+                    input: time.clone(),
+                    reason: e.to_string(),
+                })?;
+                Ok((ms as f64, i64::MIN))
             }
-        }
-
-        // Cursor position (parameterized)
-        if let Some(c) = cursor {
-            match direction {
-                CursorDirection::Forward => {
-                    conditions.push("id > ?".to_string());
-                    params.push(Box::new(c.position));
-                }
-                CursorDirection::Backward => {
-                    conditions.push("id < ?".to_string());
-                    params.push(Box::new(c.position));
+            JumpTarget::Row { row_id } => {
+                let (_, ts_unix) = self.fetch_row(*row_id)?;
+                Ok((ts_unix, *row_id))
+            }
+            JumpTarget::Event { event, number, offset_ms } => {
+                let found = self
+                    .events()?
+                    .iter()
+                    .filter(|e| e.kind == *event)
+                    .nth(number.saturating_sub(1))
+                    .ok_or_else(|| {
+                        let name = match event {
+                            EventKind::Crash => "crash",
+                            EventKind::Anr => "ANR",
+                        };
+                        LogcatError::InvalidFilter(format!("No {} #{} in this log", name, number))
+                    })?;
+                if *offset_ms == 0.0 {
+                    Ok((found.ts_unix, found.row_id))
+                } else {
+                    Ok((found.ts_unix + offset_ms, i64::MIN))
                 }
             }
+            JumpTarget::Expr { text } => match parse_jump_expr(text)? {
+                JumpTarget::Expr { .. } => unreachable!("parse_jump_expr never returns Expr"),
+                resolved => self.resolve_jump_target(&resolved),
+            },
         }
+    }
+
+    /// Rows around `row_id` with filters off, optionally limited to its pid/tid
+    pub fn get_context(
+        &self,
+        row_id: i64,
+        before: usize,
+        after: usize,
+        scope: ContextScope,
+    ) -> Result<ContextResponse> {
+        let (anchor, ts_unix) = self.fetch_row(row_id)?;
 
-        // Build SQL
-        let where_clause = if conditions.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
+        let filters = match scope {
+            ContextScope::All => LogFilters::default(),
+            ContextScope::Pid => LogFilters {
+                pids: Some(vec![anchor.pid]),
+                ..Default::default()
+            },
+            ContextScope::Tid => LogFilters {
+                tids: Some(vec![anchor.tid]),
+                ..Default::default()
+            },
         };
+        let filter_hash = compute_filter_hash(&filters);
+        let filter_sql = self.filter_sql(&filters)?;
+
+        let backward = QueryCursor::new(row_id, ts_unix, CursorDirection::Backward, filter_hash);
+        let forward = QueryCursor::new(row_id, ts_unix, CursorDirection::Forward, filter_hash);
+
+        let mut rows = self.execute_query(
+            &filter_sql,
+            Some(&backward),
+            before.min(MAX_CONTEXT_ROWS),
+            CursorDirection::Backward,
+        )?;
+        rows.reverse();
+        let anchor_index = rows.len();
+        rows.push((anchor, ts_unix));
+        rows.extend(self.execute_query(
+            &filter_sql,
+            Some(&forward),
+            after.min(MAX_CONTEXT_ROWS),
+            CursorDirection::Forward,
+        )?);
+
+        let total = self.filtered_count(&filter_sql, filter_hash)?;
+        let (first, first_ts) = &rows[0];
+        let page_start = self.count_before(&filter_sql, *first_ts, first.id.unwrap_or(0))?;
+
+        let cursor_at = |(row, ts_unix): &(LogRow, f64), direction| {
+            QueryCursor::new(row.id.unwrap_or(0), *ts_unix, direction, filter_hash)
+        };
+        let prev_cursor = rows.first().map(|r| cursor_at(r, CursorDirection::Backward));
+        let next_cursor = rows.last().map(|r| cursor_at(r, CursorDirection::Forward));
+
+        Ok(ContextResponse {
+            has_more_prev: page_start > 0,
+            has_more_next: page_start + rows.len() < total,
+            rows: rows.into_iter().map(|(row, _)| row).collect(),
+            anchor_index,
+            next_cursor,
+            prev_cursor,
+            filters,
+        })
+    }
+
+    /// Offset-based page for the legacy commands
+    pub fn query_page(&self, filters: &LogFilters, offset: usize, limit: usize) -> Result<Vec<LogRow>> {
+        let mut filter_sql = self.filter_sql(filters)?;
+
+        let sql = format!(
+            "SELECT {} FROM logs {} ORDER BY ts_unix ASC, id ASC LIMIT ? OFFSET ?",
+            ROW_COLUMNS,
+            filter_sql.where_clause(),
+        );
+        filter_sql.params.push(Value::Integer(limit as i64));
+        filter_sql.params.push(Value::Integer(offset as i64));
+
+        let rows = self.fetch_rows(&sql, &filter_sql)?;
+        Ok(rows.into_iter().map(|(row, _)| row).collect())
+    }
+
+    fn execute_query(
+        &self,
+        filter_sql: &FilterSql,
+        cursor: Option<&QueryCursor>,
+        limit: usize,
+        direction: CursorDirection,
+    ) -> Result<Vec<(LogRow, f64)>> {
+        let mut filter_sql = filter_sql.clone();
+
+        // Keyset position on (ts_unix, id), matching the ORDER BY
+        if let Some(c) = cursor {
+            let op = match direction {
+                CursorDirection::Forward => ">",
+                CursorDirection::Backward => "<",
+            };
+            filter_sql.push(
+                format!("(ts_unix {op} ? OR (ts_unix = ? AND id {op} ?))"),
+                [Value::Real(c.ts_unix), Value::Real(c.ts_unix), Value::Integer(c.position)],
+            );
+        }
 
         let order = match direction {
             CursorDirection::Forward => "ASC",
@@ -163,160 +494,660 @@ impl QueryExecutor {
         };
 
         let sql = format!(
-            "SELECT id, ts_display, ts_iso, level, tag, pid, tid, msg FROM logs {} ORDER BY ts_unix {}, id {} LIMIT ?",
-            where_clause,
+            "SELECT {} FROM logs {} ORDER BY ts_unix {}, id {} LIMIT ?",
+            ROW_COLUMNS,
+            filter_sql.where_clause(),
             order,
             order,
         );
 
         // Add limit as the last parameter
-        params.push(Box::new(limit as i64));
+        filter_sql.params.push(Value::Integer(limit as i64));
 
-        let mut stmt = self.conn.prepare(&sql)
+        self.fetch_rows(&sql, &filter_sql)
+    }
+
+    fn fetch_row(&self, row_id: i64) -> Result<(LogRow, f64)> {
+        let mut id_sql = FilterSql::default();
+        id_sql.push("id = ?", [Value::Integer(row_id)]);
+        let sql = format!("SELECT {} FROM logs {}", ROW_COLUMNS, id_sql.where_clause());
+        self.fetch_rows(&sql, &id_sql)?
+            .pop()
+            .ok_or(LogcatError::RowNotFound(row_id))
+    }
+
+    fn fetch_rows(&self, sql: &str, filter_sql: &FilterSql) -> Result<Vec<(LogRow, f64)>> {
+        let mut stmt = self.conn.prepare(sql)
             .map_err(|e| LogcatError::Database(e.to_string()))?;
 
-        // Convert params to references for rusqlite
-        let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-        let rows: Vec<LogRow> = stmt
-            .query_map(param_refs.as_slice(), |row| {
-                Ok(LogRow {
-                    ts: row.get(1)?,
-                    ts_iso: row.get(2)?,
-                    level: row.get(3)?,
-                    tag: row.get(4)?,
-                    pid: row.get(5)?,
-                    tid: row.get(6)?,
-                    msg: row.get(7)?,
-                })
-            })
+        let rows = stmt
+            .query_map(filter_sql.param_refs().as_slice(), map_row)
             .map_err(|e| LogcatError::Database(e.to_string()))?
             .filter_map(|r| r.ok())
             .collect();
 
-        // Apply text filter in Rust if using regex
-        let rows = self.apply_text_filters(rows, filters)?;
-
         Ok(rows)
     }
 
-    fn apply_text_filters(&self, rows: Vec<LogRow>, filters: &LogFilters) -> Result<Vec<LogRow>> {
-        let mode = filters.text_mode.as_deref().unwrap_or("plain");
-        let case_sensitive = filters.case_sensitive.unwrap_or(false);
+    /// `filters` restricted to the optional `[start_ms, end_ms]` range
+    fn range_filter_sql(&self, filters: &LogFilters, start_ms: Option<f64>, end_ms: Option<f64>) -> Result<FilterSql> {
+        let mut filter_sql = self.filter_sql(filters)?;
+        if let Some(start) = start_ms {
+            filter_sql.push("ts_unix >= ?", [Value::Real(start)]);
+        }
+        if let Some(end) = end_ms {
+            filter_sql.push("ts_unix <= ?", [Value::Real(end)]);
+        }
+        Ok(filter_sql)
+    }
 
-        // Apply include text filter
-        let rows = if let Some(ref text) = filters.text {
-            if mode == "regex" {
-                let re = compile_user_regex(text, !case_sensitive)?;
-                rows.into_iter()
-                    .filter(|r| re.is_match(&r.msg))
-                    .collect()
-            } else {
-                rows.into_iter()
-                    .filter(|r| plain_text_contains(&r.msg, text, case_sensitive))
-                    .collect()
+    /// Number of rows matching `filters` within the optional time range
+    pub fn count_in_range(&self, filters: &LogFilters, start_ms: Option<f64>, end_ms: Option<f64>) -> Result<usize> {
+        self.count_where(&self.range_filter_sql(filters, start_ms, end_ms)?)
+    }
+
+    /// Visit every row matching `filters` within the optional time range
+    ///
+    /// Rows are streamed in `(ts_unix, id)` order without loading the whole
+    /// result; an error from `visit` stops the scan and is returned.
+    pub fn for_each_row<F>(
+        &self,
+        filters: &LogFilters,
+        start_ms: Option<f64>,
+        end_ms: Option<f64>,
+        mut visit: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(&LogRow) -> Result<()>,
+    {
+        let filter_sql = self.range_filter_sql(filters, start_ms, end_ms)?;
+        let sql = format!(
+            "SELECT {} FROM logs {} ORDER BY ts_unix ASC, id ASC",
+            ROW_COLUMNS,
+            filter_sql.where_clause(),
+        );
+        let mut stmt = self.conn.prepare(&sql)
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+        let mut rows = stmt
+            .query(filter_sql.param_refs().as_slice())
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+        let mut visited = 0;
+        while let Some(row) = rows.next().map_err(|e| LogcatError::Database(e.to_string()))? {
+            let (log_row, _) = map_row(row).map_err(|e| LogcatError::Database(e.to_string()))?;
+            visit(&log_row)?;
+            visited += 1;
+        }
+        Ok(visited)
+    }
+
+    /// Number of rows matching the filter (cached for the last filter)
+    fn filtered_count(&self, filter_sql: &FilterSql, filter_hash: u64) -> Result<usize> {
+        if let Some((hash, count)) = self.count_cache.get() {
+            if hash == filter_hash {
+                return Ok(count);
             }
-        } else {
-            rows
+        }
+
+        let count = self.count_where(filter_sql)?;
+        self.count_cache.set(Some((filter_hash, count)));
+        Ok(count)
+    }
+
+    /// Number of matching rows ordered before `(ts_unix, id)`
+    fn count_before(&self, filter_sql: &FilterSql, ts_unix: f64, id: i64) -> Result<usize> {
+        let mut filter_sql = filter_sql.clone();
+        filter_sql.push(
+            "(ts_unix < ? OR (ts_unix = ? AND id < ?))",
+            [Value::Real(ts_unix), Value::Real(ts_unix), Value::Integer(id)],
+        );
+        self.count_where(&filter_sql)
+    }
+
+    fn count_where(&self, filter_sql: &FilterSql) -> Result<usize> {
+        let sql = format!("SELECT COUNT(*) FROM logs {}", filter_sql.where_clause());
+        self.conn
+            .query_row(&sql, filter_sql.param_refs().as_slice(), |r| r.get(0))
+            .map_err(|e| LogcatError::Database(e.to_string()))
+    }
+
+    /// Get statistics about the logcat data under `filters`
+    ///
+    /// Filtered stats over large databases are extrapolated from a sample and
+    /// flagged `is_estimate`; use `get_stats_exact` for the exact figures.
+    pub fn get_stats(&self, filters: &LogFilters) -> Result<LogcatStats> {
+        let filter_sql = self.filter_sql(filters)?;
+        let total_rows = self.count_where(&FilterSql::default())?;
+
+        if filter_sql.conditions.is_empty() || total_rows <= ESTIMATE_MIN_ROWS {
+            let aggregate = self.aggregate(&filter_sql)?;
+            return self.build_stats(filters, total_rows, aggregate, false);
+        }
+
+        let aggregate = self.sample_aggregate(&filter_sql)?;
+        self.build_stats(filters, total_rows, aggregate, true)
+    }
+
+    /// Get exact statistics under `filters` (full scan of the matches)
+    pub fn get_stats_exact(&self, filters: &LogFilters) -> Result<LogcatStats> {
+        let filter_sql = self.filter_sql(filters)?;
+        let total_rows = self.count_where(&FilterSql::default())?;
+        let aggregate = self.aggregate(&filter_sql)?;
+        self.build_stats(filters, total_rows, aggregate, false)
+    }
+
+    /// Handle to abort a long-running query on this connection from another thread
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.conn.get_interrupt_handle()
+    }
+
+    fn build_stats(
+        &self,
+        filters: &LogFilters,
+        total_rows: usize,
+        aggregate: StatsAggregate,
+        is_estimate: bool,
+    ) -> Result<LogcatStats> {
+        let filter_hash = compute_filter_hash(filters);
+
+        // Seed the page count cache so queries skip a second full count
+        if !is_estimate {
+            self.count_cache.set(Some((filter_hash, aggregate.rows)));
+        }
+
+        Ok(LogcatStats {
+            total_rows,
+            filtered_rows: Some(aggregate.rows),
+            min_timestamp_ms: aggregate.min_ts.map(|t| t as i64),
+            max_timestamp_ms: aggregate.max_ts.map(|t| t as i64),
+            min_ts_display: self.display_at(aggregate.min_ts)?,
+            max_ts_display: self.display_at(aggregate.max_ts)?,
+            level_counts: aggregate.level_counts,
+            is_estimate,
+            filter_hash,
+        })
+    }
+
+    /// Find the next / previous row matching `search` within the `filters` view
+    ///
+    /// Starts after (or before) `from_row_id`, or at the start (or end) of the
+    /// view, and wraps around once. The returned page of the view starts at
+    /// the match so it can replace the loaded rows.
+    pub fn find_match(
+        &self,
+        filters: &LogFilters,
+        search: &LogFilters,
+        from_row_id: Option<i64>,
+        direction: CursorDirection,
+        limit: usize,
+    ) -> Result<FindResponse> {
+        let mut match_sql = self.filter_sql(filters)?;
+        match_sql.extend(self.filter_sql(search)?);
+
+        let cursor = match from_row_id {
+            Some(id) => {
+                let (_, ts_unix) = self.fetch_row(id)?;
+                Some(QueryCursor::new(id, ts_unix, direction, 0))
+            }
+            None => None,
         };
 
-        // Apply exclude text filter
-        let rows = if let Some(ref not_text) = filters.not_text {
-            if mode == "regex" {
-                if let Ok(re) = compile_user_regex(not_text, !case_sensitive) {
-                    rows.into_iter()
-                        .filter(|r| !re.is_match(&r.msg))
-                        .collect()
-                } else {
-                    rows
-                }
-            } else {
-                rows.into_iter()
-                    .filter(|r| !plain_text_contains(&r.msg, not_text, case_sensitive))
-                    .collect()
+        let mut wrapped = false;
+        let mut found = self.execute_query(&match_sql, cursor.as_ref(), 1, direction)?.pop();
+        if found.is_none() && cursor.is_some() {
+            found = self.execute_query(&match_sql, None, 1, direction)?.pop();
+            wrapped = found.is_some();
+        }
+
+        let total_matches = self.count_where(&match_sql)?;
+        let (row, ts_unix) = match found {
+            Some(f) => f,
+            None => {
+                return Ok(FindResponse {
+                    row_id: None,
+                    ordinal: 0,
+                    total_matches,
+                    wrapped: false,
+                    page: None,
+                })
             }
-        } else {
-            rows
         };
 
-        Ok(rows)
+        let row_id = row.id.unwrap_or(0);
+        let ordinal = self.count_before(&match_sql, ts_unix, row_id)? + 1;
+
+        // Forward keyset pages are exclusive, so start just before the match
+        let page_cursor = QueryCursor::new(row_id - 1, ts_unix, CursorDirection::Forward, compute_filter_hash(filters));
+        let page = self.query(filters, Some(&page_cursor), limit, CursorDirection::Forward)?;
+
+        Ok(FindResponse {
+            row_id: Some(row_id),
+            ordinal,
+            total_matches,
+            wrapped,
+            page: Some(page),
+        })
     }
 
-    /// Get statistics about the logcat data
-    pub fn get_stats(&self, _filters: &LogFilters) -> Result<LogcatStats> {
-        let total_rows: usize = self.conn
-            .query_row("SELECT COUNT(*) FROM logs", [], |r| r.get(0))
+    /// Time-bucketed level counts with crash / ANR markers
+    ///
+    /// The range defaults to the whole log. All buckets come from one
+    /// `GROUP BY` over a `ts_unix` range, which is served by `idx_ts`.
+    /// Markers are placed regardless of `filters`.
+    pub fn get_histogram(
+        &self,
+        filters: &LogFilters,
+        start_ms: Option<f64>,
+        end_ms: Option<f64>,
+        buckets: usize,
+    ) -> Result<Histogram> {
+        let (min_ts, max_ts): (Option<f64>, Option<f64>) = self.conn
+            .query_row("SELECT MIN(ts_unix), MAX(ts_unix) FROM logs", [], |r| Ok((r.get(0)?, r.get(1)?)))
             .map_err(|e| LogcatError::Database(e.to_string()))?;
 
-        let (min_ts, max_ts): (Option<i64>, Option<i64>) = self.conn
-            .query_row(
-                "SELECT MIN(CAST(ts_unix AS INTEGER)), MAX(CAST(ts_unix AS INTEGER)) FROM logs",
-                [],
-                |r| Ok((r.get(0)?, r.get(1)?)),
-            )
+        let (start, end) = match (start_ms.or(min_ts), end_ms.or(max_ts)) {
+            (Some(s), Some(e)) if e >= s => (s, e),
+            (s, e) => {
+                return Ok(Histogram {
+                    start_ms: s.unwrap_or(0.0),
+                    end_ms: e.unwrap_or(0.0),
+                    bucket_ms: 0.0,
+                    buckets: Vec::new(),
+                    max_total: 0,
+                })
+            }
+        };
+
+        // Buckets are at least 1ms wide; short ranges get fewer buckets
+        let requested = buckets.clamp(1, MAX_HISTOGRAM_BUCKETS);
+        let bucket_ms = ((end - start) / requested as f64).max(1.0);
+        let count = requested.min(((end - start) / bucket_ms) as usize + 1);
+        let last = count as i64 - 1;
+
+        let mut result: Vec<HistogramBucket> = (0..count)
+            .map(|i| HistogramBucket {
+                start_ms: start + i as f64 * bucket_ms,
+                ..Default::default()
+            })
+            .collect();
+
+        let mut filter_sql = self.filter_sql(filters)?;
+        filter_sql.push("ts_unix BETWEEN ? AND ?", [Value::Real(start), Value::Real(end)]);
+
+        let sql = format!(
+            "SELECT MIN(CAST((ts_unix - ?) / ? AS INTEGER), ?) AS bucket, level, COUNT(*) \
+             FROM logs {} GROUP BY bucket, level",
+            filter_sql.where_clause(),
+        );
+        let mut params = vec![Value::Real(start), Value::Real(bucket_ms), Value::Integer(last)];
+        params.extend(filter_sql.params.iter().cloned());
+
+        let mut stmt = self.conn.prepare(&sql)
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, usize>(2)?))
+            })
             .map_err(|e| LogcatError::Database(e.to_string()))?;
 
-        // Get the display timestamps (device local time) for min and max
-        let min_ts_display: Option<String> = self.conn
-            .query_row(
-                "SELECT ts_display FROM logs ORDER BY ts_unix ASC LIMIT 1",
-                [],
-                |r| r.get(0),
-            )
-            .ok();
+        for row in rows {
+            let (index, level, n) = row.map_err(|e| LogcatError::Database(e.to_string()))?;
+            let bucket = &mut result[index.clamp(0, last) as usize];
+            bucket.total += n;
+            bucket.level_counts.add(&level, n);
+        }
 
-        let max_ts_display: Option<String> = self.conn
-            .query_row(
-                "SELECT ts_display FROM logs ORDER BY ts_unix DESC LIMIT 1",
-                [],
-                |r| r.get(0),
-            )
-            .ok();
+        for event in self.events()? {
+            if event.ts_unix < start || event.ts_unix > end {
+                continue;
+            }
+            let index = (((event.ts_unix - start) / bucket_ms) as i64).min(last);
+            let bucket = &mut result[index as usize];
+            match event.kind {
+                EventKind::Crash => bucket.crashes += 1,
+                EventKind::Anr => bucket.anrs += 1,
+            }
+        }
 
-        let level_counts = self.get_level_counts()?;
+        Ok(Histogram {
+            start_ms: start,
+            end_ms: end,
+            bucket_ms,
+            max_total: result.iter().map(|b| b.total).max().unwrap_or(0),
+            buckets: result,
+        })
+    }
 
-        Ok(LogcatStats {
+    /// Top-N rows grouped by tag, pid, process, level, message template or
+    /// extracted field value
+    ///
+    /// Counts are taken under `filters` within the optional time range. Field
+    /// aggregations only count rows carrying the field.
+    pub fn get_top(
+        &self,
+        filters: &LogFilters,
+        by: AggregateBy,
+        start_ms: Option<f64>,
+        end_ms: Option<f64>,
+        sort: AggregateSort,
+        limit: usize,
+    ) -> Result<AggregateResponse> {
+        let filter_sql = self.range_filter_sql(filters, start_ms, end_ms)?;
+
+        let mut key_param = None;
+        let (key_expr, join) = match &by {
+            AggregateBy::Tag => ("tag", ""),
+            AggregateBy::Pid | AggregateBy::Process => ("pid", ""),
+            AggregateBy::Level => ("level", ""),
+            AggregateBy::Template => {
+                require_templates(&self.conn)?;
+                ("template_id", "")
+            }
+            AggregateBy::Field(key) => {
+                key_param = Some(Value::Text(key.to_ascii_lowercase()));
+                ("f.value", "JOIN log_fields f ON f.row_id = logs.id AND f.key = ?")
+            }
+        };
+        let sql = format!(
+            "SELECT {}, COUNT(*), MIN(ts_unix), MAX(ts_unix) FROM logs {} {} GROUP BY 1",
+            key_expr,
+            join,
+            filter_sql.where_clause(),
+        );
+        let mut stmt = self.conn.prepare(&sql)
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+        let params: Vec<&dyn rusqlite::ToSql> = key_param
+            .iter()
+            .map(|p| p as &dyn rusqlite::ToSql)
+            .chain(filter_sql.param_refs())
+            .collect();
+        let rows = stmt
+            .query_map(params.as_slice(), |row| {
+                let key = match row.get_ref(0)? {
+                    rusqlite::types::ValueRef::Integer(pid) => pid.to_string(),
+                    _ => row.get::<_, String>(0)?,
+                };
+                Ok((key, row.get::<_, usize>(1)?, row.get::<_, f64>(2)?, row.get::<_, f64>(3)?))
+            })
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+        let mut groups: Vec<(String, usize, f64, f64)> = Vec::new();
+        for row in rows {
+            groups.push(row.map_err(|e| LogcatError::Database(e.to_string()))?);
+        }
+
+        if by == AggregateBy::Template {
+            let templates = load_templates(&self.conn)?;
+            for group in &mut groups {
+                if let Some((_, template)) = group.0.parse().ok().and_then(|id: i64| templates.get(&id)) {
+                    group.0 = template.clone();
+                }
+            }
+        }
+
+        // Name pids after the process running at their last row; pids of
+        // the same process are merged
+        if by == AggregateBy::Process {
+            let processes = &self.query_context()?.processes;
+            let mut merged: HashMap<String, (usize, f64, f64)> = HashMap::new();
+            for (pid, count, first, last) in groups {
+                let name = process_name(processes, pid.parse().unwrap_or(0), last);
+                let entry = merged.entry(name).or_insert((0, first, last));
+                entry.0 += count;
+                entry.1 = entry.1.min(first);
+                entry.2 = entry.2.max(last);
+            }
+            groups = merged.into_iter().map(|(k, (c, f, l))| (k, c, f, l)).collect();
+        }
+
+        let total_rows: usize = groups.iter().map(|g| g.1).sum();
+        let first_ms = groups.iter().map(|g| g.2).reduce(f64::min);
+        let last_ms = groups.iter().map(|g| g.3).reduce(f64::max);
+
+        let mut entries: Vec<AggregateEntry> = groups
+            .into_iter()
+            .map(|(key, count, first_ms, last_ms)| AggregateEntry {
+                key,
+                count,
+                share: count as f64 / total_rows as f64,
+                first_ms,
+                last_ms,
+                rate_per_minute: rate_per_minute(count, first_ms, last_ms),
+            })
+            .collect();
+        let distinct_keys = entries.len();
+
+        sort_entries(&mut entries, sort);
+        entries.truncate(limit.clamp(1, MAX_TOP_ENTRIES));
+
+        Ok(AggregateResponse {
+            by,
+            entries,
             total_rows,
-            filtered_rows: None,
-            min_timestamp_ms: min_ts,
-            max_timestamp_ms: max_ts,
-            min_ts_display,
-            max_ts_display,
-            level_counts,
+            distinct_keys,
+            rate_per_minute: match (first_ms, last_ms) {
+                (Some(f), Some(l)) => rate_per_minute(total_rows, f, l),
+                _ => 0.0,
+            },
         })
     }
 
-    fn get_level_counts(&self) -> Result<LevelCounts> {
-        let mut counts = LevelCounts::default();
+    /// Mined message templates of the rows matching `filters`
+    ///
+    /// Caches built before mining get their templates from a background
+    /// backfill; until it finishes this fails with `IndexPending`. Each entry carries up to `samples` of its earliest matching rows.
+    pub fn get_templates(
+        &self,
+        filters: &LogFilters,
+        start_ms: Option<f64>,
+        end_ms: Option<f64>,
+        limit: usize,
+        samples: usize,
+    ) -> Result<TemplateResponse> {
+        require_templates(&self.conn)?;
+        let filter_sql = self.range_filter_sql(filters, start_ms, end_ms)?;
 
-        let mut stmt = self.conn
-            .prepare("SELECT level, COUNT(*) FROM logs GROUP BY level")
+        let sql = format!(
+            "SELECT template_id, COUNT(*), MIN(ts_unix), MAX(ts_unix) FROM logs {} GROUP BY template_id ORDER BY 2 DESC, 1 ASC",
+            filter_sql.where_clause(),
+        );
+        let mut stmt = self.conn.prepare(&sql)
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+        let groups = stmt
+            .query_map(filter_sql.param_refs().as_slice(), |row| {
+                Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, usize>(1)?, row.get::<_, f64>(2)?, row.get::<_, f64>(3)?))
+            })
+            .map_err(|e| LogcatError::Database(e.to_string()))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+        let total_rows: usize = groups.iter().map(|g| g.1).sum();
+        let distinct_templates = groups.len();
+        let templates = load_templates(&self.conn)?;
+
+        let mut entries = Vec::new();
+        for (template_id, count, first_ms, last_ms) in groups.into_iter().take(limit.clamp(1, MAX_TOP_ENTRIES)) {
+            let template_id = template_id.unwrap_or(0);
+            let (tag, template) = templates.get(&template_id).cloned().unwrap_or_default();
+
+            let mut sample_sql = filter_sql.clone();
+            sample_sql.push("template_id = ?", [Value::Integer(template_id)]);
+            let sql = format!(
+                "SELECT {} FROM logs {} ORDER BY ts_unix ASC, id ASC LIMIT ?",
+                ROW_COLUMNS,
+                sample_sql.where_clause(),
+            );
+            sample_sql.params.push(Value::Integer(samples.min(MAX_TEMPLATE_SAMPLES) as i64));
+
+            entries.push(TemplateEntry {
+                template_id,
+                tag,
+                template,
+                count,
+                share: count as f64 / total_rows as f64,
+                first_ms,
+                last_ms,
+                samples: self.fetch_rows(&sql, &sample_sql)?.into_iter().map(|(row, _)| row).collect(),
+            });
+        }
+
+        Ok(TemplateResponse { entries, total_rows, distinct_templates })
+    }
+
+    /// Per-level counts and time range of the rows matching `filter_sql`
+    fn aggregate(&self, filter_sql: &FilterSql) -> Result<StatsAggregate> {
+        let mut aggregate = StatsAggregate::default();
+        self.aggregate_into(filter_sql, &mut aggregate)?;
+        Ok(aggregate)
+    }
+
+    fn aggregate_into(&self, filter_sql: &FilterSql, aggregate: &mut StatsAggregate) -> Result<()> {
+        let sql = format!(
+            "SELECT level, COUNT(*), MIN(ts_unix), MAX(ts_unix) FROM logs {} GROUP BY level",
+            filter_sql.where_clause(),
+        );
+        let mut stmt = self.conn.prepare_cached(&sql)
             .map_err(|e| LogcatError::Database(e.to_string()))?;
 
         let rows = stmt
-            .query_map([], |row| {
-                let level: String = row.get(0)?;
-                let count: usize = row.get(1)?;
-                Ok((level, count))
+            .query_map(filter_sql.param_refs().as_slice(), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, usize>(1)?,
+                    row.get::<_, Option<f64>>(2)?,
+                    row.get::<_, Option<f64>>(3)?,
+                ))
             })
             .map_err(|e| LogcatError::Database(e.to_string()))?;
 
-        for row in rows.flatten() {
-            match row.0.as_str() {
-                "V" => counts.verbose = row.1,
-                "D" => counts.debug = row.1,
-                "I" => counts.info = row.1,
-                "W" => counts.warning = row.1,
-                "E" => counts.error = row.1,
-                "F" => counts.fatal = row.1,
-                _ => {}
-            }
+        for row in rows {
+            let (level, count, min_ts, max_ts) = row.map_err(|e| LogcatError::Database(e.to_string()))?;
+            aggregate.merge(&level, count, min_ts, max_ts);
+        }
+
+        Ok(())
+    }
+
+    /// Exact `ts_unix` range of the rows matching `filter_sql`
+    ///
+    /// Walks `idx_ts` from either end, so it stays cheap unless matches are
+    /// very rare.
+    fn time_bounds(&self, filter_sql: &FilterSql) -> Result<(Option<f64>, Option<f64>)> {
+        let bound = |order: &str| -> Result<Option<f64>> {
+            let sql = format!(
+                "SELECT ts_unix FROM logs {} ORDER BY ts_unix {} LIMIT 1",
+                filter_sql.where_clause(),
+                order,
+            );
+            self.conn
+                .query_row(&sql, filter_sql.param_refs().as_slice(), |r| r.get(0))
+                .optional()
+                .map_err(|e| LogcatError::Database(e.to_string()))
+        };
+        Ok((bound("ASC")?, bound("DESC")?))
+    }
+
+    /// Aggregate over evenly spaced id windows, scaled to the whole table
+    ///
+    /// Only the counts are estimated; the time range is exact.
+    fn sample_aggregate(&self, filter_sql: &FilterSql) -> Result<StatsAggregate> {
+        let (min_id, max_id): (Option<i64>, Option<i64>) = self.conn
+            .query_row("SELECT MIN(id), MAX(id) FROM logs", [], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+        let (min_id, max_id) = match (min_id, max_id) {
+            (Some(lo), Some(hi)) => (lo, hi),
+            _ => return Ok(StatsAggregate::default()),
+        };
+
+        let span = max_id - min_id + 1;
+        if span <= SAMPLE_ROWS as i64 {
+            return self.aggregate(filter_sql);
         }
 
-        Ok(counts)
+        let window = (SAMPLE_ROWS / SAMPLE_WINDOWS) as i64;
+        let mut sample = StatsAggregate::default();
+
+        for k in 0..SAMPLE_WINDOWS as i64 {
+            let start = min_id + span * k / SAMPLE_WINDOWS as i64;
+            let mut window_sql = filter_sql.clone();
+            window_sql.push(
+                "id BETWEEN ? AND ?",
+                [Value::Integer(start), Value::Integer(start + window - 1)],
+            );
+            self.aggregate_into(&window_sql, &mut sample)?;
+        }
+
+        let factor = span as f64 / (window * SAMPLE_WINDOWS as i64) as f64;
+        let (min_ts, max_ts) = self.time_bounds(filter_sql)?;
+
+        Ok(StatsAggregate {
+            rows: (sample.rows as f64 * factor).round() as usize,
+            level_counts: sample.level_counts.scaled(factor),
+            min_ts,
+            max_ts,
+        })
+    }
+
+    /// Display timestamp of a row at `ts_unix`
+    fn display_at(&self, ts_unix: Option<f64>) -> Result<Option<String>> {
+        let ts_unix = match ts_unix {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        self.conn
+            .query_row(
+                "SELECT ts_display FROM logs WHERE ts_unix = ? LIMIT 1",
+                [ts_unix],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(|e| LogcatError::Database(e.to_string()))
     }
 }
 
+/// Partial stats accumulated from `GROUP BY level` results
+#[derive(Debug, Default)]
+struct StatsAggregate {
+    rows: usize,
+    level_counts: LevelCounts,
+    min_ts: Option<f64>,
+    max_ts: Option<f64>,
+}
+
+impl StatsAggregate {
+    fn merge(&mut self, level: &str, count: usize, min_ts: Option<f64>, max_ts: Option<f64>) {
+        self.rows += count;
+        self.level_counts.add(level, count);
+        if let Some(t) = min_ts {
+            self.min_ts = Some(self.min_ts.map_or(t, |m| m.min(t)));
+        }
+        if let Some(t) = max_ts {
+            self.max_ts = Some(self.max_ts.map_or(t, |m| m.max(t)));
+        }
+    }
+}
+
+/// Map a `ROW_COLUMNS` result row to the log row and its `ts_unix`
+fn map_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<(LogRow, f64)> {
+    Ok((
+        LogRow {
+            id: Some(row.get(0)?),
+            ts: row.get(1)?,
+            ts_iso: row.get(2)?,
+            level: row.get(3)?,
+            tag: row.get(4)?,
+            pid: row.get(5)?,
+            tid: row.get(6)?,
+            msg: row.get(7)?,
+            matches: None,
+            repeat: None,
+            instance_id: row.get(9)?,
+        },
+        row.get(8)?,
+    ))
+}
+
 /// Compute hash of filter conditions for cursor validation
 /// Uses JSON serialization for deterministic hash values across invocations
 fn compute_filter_hash(filters: &LogFilters) -> u64 {
@@ -333,6 +1164,688 @@ fn compute_filter_hash(filters: &LogFilters) -> u64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::index::LogcatDatabase;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_db_path() -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("test_executor_{}.db", nanos))
+    }
+
+    /// 1000 rows where every 100th message contains "Needle"
+    fn create_sparse_db() -> std::path::PathBuf {
+        create_test_db(|i| i as f64)
+    }
+
+    /// Same rows as `create_sparse_db` with timestamp offsets from `ts_offset`
+    fn create_test_db(ts_offset: impl Fn(usize) -> f64) -> std::path::PathBuf {
+        let path = temp_db_path();
+        let mut db = LogcatDatabase::create(&path).unwrap();
+        {
+            let batch = db.begin_batch().unwrap();
+            for i in 0..1000 {
+                let msg = if i % 100 == 0 {
+                    format!("found Needle {}", i)
+                } else {
+                    format!("filler message {}", i)
+                };
+                let row = LogRow {
+                    id: None,
+                    ts: "08-24 14:22:33.123".to_string(),
+                    ts_iso: None,
+                    level: "I".to_string(),
+                    tag: "Test".to_string(),
+                    pid: 1000,
+                    tid: 1000,
+                    msg,
+                    matches: None,
+                    repeat: None,
+                    instance_id: None,
+                };
+                batch.insert(&row, 1724487753000.0 + ts_offset(i)).unwrap();
+            }
+            batch.commit().unwrap();
+        }
+        path
+    }
+
+    fn query_msgs(executor: &QueryExecutor, filters: &LogFilters, limit: usize) -> Vec<String> {
+        executor
+            .query(filters, None, limit, CursorDirection::Forward)
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|r| r.msg)
+            .collect()
+    }
+
+    #[test]
+    fn test_rare_text_fills_page() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let filters = LogFilters {
+            text: Some("needle".to_string()),
+            ..Default::default()
+        };
+        let msgs = query_msgs(&executor, &filters, 5);
+        assert_eq!(msgs.len(), 5);
+        assert!(msgs.iter().all(|m| m.contains("Needle")));
+
+        // Case-sensitive search must not match "Needle"
+        let filters = LogFilters {
+            text: Some("needle".to_string()),
+            case_sensitive: Some(true),
+            ..Default::default()
+        };
+        assert!(query_msgs(&executor, &filters, 5).is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_regex_filters_in_sql() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let filters = LogFilters {
+            text: Some(r"needle [1-9]\d+$".to_string()),
+            text_mode: Some("regex".to_string()),
+            ..Default::default()
+        };
+        let msgs = query_msgs(&executor, &filters, 100);
+        assert_eq!(msgs.len(), 9);
+
+        let filters = LogFilters {
+            not_text: Some("filler|Needle 0$".to_string()),
+            text_mode: Some("regex".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query_msgs(&executor, &filters, 100).len(), 9);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_keyset_pagination_both_directions() {
+        // Out-of-order timestamps with many ties
+        let path = create_test_db(|i| ((i * 7) % 50) as f64);
+        let executor = QueryExecutor::open(&path).unwrap();
+        let filters = LogFilters {
+            not_text: Some("Needle".to_string()),
+            ..Default::default()
+        };
+
+        // Forward walk
+        let mut forward = Vec::new();
+        let mut cursor: Option<QueryCursor> = None;
+        loop {
+            let resp = executor
+                .query(&filters, cursor.as_ref(), 37, CursorDirection::Forward)
+                .unwrap();
+            assert_eq!(resp.estimated_total, Some(990));
+            assert_eq!(resp.has_more_prev, !forward.is_empty());
+            let expected_ratio = forward.len() as f32 / 990.0;
+            assert!((resp.position_ratio - expected_ratio).abs() < 1e-6);
+
+            forward.extend(resp.rows.iter().map(|r| r.id.unwrap()));
+            if !resp.has_more_next {
+                break;
+            }
+            cursor = resp.next_cursor;
+        }
+        assert_eq!(forward.len(), 990);
+        let mut unique = forward.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 990);
+
+        // Adjacent pages take their start from the previous page
+        let position = executor.position_cache.get().unwrap();
+        assert_eq!(position.page_start + position.spanned_rows, 990);
+
+        // Backward walk from the end yields the same rows in reverse
+        let mut backward = Vec::new();
+        let mut cursor = Some(QueryCursor::new(i64::MAX, f64::MAX, CursorDirection::Backward, 0));
+        loop {
+            let resp = executor
+                .query(&filters, cursor.as_ref(), 37, CursorDirection::Backward)
+                .unwrap();
+            let expected_ratio = (990 - backward.len() - resp.rows.len()) as f32 / 990.0;
+            assert!((resp.position_ratio - expected_ratio).abs() < 1e-6);
+            backward.extend(resp.rows.iter().map(|r| r.id.unwrap()));
+            if !resp.has_more_prev {
+                break;
+            }
+            cursor = resp.prev_cursor;
+        }
+        backward.reverse();
+        assert_eq!(backward, forward);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_collapsed_pages_and_expand() {
+        let path = create_test_db(|i| i as f64 * 10.0);
+        let executor = QueryExecutor::open(&path).unwrap();
+        let filters = LogFilters::default();
+
+        // "filler message N" rows share a template; every 100th row breaks the run
+        let mut forward = Vec::new();
+        let mut cursor: Option<QueryCursor> = None;
+        loop {
+            let resp = executor
+                .query_collapsed(&filters, cursor.as_ref(), 3, CursorDirection::Forward, CollapseMode::Template)
+                .unwrap();
+            forward.extend(resp.rows);
+            if !resp.has_more_next {
+                break;
+            }
+            cursor = resp.next_cursor;
+        }
+        assert_eq!(forward.len(), 20);
+        let counts: usize = forward.iter().map(|r| r.repeat.as_ref().map_or(1, |rep| rep.count)).sum();
+        assert_eq!(counts, 1000);
+
+        let run = forward[1].repeat.as_ref().unwrap();
+        assert_eq!((forward[1].id, run.count, run.last_id), (Some(2), 99, 100));
+        let expanded = executor
+            .query(&filters, Some(&run.expand_cursor), run.count, CursorDirection::Forward)
+            .unwrap();
+        let ids: Vec<i64> = expanded.rows.iter().map(|r| r.id.unwrap()).collect();
+        assert_eq!(ids, (2..=100).collect::<Vec<i64>>());
+
+        // Exact mode keeps every distinct message apart
+        let exact = executor
+            .query_collapsed(&filters, None, 5, CursorDirection::Forward, CollapseMode::Exact)
+            .unwrap();
+        assert!(exact.rows.iter().all(|r| r.repeat.is_none()));
+
+        // Backward pages yield the same runs in reverse
+        let mut backward = Vec::new();
+        let mut cursor = Some(QueryCursor::new(i64::MAX, f64::MAX, CursorDirection::Backward, 0));
+        loop {
+            let resp = executor
+                .query_collapsed(&filters, cursor.as_ref(), 3, CursorDirection::Backward, CollapseMode::Template)
+                .unwrap();
+            backward.extend(resp.rows.iter().map(|r| r.id.unwrap()));
+            if !resp.has_more_prev {
+                break;
+            }
+            cursor = resp.prev_cursor;
+        }
+        backward.reverse();
+        assert_eq!(backward, forward.iter().map(|r| r.id.unwrap()).collect::<Vec<i64>>());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_query_page_offsets() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let rows = executor.query_page(&LogFilters::default(), 990, 20).unwrap();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[0].id, Some(991));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_stats_under_filters() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let filters = LogFilters {
+            text: Some("needle".to_string()),
+            ..Default::default()
+        };
+        let stats = executor.get_stats(&filters).unwrap();
+        assert_eq!(stats.total_rows, 1000);
+        assert_eq!(stats.filtered_rows, Some(10));
+        assert_eq!(stats.level_counts.info, 10);
+        assert_eq!(stats.min_timestamp_ms, Some(1724487753000));
+        assert_eq!(stats.max_timestamp_ms, Some(1724487753900));
+        assert!(!stats.is_estimate);
+        assert_eq!(stats.filter_hash, compute_filter_hash(&filters));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_sample_estimate_close_to_exact() {
+        let path = temp_db_path();
+        let mut db = LogcatDatabase::create(&path).unwrap();
+        {
+            let batch = db.begin_batch().unwrap();
+            for i in 0..80_000 {
+                let row = LogRow {
+                    id: None,
+                    ts: "08-24 14:22:33.123".to_string(),
+                    ts_iso: None,
+                    level: if i % 4 == 0 { "E" } else { "I" }.to_string(),
+                    tag: "Test".to_string(),
+                    pid: 1000,
+                    tid: 1000,
+                    msg: format!("message {}", i),
+                    matches: None,
+                    repeat: None,
+                    instance_id: None,
+                };
+                batch.insert(&row, 1724487753000.0 + i as f64).unwrap();
+            }
+            batch.commit().unwrap();
+        }
+        drop(db);
+
+        let executor = QueryExecutor::open(&path).unwrap();
+        let filters = LogFilters {
+            levels: Some(vec!["E".to_string()]),
+            ..Default::default()
+        };
+        let filter_sql = build_filter_sql(&filters, executor.sql_options).unwrap();
+
+        let estimate = executor.sample_aggregate(&filter_sql).unwrap();
+        let exact = executor.get_stats_exact(&filters).unwrap();
+        assert_eq!(exact.filtered_rows, Some(20_000));
+
+        let error = (estimate.rows as f64 - 20_000.0).abs() / 20_000.0;
+        assert!(error < 0.05, "estimate {} too far from exact", estimate.rows);
+        assert_eq!(estimate.level_counts.info, 0);
+
+        // The time range is exact, not limited to the sampled windows
+        assert_eq!(estimate.min_ts.map(|t| t as i64), exact.min_timestamp_ms);
+        assert_eq!(estimate.max_ts.map(|t| t as i64), exact.max_timestamp_ms);
+        assert_eq!(exact.max_timestamp_ms, Some(1724487753000 + 79_996));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_query_language_filter() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let filters = LogFilters {
+            query: Some(r#"tag=Test (needle OR msg:"filler message 1") -/Needle 0$/"#.to_string()),
+            ..Default::default()
+        };
+        let stats = executor.get_stats(&filters).unwrap();
+        // 9 needles plus "filler message 1", "...11", "...1xx" (minus needles)
+        assert_eq!(stats.filtered_rows, Some(9 + 111 - 1));
+
+        let filters = LogFilters {
+            query: Some("level:(".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            executor.get_stats(&filters),
+            Err(LogcatError::QuerySyntax { position: 6, .. })
+        ));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_context_ignores_filters_and_pages() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let ctx = executor.get_context(501, 3, 2, ContextScope::Pid).unwrap();
+        let ids: Vec<i64> = ctx.rows.iter().map(|r| r.id.unwrap()).collect();
+        assert_eq!(ids, vec![498, 499, 500, 501, 502, 503]);
+        assert_eq!(ctx.anchor_index, 3);
+        assert!(ctx.has_more_prev && ctx.has_more_next);
+        assert_eq!(ctx.filters.pids, Some(vec![1000]));
+
+        // Paging further out continues from the context edges
+        let next = executor
+            .query(&ctx.filters, ctx.next_cursor.as_ref(), 2, CursorDirection::Forward)
+            .unwrap();
+        let ids: Vec<i64> = next.rows.iter().map(|r| r.id.unwrap()).collect();
+        assert_eq!(ids, vec![504, 505]);
+
+        let prev = executor
+            .query(&ctx.filters, ctx.prev_cursor.as_ref(), 2, CursorDirection::Backward)
+            .unwrap();
+        let ids: Vec<i64> = prev.rows.iter().map(|r| r.id.unwrap()).collect();
+        assert_eq!(ids, vec![497, 496]);
+
+        // Edges of the log
+        let ctx = executor.get_context(1, 5, 0, ContextScope::All).unwrap();
+        assert_eq!(ctx.anchor_index, 0);
+        assert_eq!(ctx.rows.len(), 1);
+        assert!(!ctx.has_more_prev && ctx.has_more_next);
+
+        assert!(matches!(
+            executor.get_context(5000, 1, 1, ContextScope::All),
+            Err(LogcatError::RowNotFound(5000))
+        ));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_histogram_buckets_and_markers() {
+        let path = create_sparse_db();
+        {
+            let mut db = LogcatDatabase::open(&path).unwrap();
+            let batch = db.begin_batch().unwrap();
+            for (offset, level, tag, msg) in [
+                (150.0, "E", "AndroidRuntime", "FATAL EXCEPTION: main"),
+                (160.0, "E", "ActivityManager", "ANR in com.example.app"),
+                (950.0, "F", "libc", "Fatal signal 11 (SIGSEGV), code 1"),
+            ] {
+                let row = LogRow {
+                    id: None,
+                    ts: "08-24 14:22:33.123".to_string(),
+                    ts_iso: None,
+                    level: level.to_string(),
+                    tag: tag.to_string(),
+                    pid: 2000,
+                    tid: 2000,
+                    msg: msg.to_string(),
+                    matches: None,
+                    repeat: None,
+                    instance_id: None,
+                };
+                batch.insert(&row, 1724487753000.0 + offset).unwrap();
+            }
+            batch.commit().unwrap();
+        }
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let histogram = executor.get_histogram(&LogFilters::default(), None, None, 10).unwrap();
+        assert_eq!(histogram.buckets.len(), 10);
+        assert_eq!(histogram.start_ms, 1724487753000.0);
+        assert_eq!(histogram.end_ms, 1724487753999.0);
+        assert_eq!(histogram.buckets.iter().map(|b| b.total).sum::<usize>(), 1003);
+        assert_eq!(histogram.buckets[1].level_counts.error, 2);
+        assert_eq!(histogram.buckets[1].crashes, 1);
+        assert_eq!(histogram.buckets[1].anrs, 1);
+        assert_eq!(histogram.buckets[9].crashes, 1);
+        assert_eq!(histogram.buckets[9].total, 101);
+
+        // Filters apply to counts, not markers
+        let filters = LogFilters {
+            levels: Some(vec!["E".to_string()]),
+            ..Default::default()
+        };
+        let histogram = executor
+            .get_histogram(&filters, Some(1724487753100.0), Some(1724487753199.0), 4)
+            .unwrap();
+        assert_eq!(histogram.buckets.len(), 4);
+        assert_eq!(histogram.max_total, 2);
+        assert_eq!(histogram.buckets.iter().map(|b| b.crashes + b.anrs).sum::<usize>(), 2);
+
+        // Short ranges get at most one bucket per millisecond
+        let histogram = executor
+            .get_histogram(&LogFilters::default(), Some(1724487753000.0), Some(1724487753004.0), 100)
+            .unwrap();
+        assert_eq!(histogram.buckets.len(), 5);
+        assert_eq!(histogram.max_total, 1);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_top_aggregations() {
+        let path = temp_db_path();
+        let mut db = LogcatDatabase::create(&path).unwrap();
+        {
+            let batch = db.begin_batch().unwrap();
+            let insert = |offset: f64, tag: &str, pid: i32, msg: String| {
+                let row = LogRow {
+                    id: None,
+                    ts: "08-24 14:22:33.123".to_string(),
+                    ts_iso: None,
+                    level: "I".to_string(),
+                    tag: tag.to_string(),
+                    pid,
+                    tid: pid,
+                    msg,
+                    matches: None,
+                    repeat: None,
+                    instance_id: None,
+                };
+                batch.insert(&row, 1724487753000.0 + offset).unwrap();
+            };
+            insert(0.0, "ActivityManager", 100, "Start proc 200:com.spam/u0a1 for activity".to_string());
+            insert(0.0, "ActivityManager", 100, "Start proc 300:com.spam/u0a1 for service".to_string());
+            // Slow tag spread over ten minutes, burst tag within one second
+            for i in 0..50 {
+                insert(i as f64 * 12_000.0, "Slow", 200, format!("tick {}", i));
+            }
+            for i in 0..30 {
+                insert(i as f64 * 10.0, "Burst", 300, format!("wrote {} bytes to 0x{:x}", i * 7, i));
+            }
+            batch.commit().unwrap();
+        }
+        let executor = QueryExecutor::open(&path).unwrap();
+        crate::index::templates::mine_templates(executor.connection()).unwrap();
+        let filters = LogFilters::default();
+
+        let top = executor.get_top(&filters, AggregateBy::Tag, None, None, AggregateSort::Count, 2).unwrap();
+        let keys: Vec<&str> = top.entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["Slow", "Burst"]);
+        assert_eq!(top.total_rows, 82);
+        assert_eq!(top.distinct_keys, 3);
+
+        let top = executor.get_top(&filters, AggregateBy::Tag, None, None, AggregateSort::Rate, 1).unwrap();
+        assert_eq!(top.entries[0].key, "Burst");
+        assert_eq!(top.entries[0].rate_per_minute, 30.0);
+
+        // Both pids belong to the same process
+        let top = executor.get_top(&filters, AggregateBy::Process, None, None, AggregateSort::Count, 10).unwrap();
+        assert_eq!(top.entries[0].key, "com.spam");
+        assert_eq!(top.entries[0].count, 80);
+        assert_eq!(top.entries[1].key, "pid 100");
+
+        let top = executor
+            .get_top(&filters, AggregateBy::Template, None, Some(1724487753500.0), AggregateSort::Count, 10)
+            .unwrap();
+        assert_eq!(top.entries[0].key, "wrote <*> bytes to <*>");
+        assert_eq!(top.entries[0].count, 30);
+
+        let templates = executor
+            .get_templates(&LogFilters { tag: Some("ActivityManager".to_string()), ..Default::default() }, None, None, 10, 1)
+            .unwrap();
+        assert_eq!(templates.total_rows, 2);
+        assert_eq!(templates.distinct_templates, 1);
+        let entry = &templates.entries[0];
+        assert_eq!(entry.template, "Start proc <*>:<*>/<*> for <*>");
+        assert_eq!((entry.tag.as_str(), entry.count), ("ActivityManager", 2));
+        assert_eq!(entry.samples.len(), 1);
+        assert!(entry.samples[0].msg.ends_with("for activity"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_field_filters_and_top() {
+        let path = temp_db_path();
+        let mut db = LogcatDatabase::create(&path).unwrap();
+        {
+            let batch = db.begin_batch().unwrap();
+            let messages = [
+                "Killing 100:com.foo uid=10001 reason=anr",
+                "Killing 101:com.bar uid=10002 reason=timeout",
+                "Killing 102:com.baz uid=10003 reason=timeout",
+                "no fields here",
+            ];
+            for (i, msg) in messages.iter().enumerate() {
+                let row = LogRow {
+                    id: None,
+                    ts: "08-24 14:22:33.123".to_string(),
+                    ts_iso: None,
+                    level: "I".to_string(),
+                    tag: "ActivityManager".to_string(),
+                    pid: 1,
+                    tid: 1,
+                    msg: msg.to_string(),
+                    matches: None,
+                    repeat: None,
+                    instance_id: None,
+                };
+                batch.insert(&row, 1724487753000.0 + i as f64).unwrap();
+            }
+            batch.commit().unwrap();
+        }
+        crate::index::fields::extract_fields(db.connection(), &["ActivityManager".to_string()]).unwrap();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let count = |filters: &LogFilters| executor.get_stats(filters).unwrap().filtered_rows;
+        let by_list = LogFilters { fields: Some(vec!["reason=timeout".to_string()]), ..Default::default() };
+        let by_query = LogFilters { query: Some("field:reason=anr OR -field:uid".to_string()), ..Default::default() };
+        assert_eq!(count(&by_list), Some(2));
+        assert_eq!(count(&by_query), Some(2));
+
+        let by = AggregateBy::Field("Reason".to_string());
+        let top = executor.get_top(&LogFilters::default(), by, None, None, AggregateSort::Count, 10).unwrap();
+        let entries: Vec<(&str, usize)> = top.entries.iter().map(|e| (e.key.as_str(), e.count)).collect();
+        assert_eq!(entries, vec![("timeout", 2), ("anr", 1)]);
+        assert_eq!(top.total_rows, 3);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_find_match_next_prev() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        // View hides "Needle 500"; search for the remaining needles
+        let filters = LogFilters {
+            not_text: Some("Needle 500".to_string()),
+            ..Default::default()
+        };
+        let search = LogFilters {
+            text: Some("needle".to_string()),
+            ..Default::default()
+        };
+
+        let found = executor.find_match(&filters, &search, None, CursorDirection::Forward, 5).unwrap();
+        assert_eq!(found.row_id, Some(1));
+        assert_eq!((found.ordinal, found.total_matches), (1, 9));
+
+        let mut found = executor.find_match(&filters, &search, Some(450), CursorDirection::Forward, 5).unwrap();
+        assert_eq!(found.row_id, Some(601));
+        assert_eq!(found.ordinal, 6);
+
+        // The hit is highlighted by the search although the view has no text filter
+        assert!(filters.text.is_none());
+        crate::query::highlight_find(&search, &mut found).unwrap();
+        let page = found.page.unwrap();
+        let spans = &page.rows[0].matches.as_ref().unwrap().msg;
+        assert_eq!(page.rows[0].msg[spans[0].start..spans[0].end].to_lowercase(), "needle");
+        assert_eq!(page.rows.len(), 5);
+        assert_eq!(page.rows[0].id, Some(601));
+        assert!(page.has_more_prev);
+
+        let found = executor.find_match(&filters, &search, Some(450), CursorDirection::Backward, 5).unwrap();
+        assert_eq!((found.row_id, found.ordinal), (Some(401), 5));
+
+        // Past the last match wraps to the first
+        let found = executor.find_match(&filters, &search, Some(950), CursorDirection::Forward, 5).unwrap();
+        assert_eq!((found.row_id, found.ordinal), (Some(1), 1));
+        assert!(found.wrapped);
+
+        let search = LogFilters {
+            text: Some("haystack".to_string()),
+            ..Default::default()
+        };
+        let found = executor.find_match(&filters, &search, Some(450), CursorDirection::Forward, 5).unwrap();
+        assert_eq!((found.row_id, found.total_matches), (None, 0));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_jump_centres_window_on_target() {
+        let path = create_sparse_db();
+        {
+            let mut db = LogcatDatabase::open(&path).unwrap();
+            let batch = db.begin_batch().unwrap();
+            let row = LogRow {
+                id: None,
+                ts: "08-24 14:22:33.623".to_string(),
+                ts_iso: None,
+                level: "E".to_string(),
+                tag: "AndroidRuntime".to_string(),
+                pid: 1000,
+                tid: 1000,
+                msg: "FATAL EXCEPTION: main".to_string(),
+                matches: None,
+                repeat: None,
+                instance_id: None,
+            };
+            batch.insert(&row, 1724487753500.0).unwrap();
+            batch.commit().unwrap();
+        }
+        let executor = QueryExecutor::open(&path).unwrap();
+        let ids = |r: &JumpResponse| r.page.rows.iter().map(|r| r.id.unwrap()).collect::<Vec<_>>();
+
+        let jump = executor.jump(&LogFilters::default(), &JumpTarget::Row { row_id: 300 }, 10).unwrap();
+        assert_eq!(ids(&jump), (295..=304).collect::<Vec<_>>());
+        assert_eq!(jump.target_index, 5);
+        assert!(jump.page.has_more_prev && jump.page.has_more_next);
+
+        // Cursors continue on both sides of the window
+        let prev = executor
+            .query(&LogFilters::default(), jump.page.prev_cursor.as_ref(), 2, CursorDirection::Backward)
+            .unwrap();
+        assert_eq!(prev.rows[0].id, Some(294));
+
+        // A row hidden by the filters lands on the next visible one
+        let filters = LogFilters {
+            text: Some("needle".to_string()),
+            ..Default::default()
+        };
+        let jump = executor.jump(&filters, &JumpTarget::Row { row_id: 150 }, 4).unwrap();
+        assert_eq!(ids(&jump), vec![1, 101, 201, 301]);
+        assert_eq!(jump.target_index, 2);
+
+        // Rows sharing the crash timestamp sort by id
+        let target = JumpTarget::Expr { text: "crash #1".to_string() };
+        let jump = executor.jump(&LogFilters::default(), &target, 4).unwrap();
+        assert_eq!(ids(&jump), vec![500, 501, 1001, 502]);
+        assert_eq!(jump.target_index, 2);
+
+        let target = JumpTarget::Expr { text: "30ms before crash #1".to_string() };
+        let jump = executor.jump(&LogFilters::default(), &target, 4).unwrap();
+        assert_eq!(jump.page.rows[jump.target_index].id, Some(471));
+        assert_eq!(jump.target_ms, 1724487753470.0);
+
+        // Past the end the target is the last row
+        let target = JumpTarget::Expr { text: "10s after crash #1".to_string() };
+        let jump = executor.jump(&LogFilters::default(), &target, 4).unwrap();
+        assert_eq!(ids(&jump), vec![999, 1000]);
+        assert_eq!(jump.target_index, 1);
+
+        assert!(executor.jump(&LogFilters::default(), &JumpTarget::Expr { text: "anr #1".to_string() }, 4).is_err());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_short_text_without_fts() {
+        let path = create_sparse_db();
+        let executor = QueryExecutor::open(&path).unwrap();
+
+        let filters = LogFilters {
+            text: Some("Ne".to_string()),
+            not_text: Some("0 ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(query_msgs(&executor, &filters, 20).len(), 10);
+
+        std::fs::remove_file(&path).ok();
+    }
 
     #[test]
     fn test_compute_filter_hash() {
@@ -1,6 +1,9 @@
 use crate::error::{LogcatError, Result};
+use crate::query::aggregate::message_template;
 use regex::{Regex, RegexBuilder};
 use once_cell::sync::Lazy;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
 
 /// Maximum allowed regex pattern length
 const REGEX_SIZE_LIMIT: usize = 1024;
@@ -78,10 +81,82 @@ pub fn plain_text_contains(text: &str, pattern: &str, case_sensitive: bool) -> b
     }
 }
 
+/// Byte ranges of `pattern` in `text`, folding case like `plain_text_contains`
+pub fn plain_text_spans(text: &str, pattern: &str, case_sensitive: bool) -> Vec<(usize, usize)> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    if case_sensitive {
+        return text.match_indices(pattern).map(|(i, m)| (i, i + m.len())).collect();
+    }
+
+    // Offsets in the lowercased text only carry over when no char changes length
+    let same_widths = text
+        .chars()
+        .all(|c| c.to_lowercase().map(char::len_utf8).sum::<usize>() == c.len_utf8());
+    if same_widths {
+        let pattern = pattern.to_lowercase();
+        return text
+            .to_lowercase()
+            .match_indices(&pattern)
+            .map(|(i, m)| (i, i + m.len()))
+            .collect();
+    }
+
+    RegexBuilder::new(&regex::escape(pattern))
+        .case_insensitive(true)
+        .build()
+        .map(|re| re.find_iter(text).map(|m| (m.start(), m.end())).collect())
+        .unwrap_or_default()
+}
+
+/// Register the SQL functions used by compiled filters
+///
+/// - `regexp(pattern, text)` backs the `text REGEXP pattern` operator; the
+///   compiled regex is cached per statement via SQLite aux data.
+/// - `lm_contains(text, pattern, case_sensitive)` matches `plain_text_contains`.
+pub fn register_sql_functions(conn: &Connection) -> Result<()> {
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+
+    conn.create_scalar_function("regexp", 2, flags, |ctx| {
+        let re = ctx.get_or_create_aux(0, |vr| -> Result<Regex> {
+            let pattern = vr.as_str().map_err(|e| LogcatError::InvalidFilter(e.to_string()))?;
+            compile_user_regex(pattern, false)
+        })?;
+        let text = ctx.get_raw(1).as_str().unwrap_or("");
+        Ok(re.is_match(text))
+    })
+    .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    conn.create_scalar_function("lm_contains", 3, flags, |ctx| {
+        let text = ctx.get_raw(0).as_str().unwrap_or("");
+        let pattern = ctx.get_raw(1).as_str().unwrap_or("");
+        let case_sensitive: bool = ctx.get(2)?;
+        Ok(plain_text_contains(text, pattern, case_sensitive))
+    })
+    .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    conn.create_scalar_function("lm_template", 1, flags, |ctx| {
+        Ok(message_template(ctx.get_raw(0).as_str().unwrap_or("")))
+    })
+    .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_plain_text_spans() {
+        assert_eq!(plain_text_spans("Foo foo FOO", "foo", true), vec![(4, 7)]);
+        assert_eq!(plain_text_spans("Foo foo FOO", "foo", false), vec![(0, 3), (4, 7), (8, 11)]);
+        // "İ" lowercases to two chars; ranges still point into the original
+        assert_eq!(plain_text_spans("İx ab AB", "ab", false), vec![(4, 6), (7, 9)]);
+        assert!(plain_text_spans("abc", "", false).is_empty());
+    }
+
     #[test]
     fn test_validate_safe_pattern() {
         assert!(validate_regex_safety("hello.*world").is_ok());
@@ -132,4 +207,25 @@ mod tests {
         assert!(!plain_text_contains("Hello World", "world", true));
         assert!(plain_text_contains("Hello World", "World", true));
     }
+
+    #[test]
+    fn test_sql_functions() {
+        let conn = Connection::open_in_memory().unwrap();
+        register_sql_functions(&conn).unwrap();
+
+        let matched: bool = conn
+            .query_row("SELECT 'Hello World' REGEXP '(?i)^hello'", [], |r| r.get(0))
+            .unwrap();
+        assert!(matched);
+
+        let matched: bool = conn
+            .query_row("SELECT lm_contains('Hello World', 'WORLD', 0)", [], |r| r.get(0))
+            .unwrap();
+        assert!(matched);
+
+        let template: String = conn
+            .query_row("SELECT lm_template('took 12ms')", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(template, "took <*>");
+    }
 }
@@ -0,0 +1,196 @@
+use crate::error::Result;
+use crate::query::cursor::FindResponse;
+use crate::query::filter::{compile_user_regex, plain_text_spans};
+use crate::types::{LogFilters, LogRow, MatchSpan, RowMatches};
+use regex::Regex;
+
+/// How one filter value matches text
+enum Matcher {
+    Plain { pattern: String, case_sensitive: bool },
+    Regex(Regex),
+    Exact(String),
+}
+
+impl Matcher {
+    /// Non-empty byte ranges matched in `text`
+    fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        match self {
+            Matcher::Plain { pattern, case_sensitive } => plain_text_spans(text, pattern, *case_sensitive),
+            Matcher::Regex(re) => re
+                .find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+            Matcher::Exact(value) if value == text => vec![(0, text.len())],
+            Matcher::Exact(_) => Vec::new(),
+        }
+    }
+}
+
+/// Computes match ranges for the `text` and `tag` filters
+///
+/// Uses the same matching as SQL filtering: `compile_user_regex` for regex
+/// modes, `plain_text_spans` (the span form of `lm_contains`) for plain text
+/// and the ASCII case-insensitive substring of tag `LIKE`.
+pub struct Highlighter {
+    msg: Option<Matcher>,
+    tags: Vec<Matcher>,
+}
+
+impl Highlighter {
+    pub fn new(filters: &LogFilters) -> Result<Self> {
+        let case_sensitive = filters.case_sensitive.unwrap_or(false);
+        let msg = match filters.text.as_deref().filter(|t| !t.is_empty()) {
+            Some(text) if filters.text_mode.as_deref() == Some("regex") => {
+                Some(Matcher::Regex(compile_user_regex(text, !case_sensitive)?))
+            }
+            Some(text) => Some(Matcher::Plain {
+                pattern: text.to_string(),
+                case_sensitive,
+            }),
+            None => None,
+        };
+
+        let mut tags = Vec::new();
+        if let Some(ref tag) = filters.tag {
+            for t in tag.split('|').map(|t| t.trim()).filter(|t| !t.is_empty()) {
+                tags.push(match filters.tag_mode.as_deref() {
+                    Some("regex") => Matcher::Regex(compile_user_regex(t, false)?),
+                    Some("exact") => Matcher::Exact(t.to_string()),
+                    _ => Matcher::Plain {
+                        pattern: t.to_ascii_lowercase(),
+                        case_sensitive: false,
+                    },
+                });
+            }
+        }
+
+        Ok(Self { msg, tags })
+    }
+
+    /// Set `row.matches` (left as `None` when there is nothing to highlight)
+    pub fn apply(&self, row: &mut LogRow) {
+        if self.msg.is_none() && self.tags.is_empty() {
+            return;
+        }
+
+        let msg = self.msg.as_ref().map(|m| m.spans(&row.msg)).unwrap_or_default();
+        let mut tag: Vec<(usize, usize)> = self.tags.iter().flat_map(|m| m.spans(&row.tag)).collect();
+        tag.sort_unstable();
+
+        row.matches = Some(RowMatches {
+            msg: to_utf16_spans(&row.msg, &merge_overlapping(msg)),
+            tag: to_utf16_spans(&row.tag, &merge_overlapping(tag)),
+        });
+    }
+}
+
+/// Highlight `rows` in place under `filters`
+pub fn highlight_rows(filters: &LogFilters, rows: &mut [LogRow]) -> Result<()> {
+    let highlighter = Highlighter::new(filters)?;
+    for row in rows {
+        highlighter.apply(row);
+    }
+    Ok(())
+}
+
+/// Highlight the search hits on the page a find jumped to
+///
+/// Matches under `search` rather than the view filters, which may have no
+/// text at all.
+pub fn highlight_find(search: &LogFilters, response: &mut FindResponse) -> Result<()> {
+    match response.page.as_mut() {
+        Some(page) => highlight_rows(search, &mut page.rows),
+        None => Ok(()),
+    }
+}
+
+/// Merge sorted ranges that overlap or touch
+fn merge_overlapping(spans: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Convert sorted byte ranges to UTF-16 code unit ranges
+fn to_utf16_spans(text: &str, spans: &[(usize, usize)]) -> Vec<MatchSpan> {
+    let mut result = Vec::with_capacity(spans.len());
+    let (mut byte_pos, mut utf16_pos) = (0, 0);
+    let mut advance = |to: usize| {
+        utf16_pos += text[byte_pos..to].encode_utf16().count();
+        byte_pos = to;
+        utf16_pos
+    };
+    for &(start, end) in spans {
+        let start = advance(start);
+        let end = advance(end);
+        result.push(MatchSpan { start, end });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(tag: &str, msg: &str) -> LogRow {
+        LogRow {
+            id: None,
+            ts: "08-24 14:22:33.123".to_string(),
+            ts_iso: None,
+            level: "I".to_string(),
+            tag: tag.to_string(),
+            pid: 1,
+            tid: 1,
+            msg: msg.to_string(),
+            matches: None,
+            repeat: None,
+            instance_id: None,
+        }
+    }
+
+    fn spans(pairs: &[(usize, usize)]) -> Vec<MatchSpan> {
+        pairs.iter().map(|&(start, end)| MatchSpan { start, end }).collect()
+    }
+
+    #[test]
+    fn test_plain_and_tag_spans() {
+        let filters = LogFilters {
+            text: Some("timeout".to_string()),
+            tag: Some("net|IVITY".to_string()),
+            ..Default::default()
+        };
+        let mut r = row("NetConnectivity", "Timeout after timeout");
+        highlight_rows(&filters, std::slice::from_mut(&mut r)).unwrap();
+
+        let matches = r.matches.unwrap();
+        assert_eq!(matches.msg, spans(&[(0, 7), (14, 21)]));
+        assert_eq!(matches.tag, spans(&[(0, 3), (10, 15)]));
+    }
+
+    #[test]
+    fn test_regex_spans_in_utf16() {
+        let filters = LogFilters {
+            text: Some(r"\d+ms".to_string()),
+            text_mode: Some("regex".to_string()),
+            case_sensitive: Some(true),
+            ..Default::default()
+        };
+        // "é" is 2 bytes but one UTF-16 unit, "😀" is 4 bytes and two units
+        let mut r = row("Test", "é😀 took 12ms");
+        Highlighter::new(&filters).unwrap().apply(&mut r);
+        assert_eq!(r.matches.unwrap().msg, spans(&[(9, 13)]));
+    }
+
+    #[test]
+    fn test_no_filters_leaves_row_untouched() {
+        let mut r = row("Test", "hello");
+        Highlighter::new(&LogFilters::default()).unwrap().apply(&mut r);
+        assert!(r.matches.is_none());
+    }
+}
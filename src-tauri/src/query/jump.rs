@@ -0,0 +1,101 @@
+use crate::error::{LogcatError, Result};
+use crate::query::events::EventKind;
+use crate::query::lang::parse_duration_ms;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// `[30s before|after] crash|anr [#]3`
+static EVENT_EXPR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^(?:(\d+(?:ms|s|m|h|d))\s+(before|after)\s+)?(crash|anr)\s*#?(\d+)$").unwrap()
+});
+
+/// `row 1234` / `row #1234`
+static ROW_EXPR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^row\s*#?(\d+)$").unwrap());
+
+/// Where a jump should land
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum JumpTarget {
+    /// First row at or after an absolute time (`YYYY-MM-DD HH:MM:SS[.mmm]`)
+    #[serde(rename_all = "camelCase")]
+    Time { time: String },
+    /// A specific row
+    #[serde(rename_all = "camelCase")]
+    Row { row_id: i64 },
+    /// The `number`-th (1-based) crash or ANR, shifted by `offset_ms`
+    #[serde(rename_all = "camelCase")]
+    Event {
+        event: EventKind,
+        number: usize,
+        #[serde(default)]
+        offset_ms: f64,
+    },
+    /// Free-form target such as "30s before crash #3", "row 1234" or a time
+    #[serde(rename_all = "camelCase")]
+    Expr { text: String },
+}
+
+/// Parse a free-form jump target
+pub fn parse_jump_expr(text: &str) -> Result<JumpTarget> {
+    let text = text.trim();
+
+    if let Some(caps) = ROW_EXPR_RE.captures(text) {
+        let row_id = caps[1]
+            .parse()
+            .map_err(|_| LogcatError::InvalidFilter(format!("Invalid row id: {}", &caps[1])))?;
+        return Ok(JumpTarget::Row { row_id });
+    }
+
+    if let Some(caps) = EVENT_EXPR_RE.captures(text) {
+        let offset = match caps.get(1) {
+            Some(d) => parse_duration_ms(&d.as_str().to_ascii_lowercase())
+                .ok_or_else(|| LogcatError::InvalidFilter(format!("Invalid duration: {}", d.as_str())))?,
+            None => 0.0,
+        };
+        let before = caps
+            .get(2)
+            .is_some_and(|w| w.as_str().eq_ignore_ascii_case("before"));
+        let event = if caps[3].eq_ignore_ascii_case("anr") {
+            EventKind::Anr
+        } else {
+            EventKind::Crash
+        };
+        let number = caps[4]
+            .parse()
+            .map_err(|_| LogcatError::InvalidFilter(format!("Invalid event number: {}", &caps[4])))?;
+        return Ok(JumpTarget::Event {
+            event,
+            number,
+            offset_ms: if before { -offset } else { offset },
+        });
+    }
+
+    Ok(JumpTarget::Time { time: text.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jump_expr() {
+        assert_eq!(
+            parse_jump_expr("30s before crash #3").unwrap(),
+            JumpTarget::Event { event: EventKind::Crash, number: 3, offset_ms: -30_000.0 }
+        );
+        assert_eq!(
+            parse_jump_expr("2m after ANR 1").unwrap(),
+            JumpTarget::Event { event: EventKind::Anr, number: 1, offset_ms: 120_000.0 }
+        );
+        assert_eq!(
+            parse_jump_expr("crash#2").unwrap(),
+            JumpTarget::Event { event: EventKind::Crash, number: 2, offset_ms: 0.0 }
+        );
+        assert_eq!(parse_jump_expr("row #42").unwrap(), JumpTarget::Row { row_id: 42 });
+        assert_eq!(
+            parse_jump_expr(" 2024-08-24 14:22:33 ").unwrap(),
+            JumpTarget::Time { time: "2024-08-24 14:22:33".to_string() }
+        );
+    }
+}
@@ -0,0 +1,114 @@
+/// Parsed query expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+/// Field a term applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Msg,
+    Tag,
+    Level,
+    Pid,
+    Tid,
+    /// Process instance id (one lifetime of a pid)
+    Instance,
+    Proc,
+    Since,
+    Until,
+    /// Extracted `key=value` message field
+    Extracted,
+}
+
+impl Field {
+    /// Resolve a field name (including aliases)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "msg" | "message" | "text" => Some(Field::Msg),
+            "tag" => Some(Field::Tag),
+            "level" | "lvl" => Some(Field::Level),
+            "pid" => Some(Field::Pid),
+            "tid" => Some(Field::Tid),
+            "instance" | "inst" => Some(Field::Instance),
+            "proc" | "process" => Some(Field::Proc),
+            "since" | "from" => Some(Field::Since),
+            "until" | "to" => Some(Field::Until),
+            "field" | "kv" => Some(Field::Extracted),
+            _ => None,
+        }
+    }
+}
+
+/// Comparison operator of a term
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// `:` substring / membership match
+    Contains,
+    /// `=` exact match
+    Equals,
+    /// `!=` exact mismatch
+    NotEquals,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Op {
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            ":" => Some(Op::Contains),
+            "=" => Some(Op::Equals),
+            "!=" => Some(Op::NotEquals),
+            ">" => Some(Op::Gt),
+            ">=" => Some(Op::Ge),
+            "<" => Some(Op::Lt),
+            "<=" => Some(Op::Le),
+            _ => None,
+        }
+    }
+
+    pub fn is_comparison(self) -> bool {
+        matches!(self, Op::Gt | Op::Ge | Op::Lt | Op::Le)
+    }
+}
+
+/// Value of a term
+#[derive(Debug, Clone, PartialEq)]
+pub enum TermValue {
+    /// One or more literal values (comma lists for tag/level/pid/tid/proc)
+    Text(Vec<String>),
+    /// `/regex/`
+    Regex(String),
+    /// Integer values for pid/tid/instance
+    Int(Vec<i64>),
+    /// Level letters
+    Level(Vec<char>),
+    /// Time bound for since/until
+    Time(TimeBound),
+    /// Extracted field key, with accepted values (any value if empty)
+    Field { key: String, values: Vec<String> },
+}
+
+/// Time value of a since/until term
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeBound {
+    /// Absolute time (Unix ms)
+    Absolute(f64),
+    /// Offset from the end of the log (ms, negative = before)
+    FromEnd(f64),
+}
+
+/// A single `field op value` condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub field: Field,
+    pub op: Op,
+    pub value: TermValue,
+    /// Character position of the term in the query
+    pub position: usize,
+}
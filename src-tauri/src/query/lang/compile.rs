@@ -0,0 +1,263 @@
+use crate::query::lang::ast::{Expr, Field, Op, Term, TermValue, TimeBound};
+use crate::query::process::ProcessStart;
+use crate::query::sql::{escape_like, field_condition, fts_phrase, LEVEL_ORDER, TRIGRAM_MIN_CHARS};
+use rusqlite::types::Value;
+
+/// Report data needed to compile a query
+#[derive(Debug, Clone, Default)]
+pub struct QueryContext {
+    /// Latest timestamp in the log (Unix ms), for `since:-5m`
+    pub log_end_ms: Option<f64>,
+    /// Process starts, for `proc:` lookups
+    pub processes: Vec<ProcessStart>,
+    /// Whether `logs_fts` uses the trigram tokenizer
+    pub trigram_fts: bool,
+}
+
+/// Compile an expression into a parameterized SQL condition on `logs`
+pub fn compile_expr(expr: &Expr, ctx: &QueryContext) -> (String, Vec<Value>) {
+    let mut params = Vec::new();
+    let sql = compile_into(expr, ctx, &mut params);
+    (sql, params)
+}
+
+fn compile_into(expr: &Expr, ctx: &QueryContext, params: &mut Vec<Value>) -> String {
+    match expr {
+        Expr::And(items) => join(items, " AND ", ctx, params),
+        Expr::Or(items) => join(items, " OR ", ctx, params),
+        Expr::Not(inner) => format!("NOT ({})", compile_into(inner, ctx, params)),
+        Expr::Term(term) => compile_term(term, ctx, params),
+    }
+}
+
+fn join(items: &[Expr], sep: &str, ctx: &QueryContext, params: &mut Vec<Value>) -> String {
+    let parts: Vec<String> = items.iter().map(|e| compile_into(e, ctx, params)).collect();
+    format!("({})", parts.join(sep))
+}
+
+fn compile_term(term: &Term, ctx: &QueryContext, params: &mut Vec<Value>) -> String {
+    let column = match term.field {
+        Field::Msg => "msg",
+        Field::Tag => "tag",
+        Field::Level => "level",
+        Field::Pid | Field::Proc => "pid",
+        Field::Tid => "tid",
+        Field::Instance => "instance_id",
+        Field::Since | Field::Until => "ts_unix",
+        Field::Extracted => "id",
+    };
+
+    match (&term.value, term.field) {
+        (TermValue::Regex(pattern), _) => {
+            params.push(Value::Text(pattern.clone()));
+            format!("{} REGEXP ?", column)
+        }
+        (TermValue::Text(values), Field::Msg) => {
+            let sql = any_of(values, params, |value, params| msg_condition(term.op, value, ctx, params));
+            negate_if(term.op, sql)
+        }
+        (TermValue::Text(values), Field::Tag) => match term.op {
+            Op::Contains => any_of(values, params, |value, params| {
+                params.push(Value::Text(format!("%{}%", escape_like(value))));
+                "tag LIKE ? ESCAPE '\\'".to_string()
+            }),
+            _ => in_list(column, term.op, values.iter().map(|v| Value::Text(v.clone())), params),
+        },
+        (TermValue::Text(values), Field::Proc) => {
+            let pids = resolve_processes(values, term.op, &ctx.processes);
+            in_list(column, term.op, pids.into_iter().map(|p| Value::Integer(p as i64)), params)
+        }
+        (TermValue::Level(levels), _) => {
+            let levels: Vec<char> = if term.op.is_comparison() {
+                levels_in_range(levels[0], term.op)
+            } else {
+                levels.clone()
+            };
+            in_list(column, term.op, levels.into_iter().map(|l| Value::Text(l.to_string())), params)
+        }
+        (TermValue::Int(values), _) => match term.op {
+            op if op.is_comparison() => {
+                params.push(Value::Integer(values[0]));
+                format!("{} {} ?", column, comparison_symbol(op))
+            }
+            op => in_list(column, op, values.iter().map(|v| Value::Integer(*v)), params),
+        },
+        (TermValue::Time(bound), field) => {
+            let ms = match bound {
+                TimeBound::Absolute(ms) => Some(*ms),
+                TimeBound::FromEnd(offset) => ctx.log_end_ms.map(|end| end + offset),
+            };
+            match ms {
+                Some(ms) => {
+                    params.push(Value::Real(ms));
+                    let symbol = if field == Field::Since { ">=" } else { "<=" };
+                    format!("ts_unix {} ?", symbol)
+                }
+                // Empty log: a relative bound matches nothing
+                None => "0".to_string(),
+            }
+        }
+        (TermValue::Field { key, values }, _) => field_condition(key, values, params),
+        (TermValue::Text(_), _) => "0".to_string(),
+    }
+}
+
+/// Message condition for one value (`=` / `!=` exact, `:` substring)
+fn msg_condition(op: Op, value: &str, ctx: &QueryContext, params: &mut Vec<Value>) -> String {
+    if op != Op::Contains {
+        params.push(Value::Text(value.to_string()));
+        return "msg = ?".to_string();
+    }
+
+    let mut sql = String::new();
+    if ctx.trigram_fts && value.chars().count() >= TRIGRAM_MIN_CHARS {
+        // Narrow candidates through the trigram index first
+        params.push(Value::Text(fts_phrase(value)));
+        sql.push_str("id IN (SELECT rowid FROM logs_fts WHERE logs_fts MATCH ?) AND ");
+    }
+    params.push(Value::Text(value.to_string()));
+    sql.push_str("lm_contains(msg, ?, 0)");
+    format!("({})", sql)
+}
+
+/// OR of a per-value condition
+fn any_of<F>(values: &[String], params: &mut Vec<Value>, mut condition: F) -> String
+where
+    F: FnMut(&str, &mut Vec<Value>) -> String,
+{
+    let parts: Vec<String> = values.iter().map(|v| condition(v, params)).collect();
+    if parts.len() == 1 {
+        parts.into_iter().next().unwrap_or_default()
+    } else {
+        format!("({})", parts.join(" OR "))
+    }
+}
+
+/// Wrap `!=` conditions in NOT
+fn negate_if(op: Op, sql: String) -> String {
+    if op == Op::NotEquals {
+        format!("NOT {}", sql)
+    } else {
+        sql
+    }
+}
+
+/// `column IN (...)`, or `NOT IN` for `!=`
+fn in_list<I>(column: &str, op: Op, values: I, params: &mut Vec<Value>) -> String
+where
+    I: IntoIterator<Item = Value>,
+{
+    let before = params.len();
+    params.extend(values);
+    let count = params.len() - before;
+
+    if count == 0 {
+        // Nothing to match (e.g. unknown process)
+        return if op == Op::NotEquals { "1".to_string() } else { "0".to_string() };
+    }
+
+    let placeholders = vec!["?"; count].join(",");
+    let keyword = if op == Op::NotEquals { "NOT IN" } else { "IN" };
+    format!("{} {} ({})", column, keyword, placeholders)
+}
+
+fn comparison_symbol(op: Op) -> &'static str {
+    match op {
+        Op::Gt => ">",
+        Op::Ge => ">=",
+        Op::Lt => "<",
+        Op::Le => "<=",
+        _ => "=",
+    }
+}
+
+/// Levels satisfying `level <op> bound` in V < D < I < W < E < F order
+fn levels_in_range(bound: char, op: Op) -> Vec<char> {
+    let rank = LEVEL_ORDER.iter().position(|&l| l == bound).unwrap_or(0);
+    LEVEL_ORDER
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| match op {
+            Op::Gt => *i > rank,
+            Op::Ge => *i >= rank,
+            Op::Lt => *i < rank,
+            Op::Le => *i <= rank,
+            _ => *i == rank,
+        })
+        .map(|(_, &l)| l)
+        .collect()
+}
+
+/// Pids of processes whose name matches (substring for `:`, exact otherwise)
+fn resolve_processes(names: &[String], op: Op, processes: &[ProcessStart]) -> Vec<i32> {
+    let mut pids: Vec<i32> = processes
+        .iter()
+        .filter(|p| {
+            names.iter().any(|name| match op {
+                Op::Contains => p.name.to_lowercase().contains(&name.to_lowercase()),
+                _ => p.name == *name,
+            })
+        })
+        .map(|p| p.pid)
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    pids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::lang::parse_query;
+
+    fn compile(query: &str, ctx: &QueryContext) -> (String, Vec<Value>) {
+        compile_expr(&parse_query(query).unwrap().unwrap(), ctx)
+    }
+
+    #[test]
+    fn test_compile_level_and_tag() {
+        let (sql, params) = compile("level>=W -tag:chatty", &QueryContext::default());
+        assert_eq!(sql, "(level IN (?,?,?) AND NOT (tag LIKE ? ESCAPE '\\'))");
+        assert_eq!(params.len(), 4);
+        assert_eq!(params[3], Value::Text("%chatty%".to_string()));
+    }
+
+    #[test]
+    fn test_compile_proc_and_since() {
+        let ctx = QueryContext {
+            log_end_ms: Some(1_000_000.0),
+            processes: vec![
+                ProcessStart { pid: 10, name: "com.foo".to_string(), ts_unix: 0.0 },
+                ProcessStart { pid: 11, name: "com.foo:remote".to_string(), ts_unix: 0.0 },
+                ProcessStart { pid: 12, name: "com.bar".to_string(), ts_unix: 0.0 },
+            ],
+            trigram_fts: false,
+        };
+
+        let (sql, params) = compile("proc=com.foo since:-5m", &ctx);
+        assert_eq!(sql, "(pid IN (?) AND ts_unix >= ?)");
+        assert_eq!(params, vec![Value::Integer(10), Value::Real(700_000.0)]);
+
+        let (sql, _) = compile("proc:unknown", &ctx);
+        assert_eq!(sql, "0");
+    }
+
+    #[test]
+    fn test_compile_extracted_field() {
+        let (sql, params) = compile("field:Reason=timeout -field:uid", &QueryContext::default());
+        assert_eq!(
+            sql,
+            "(id IN (SELECT row_id FROM log_fields WHERE key = ? AND value IN (?)) AND \
+             NOT (id IN (SELECT row_id FROM log_fields WHERE key = ?)))"
+        );
+        assert_eq!(params[0], Value::Text("reason".to_string()));
+    }
+
+    #[test]
+    fn test_compile_msg_uses_fts() {
+        let ctx = QueryContext { trigram_fts: true, ..Default::default() };
+        let (sql, params) = compile("timeout", &ctx);
+        assert!(sql.contains("logs_fts MATCH ?"));
+        assert_eq!(params[0], Value::Text("\"timeout\"".to_string()));
+    }
+}
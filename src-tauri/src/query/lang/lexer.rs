@@ -0,0 +1,243 @@
+use crate::error::{LogcatError, Result};
+
+/// Token kinds of the query language
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    LParen,
+    RParen,
+    /// `-` or `!` directly before a term
+    Not,
+    /// `AND` / `OR` / `NOT` keywords (uppercase only)
+    And,
+    Or,
+    NotKeyword,
+    /// Field operator: `:`, `=`, `!=`, `>`, `>=`, `<`, `<=`
+    Op(String),
+    /// Bare word
+    Word(String),
+    /// `"quoted string"`
+    Quoted(String),
+    /// `/regex/`
+    Regex(String),
+}
+
+/// Token with its character position in the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub position: usize,
+}
+
+/// Build a syntax error at a character position
+pub fn syntax_error(position: usize, message: impl Into<String>) -> LogcatError {
+    LogcatError::QuerySyntax {
+        position,
+        message: message.into(),
+    }
+}
+
+/// Split a query into tokens
+pub fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // Values directly following an operator may contain ':' etc.
+        let after_op = matches!(tokens.last(), Some(Token { kind: TokenKind::Op(_), .. }))
+            && i > 0
+            && !chars[i - 1].is_whitespace();
+
+        let start = i;
+        let kind = match c {
+            '(' if !after_op => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            '-' | '!' if !after_op && i + 1 < chars.len() && starts_term(chars[i + 1]) => {
+                i += 1;
+                TokenKind::Not
+            }
+            '"' => {
+                let (text, end) = read_delimited(&chars, i, '"')?;
+                i = end;
+                TokenKind::Quoted(text)
+            }
+            '/' => {
+                let (text, end) = read_delimited(&chars, i, '/')?;
+                if text.is_empty() {
+                    return Err(syntax_error(start, "Empty regex"));
+                }
+                i = end;
+                TokenKind::Regex(text)
+            }
+            _ if after_op => {
+                while i < chars.len() && !chars[i].is_whitespace() && chars[i] != ')' {
+                    i += 1;
+                }
+                TokenKind::Word(chars[start..i].iter().collect())
+            }
+            _ if is_op_char(c) => {
+                return Err(syntax_error(start, format!("Unexpected '{}'", c)));
+            }
+            _ => {
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.as_str() {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::NotKeyword,
+                    _ => TokenKind::Word(word),
+                }
+            }
+        };
+        tokens.push(Token { kind, position: start });
+
+        // Operator directly after a word: `field:value`
+        if matches!(tokens.last(), Some(Token { kind: TokenKind::Word(_), .. }))
+            && i < chars.len()
+            && is_op_char(chars[i])
+        {
+            let op_start = i;
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let op = match two.as_str() {
+                ">=" | "<=" | "!=" => two,
+                _ if chars[i] == '!' => {
+                    return Err(syntax_error(op_start, "Expected '!='"));
+                }
+                _ => chars[i].to_string(),
+            };
+            i += op.chars().count();
+            if i >= chars.len() || chars[i].is_whitespace() || chars[i] == ')' {
+                return Err(syntax_error(op_start, format!("Missing value after '{}'", op)));
+            }
+            tokens.push(Token { kind: TokenKind::Op(op), position: op_start });
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_op_char(c: char) -> bool {
+    matches!(c, ':' | '=' | '<' | '>' | '!')
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '"') && !is_op_char(c)
+}
+
+/// Whether a character can begin a negated term
+fn starts_term(c: char) -> bool {
+    !c.is_whitespace() && c != ')'
+}
+
+/// Read a `"..."` or `/.../` literal starting at `start`
+///
+/// Returns the unescaped text and the index after the closing delimiter.
+/// Only the delimiter (and `\\` in strings) is unescaped so regex escapes
+/// like `\d` survive.
+fn read_delimited(chars: &[char], start: usize, delim: char) -> Result<(String, usize)> {
+    let mut text = String::new();
+    let mut i = start + 1;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() && (chars[i + 1] == delim || (delim == '"' && chars[i + 1] == '\\')) => {
+                text.push(chars[i + 1]);
+                i += 2;
+            }
+            c if c == delim => return Ok((text, i + 1)),
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    let what = if delim == '"' { "string" } else { "regex" };
+    Err(syntax_error(start, format!("Unterminated {}", what)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input).unwrap().into_iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn test_field_terms() {
+        assert_eq!(
+            kinds("tag:ActivityManager level>=W -tag:chatty"),
+            vec![
+                TokenKind::Word("tag".into()),
+                TokenKind::Op(":".into()),
+                TokenKind::Word("ActivityManager".into()),
+                TokenKind::Word("level".into()),
+                TokenKind::Op(">=".into()),
+                TokenKind::Word("W".into()),
+                TokenKind::Not,
+                TokenKind::Word("tag".into()),
+                TokenKind::Op(":".into()),
+                TokenKind::Word("chatty".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_values_after_operator() {
+        assert_eq!(
+            kinds("since:-5m until:2024-08-24T14:22"),
+            vec![
+                TokenKind::Word("since".into()),
+                TokenKind::Op(":".into()),
+                TokenKind::Word("-5m".into()),
+                TokenKind::Word("until".into()),
+                TokenKind::Op(":".into()),
+                TokenKind::Word("2024-08-24T14:22".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quoted_and_regex() {
+        assert_eq!(
+            kinds(r#"(msg:"say \"hi\"" OR /timeout \d+ms/)"#),
+            vec![
+                TokenKind::LParen,
+                TokenKind::Word("msg".into()),
+                TokenKind::Op(":".into()),
+                TokenKind::Quoted(r#"say "hi""#.into()),
+                TokenKind::Or,
+                TokenKind::Regex(r"timeout \d+ms".into()),
+                TokenKind::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_positions() {
+        match tokenize("tag:x \"open") {
+            Err(LogcatError::QuerySyntax { position, .. }) => assert_eq!(position, 6),
+            other => panic!("unexpected {:?}", other),
+        }
+        match tokenize("tag: x") {
+            Err(LogcatError::QuerySyntax { position, .. }) => assert_eq!(position, 3),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
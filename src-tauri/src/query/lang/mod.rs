@@ -0,0 +1,7 @@
+mod ast;
+mod lexer;
+mod parser;
+mod compile;
+
+pub use parser::{parse_duration_ms, parse_query};
+pub use compile::{compile_expr, QueryContext};
@@ -0,0 +1,394 @@
+use crate::error::Result;
+use crate::query::filter::compile_user_regex;
+use crate::query::lang::ast::{Expr, Field, Op, Term, TermValue, TimeBound};
+use crate::query::lang::lexer::{syntax_error, tokenize, Token, TokenKind};
+
+/// Parse a query string into an expression (`None` for an empty query)
+///
+/// Grammar (terms separated by whitespace are ANDed):
+///
+/// ```text
+/// or      := and ("OR" and)*
+/// and     := unary (["AND"] unary)*
+/// unary   := ("-" | "!" | "NOT") unary | primary
+/// primary := "(" or ")" | field op value | "quoted" | /regex/ | word
+/// ```
+///
+/// `field:key=value` matches extracted message fields, `field:key` any value:
+///
+/// ```text
+/// field:reason=timeout,anr  field:"reason=user request"  -field:uid
+/// ```
+pub fn parse_query(input: &str) -> Result<Option<Expr>> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.chars().count(),
+    };
+    let expr = parser.parse_or()?;
+
+    if let Some(token) = parser.peek() {
+        return Err(syntax_error(token.position, "Unexpected ')'"));
+    }
+    Ok(Some(expr))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Query length in characters, for errors at end of input
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut items = vec![self.parse_and()?];
+        while matches!(self.peek(), Some(Token { kind: TokenKind::Or, .. })) {
+            self.next();
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::Or(items) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut items = vec![self.parse_unary()?];
+        loop {
+            match self.peek().map(|t| &t.kind) {
+                None | Some(TokenKind::RParen) | Some(TokenKind::Or) => break,
+                Some(TokenKind::And) => {
+                    self.next();
+                    items.push(self.parse_unary()?);
+                }
+                Some(_) => items.push(self.parse_unary()?),
+            }
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::And(items) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if matches!(
+            self.peek().map(|t| &t.kind),
+            Some(TokenKind::Not) | Some(TokenKind::NotKeyword)
+        ) {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let token = match self.next() {
+            Some(t) => t,
+            None => return Err(syntax_error(self.end, "Expected a term")),
+        };
+
+        match token.kind {
+            TokenKind::LParen => {
+                if matches!(self.peek(), Some(Token { kind: TokenKind::RParen, .. })) {
+                    return Err(syntax_error(token.position, "Empty group"));
+                }
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token { kind: TokenKind::RParen, .. }) => Ok(expr),
+                    _ => Err(syntax_error(token.position, "Unclosed '('")),
+                }
+            }
+            TokenKind::RParen => Err(syntax_error(token.position, "Unexpected ')'")),
+            TokenKind::And | TokenKind::Or => Err(syntax_error(
+                token.position,
+                "Expected a term before AND/OR",
+            )),
+            TokenKind::Word(word) => {
+                if let Some(Token { kind: TokenKind::Op(_), .. }) = self.peek() {
+                    return self.parse_field_term(word, token.position);
+                }
+                Ok(text_term(Field::Msg, Op::Contains, vec![word], token.position))
+            }
+            TokenKind::Quoted(text) => Ok(text_term(Field::Msg, Op::Contains, vec![text], token.position)),
+            TokenKind::Regex(pattern) => regex_term(Field::Msg, pattern, token.position, token.position),
+            TokenKind::Op(_) | TokenKind::Not | TokenKind::NotKeyword => {
+                Err(syntax_error(token.position, "Expected a term"))
+            }
+        }
+    }
+
+    fn parse_field_term(&mut self, name: String, position: usize) -> Result<Expr> {
+        let field = Field::from_name(&name)
+            .ok_or_else(|| syntax_error(position, format!("Unknown field '{}'", name)))?;
+
+        let op_token = self.next().expect("operator token");
+        let symbol = match op_token.kind {
+            TokenKind::Op(s) => s,
+            _ => unreachable!("peeked an operator"),
+        };
+        let op = Op::from_symbol(&symbol)
+            .ok_or_else(|| syntax_error(op_token.position, format!("Unknown operator '{}'", symbol)))?;
+
+        let value = self
+            .next()
+            .ok_or_else(|| syntax_error(self.end, format!("Missing value for '{}'", name)))?;
+
+        if field == Field::Extracted {
+            return extracted_field_term(op, op_token.position, value, position);
+        }
+
+        if op.is_comparison() && !matches!(field, Field::Level | Field::Pid | Field::Tid) {
+            return Err(syntax_error(
+                op_token.position,
+                format!("'{}' does not support '{}'", name, symbol),
+            ));
+        }
+
+        match (field, value.kind) {
+            (Field::Msg | Field::Tag, TokenKind::Regex(pattern)) => {
+                if op != Op::Contains {
+                    return Err(syntax_error(op_token.position, "Regex values require ':'"));
+                }
+                regex_term(field, pattern, value.position, position)
+            }
+            (_, TokenKind::Regex(_)) => Err(syntax_error(
+                value.position,
+                format!("'{}' does not support regex values", name),
+            )),
+            (field, TokenKind::Quoted(text)) => build_value_term(field, op, vec![text], value.position, position),
+            (field, TokenKind::Word(word)) => {
+                // Unquoted values are comma lists except for message text
+                let values = if field == Field::Msg {
+                    vec![word]
+                } else {
+                    word.split(',').map(|v| v.to_string()).collect()
+                };
+                build_value_term(field, op, values, value.position, position)
+            }
+            _ => Err(syntax_error(value.position, format!("Missing value for '{}'", name))),
+        }
+    }
+}
+
+fn text_term(field: Field, op: Op, values: Vec<String>, position: usize) -> Expr {
+    Expr::Term(Term {
+        field,
+        op,
+        value: TermValue::Text(values),
+        position,
+    })
+}
+
+fn regex_term(field: Field, pattern: String, value_position: usize, position: usize) -> Result<Expr> {
+    compile_user_regex(&pattern, false).map_err(|e| syntax_error(value_position, e.to_string()))?;
+    Ok(Expr::Term(Term {
+        field,
+        op: Op::Contains,
+        value: TermValue::Regex(pattern),
+        position,
+    }))
+}
+
+/// `field:key`, `field:key=a,b` or `field:"key=text with spaces"`
+fn extracted_field_term(op: Op, op_position: usize, value: Token, position: usize) -> Result<Expr> {
+    if op != Op::Contains {
+        return Err(syntax_error(op_position, "'field' takes ':' (field:key=value)"));
+    }
+
+    let (text, split) = match value.kind {
+        TokenKind::Word(word) => (word, true),
+        TokenKind::Quoted(text) => (text, false),
+        _ => return Err(syntax_error(value.position, "Expected key or key=value")),
+    };
+    let (key, values) = match text.split_once('=') {
+        Some((key, rest)) if split => (key, rest.split(',').map(|v| v.to_string()).collect()),
+        Some((key, rest)) => (key, vec![rest.to_string()]),
+        None => (text.as_str(), Vec::new()),
+    };
+    if key.is_empty() || values.iter().any(|v: &String| v.is_empty()) {
+        return Err(syntax_error(value.position, "Expected key or key=value"));
+    }
+
+    Ok(Expr::Term(Term {
+        field: Field::Extracted,
+        op,
+        value: TermValue::Field { key: key.to_string(), values },
+        position,
+    }))
+}
+
+fn build_value_term(
+    field: Field,
+    op: Op,
+    values: Vec<String>,
+    value_position: usize,
+    position: usize,
+) -> Result<Expr> {
+    if values.iter().any(|v| v.is_empty()) {
+        return Err(syntax_error(value_position, "Empty value in list"));
+    }
+    if op.is_comparison() && values.len() > 1 {
+        return Err(syntax_error(value_position, "Comparisons take a single value"));
+    }
+
+    let value = match field {
+        Field::Msg | Field::Tag | Field::Proc => TermValue::Text(values),
+        Field::Level => TermValue::Level(
+            values
+                .iter()
+                .map(|v| parse_level(v).ok_or_else(|| syntax_error(value_position, format!("Invalid level '{}'", v))))
+                .collect::<Result<_>>()?,
+        ),
+        Field::Pid | Field::Tid | Field::Instance => TermValue::Int(
+            values
+                .iter()
+                .map(|v| v.parse::<i64>().map_err(|_| syntax_error(value_position, format!("Invalid number '{}'", v))))
+                .collect::<Result<_>>()?,
+        ),
+        Field::Extracted => unreachable!("parsed by extracted_field_term"),
+        Field::Since | Field::Until => {
+            if values.len() > 1 || !matches!(op, Op::Contains | Op::Equals) {
+                return Err(syntax_error(value_position, "Time bounds take a single value after ':'"));
+            }
+            TermValue::Time(parse_time_bound(&values[0]).ok_or_else(|| {
+                syntax_error(
+                    value_position,
+                    format!("Invalid time '{}' (use e.g. -5m or 2024-08-24T14:22:33)", values[0]),
+                )
+            })?)
+        }
+    };
+
+    Ok(Expr::Term(Term { field, op, value, position }))
+}
+
+/// Level letter (V/D/I/W/E/F, case-insensitive)
+fn parse_level(value: &str) -> Option<char> {
+    let mut chars = value.chars();
+    let c = chars.next()?.to_ascii_uppercase();
+    (chars.next().is_none() && "VDIWEF".contains(c)).then_some(c)
+}
+
+/// `-5m` style offsets from the log end, or absolute timestamps
+fn parse_time_bound(value: &str) -> Option<TimeBound> {
+    if let Some(rest) = value.strip_prefix('-') {
+        return parse_duration_ms(rest).map(|ms| TimeBound::FromEnd(-ms));
+    }
+
+    crate::time::iso_ts_key_ms(value)
+        .ok()
+        .map(|ms| TimeBound::Absolute(ms as f64))
+}
+
+/// Durations like `500ms`, `30s`, `5m`, `2h`, `1d` in milliseconds
+pub fn parse_duration_ms(value: &str) -> Option<f64> {
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let (number, unit) = value.split_at(split);
+    let n: f64 = number.parse().ok()?;
+    let unit_ms = match unit {
+        "ms" => 1.0,
+        "s" => 1000.0,
+        "m" => 60_000.0,
+        "h" => 3_600_000.0,
+        "d" => 86_400_000.0,
+        _ => return None,
+    };
+    Some(n * unit_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LogcatError;
+
+    fn term(expr: &Expr) -> &Term {
+        match expr {
+            Expr::Term(t) => t,
+            other => panic!("expected term, got {:?}", other),
+        }
+    }
+
+    fn error_position(input: &str) -> usize {
+        match parse_query(input) {
+            Err(LogcatError::QuerySyntax { position, .. }) => position,
+            other => panic!("expected syntax error for {:?}, got {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn test_full_example() {
+        let expr = parse_query(
+            r#"tag:ActivityManager level>=W (msg:"ANR" OR msg:/timeout \d+ms/) -tag:chatty pid:1234,5678 proc:com.foo since:-5m"#,
+        )
+        .unwrap()
+        .unwrap();
+
+        let items = match expr {
+            Expr::And(items) => items,
+            other => panic!("expected AND, got {:?}", other),
+        };
+        assert_eq!(items.len(), 7);
+        assert_eq!(term(&items[1]).op, Op::Ge);
+        assert_eq!(term(&items[1]).value, TermValue::Level(vec!['W']));
+        assert!(matches!(&items[2], Expr::Or(v) if v.len() == 2));
+        assert!(matches!(&items[3], Expr::Not(_)));
+        assert_eq!(term(&items[4]).value, TermValue::Int(vec![1234, 5678]));
+        assert_eq!(term(&items[6]).value, TermValue::Time(TimeBound::FromEnd(-300_000.0)));
+    }
+
+    #[test]
+    fn test_precedence() {
+        // AND binds tighter than OR
+        let expr = parse_query("a b OR c").unwrap().unwrap();
+        match expr {
+            Expr::Or(items) => {
+                assert!(matches!(&items[0], Expr::And(v) if v.len() == 2));
+                assert!(matches!(&items[1], Expr::Term(_)));
+            }
+            other => panic!("expected OR, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_empty_query() {
+        assert_eq!(parse_query("   ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_error_positions() {
+        assert_eq!(error_position("level:X"), 6);
+        assert_eq!(error_position("foo:bar"), 0);
+        assert_eq!(error_position("(tag:a"), 0);
+        assert_eq!(error_position("tag:a )"), 6);
+        assert_eq!(error_position("a OR"), 4);
+        assert_eq!(error_position("pid:12x"), 4);
+        assert_eq!(error_position("msg:/(a+)+/"), 4);
+        assert_eq!(error_position("tag>x"), 3);
+        assert_eq!(error_position("field=reason"), 5);
+        assert_eq!(error_position("field:=x"), 6);
+    }
+
+    #[test]
+    fn test_extracted_fields() {
+        let field = |query: &str| term(&parse_query(query).unwrap().unwrap()).value.clone();
+        assert_eq!(
+            field("field:reason=timeout,anr"),
+            TermValue::Field { key: "reason".into(), values: vec!["timeout".into(), "anr".into()] }
+        );
+        assert_eq!(
+            field(r#"kv:"reason=user request, retry""#),
+            TermValue::Field { key: "reason".into(), values: vec!["user request, retry".into()] }
+        );
+        assert_eq!(field("field:uid"), TermValue::Field { key: "uid".into(), values: vec![] });
+    }
+}
@@ -1,7 +1,21 @@
 mod filter;
 mod cursor;
 mod executor;
+mod sql;
+mod process;
+mod lang;
+mod events;
+mod aggregate;
+mod highlight;
+mod jump;
+mod collapse;
 
 pub use filter::{compile_user_regex, validate_regex_safety};
-pub use cursor::{QueryCursor, CursorDirection, QueryResponse, LogcatStats, LevelCounts};
+pub use cursor::{QueryCursor, CursorDirection, QueryResponse, LogcatStats, LevelCounts, ContextScope, ContextResponse, FindResponse, Histogram, JumpResponse};
 pub use executor::QueryExecutor;
+pub use highlight::{highlight_find, highlight_rows};
+pub use jump::JumpTarget;
+pub use collapse::CollapseMode;
+pub use aggregate::{AggregateBy, AggregateSort, AggregateResponse, TemplateResponse, message_template, TEMPLATE_WILDCARD};
+pub use lang::parse_query;
+pub use process::parse_process_start;
@@ -0,0 +1,73 @@
+use crate::error::{LogcatError, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::Connection;
+
+/// `am_proc_start: [user,pid,uid,process,...]` (events buffer)
+static AM_PROC_START_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[\d+,(\d+),\d+,([^,\]]+)").unwrap());
+
+/// `Start proc 1234:com.foo/u0a123 for ...` (ActivityManager)
+static START_PROC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Start proc (\d+):([^/\s]+)").unwrap());
+
+/// A process start seen in the log
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessStart {
+    pub pid: i32,
+    pub name: String,
+    /// Timestamp of the start record (Unix ms)
+    pub ts_unix: f64,
+}
+
+/// Collect process starts from `am_proc_start` and ActivityManager records
+pub fn load_process_starts(conn: &Connection) -> Result<Vec<ProcessStart>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT msg, ts_unix FROM logs
+             WHERE tag = 'am_proc_start' OR (tag = 'ActivityManager' AND msg LIKE 'Start proc %')
+             ORDER BY ts_unix, id",
+        )
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    let mut starts = Vec::new();
+    for row in rows {
+        let (msg, ts_unix) = row.map_err(|e| LogcatError::Database(e.to_string()))?;
+        if let Some((pid, name)) = parse_process_start(&msg) {
+            starts.push(ProcessStart { pid, name, ts_unix });
+        }
+    }
+
+    Ok(starts)
+}
+
+/// Extract (pid, process name) from a process start message
+pub fn parse_process_start(msg: &str) -> Option<(i32, String)> {
+    let caps = AM_PROC_START_RE
+        .captures(msg)
+        .or_else(|| START_PROC_RE.captures(msg))?;
+    let pid = caps[1].parse().ok()?;
+    Some((pid, caps[2].trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_process_start() {
+        assert_eq!(
+            parse_process_start("[0,4567,10123,com.foo,activity,{com.foo/com.foo.Main}]"),
+            Some((4567, "com.foo".to_string()))
+        );
+        assert_eq!(
+            parse_process_start("Start proc 4567:com.foo:remote/u0a123 for service"),
+            Some((4567, "com.foo:remote".to_string()))
+        );
+        assert_eq!(parse_process_start("Killing 4567:com.foo"), None);
+    }
+}
@@ -0,0 +1,447 @@
+use crate::error::{LogcatError, Result};
+use crate::query::filter::compile_user_regex;
+use crate::types::LogFilters;
+use rusqlite::types::Value;
+use rusqlite::ToSql;
+
+/// Minimum pattern length the trigram FTS index can match
+pub const TRIGRAM_MIN_CHARS: usize = 3;
+
+/// Parameterized WHERE conditions compiled from `LogFilters`
+#[derive(Debug, Clone, Default)]
+pub struct FilterSql {
+    pub conditions: Vec<String>,
+    pub params: Vec<Value>,
+}
+
+impl FilterSql {
+    /// Add a condition with its parameters
+    pub fn push<I: IntoIterator<Item = Value>>(&mut self, condition: impl Into<String>, params: I) {
+        self.conditions.push(condition.into());
+        self.params.extend(params);
+    }
+
+    /// Add all conditions of `other` (ANDed with the existing ones)
+    pub fn extend(&mut self, other: FilterSql) {
+        self.conditions.extend(other.conditions);
+        self.params.extend(other.params);
+    }
+
+    /// `WHERE ...` clause, or empty if there are no conditions
+    pub fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", self.conditions.join(" AND "))
+        }
+    }
+
+    /// Parameters as references for rusqlite
+    pub fn param_refs(&self) -> Vec<&dyn ToSql> {
+        self.params.iter().map(|p| p as &dyn ToSql).collect()
+    }
+}
+
+/// Options affecting how filters are compiled
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqlOptions {
+    /// Whether `logs_fts` uses the trigram tokenizer (substring MATCH)
+    pub trigram_fts: bool,
+}
+
+/// Compile `LogFilters` into parameterized SQL conditions on `logs`
+///
+/// Text filters run inside SQLite (FTS prefilter plus the registered
+/// `lm_contains` / `REGEXP` functions) so `LIMIT` applies after filtering.
+pub fn build_filter_sql(filters: &LogFilters, options: SqlOptions) -> Result<FilterSql> {
+    let mut sql = FilterSql::default();
+
+    // Level filter (parameterized)
+    if let Some(ref levels) = filters.levels {
+        if !levels.is_empty() {
+            let placeholders: Vec<&str> = levels.iter().map(|_| "?").collect();
+            sql.push(
+                format!("level IN ({})", placeholders.join(",")),
+                levels.iter().map(|l| Value::Text(l.clone())),
+            );
+        }
+    }
+
+    // Minimum level (e.g. "W" keeps W, E and F)
+    if let Some(ref min_level) = filters.min_level {
+        let levels = min_level
+            .chars()
+            .next()
+            .map(|c| levels_at_least(c.to_ascii_uppercase()))
+            .unwrap_or_default();
+        if levels.is_empty() {
+            return Err(LogcatError::InvalidFilter(format!("Invalid minimum level: {}", min_level)));
+        }
+        push_in_list(&mut sql, "level", false, levels.iter().map(|l| Value::Text(l.to_string())));
+    }
+
+    // Tag filters (supports OR with | in `tag`)
+    let tag_mode = TagMode::parse(filters.tag_mode.as_deref())?;
+    if let Some(ref tag) = filters.tag {
+        let tags: Vec<&str> = tag.split('|').map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+        push_tag_filter(&mut sql, &tags, tag_mode, false)?;
+    }
+
+    if let Some(ref exclude_tags) = filters.exclude_tags {
+        let tags: Vec<&str> = exclude_tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+        push_tag_filter(&mut sql, &tags, tag_mode, true)?;
+    }
+
+    // PID filters (parameterized)
+    if let Some(pid) = filters.pid {
+        sql.push("pid = ?", [Value::Integer(pid as i64)]);
+    }
+    push_id_lists(&mut sql, "pid", filters.pids.as_deref(), filters.exclude_pids.as_deref());
+
+    // Process instance filter (a pid between its start and death)
+    if let Some(instances) = filters.instances.as_ref().filter(|ids| !ids.is_empty()) {
+        push_in_list(&mut sql, "instance_id", false, instances.iter().map(|id| Value::Integer(*id)));
+    }
+
+    // TID filters (parameterized)
+    if let Some(tid) = filters.tid {
+        sql.push("tid = ?", [Value::Integer(tid as i64)]);
+    }
+    push_id_lists(&mut sql, "tid", filters.tids.as_deref(), filters.exclude_tids.as_deref());
+
+    // Time range filter (parameterized)
+    if let Some(ref ts_from) = filters.ts_from {
+        if let Ok(ms) = crate::time::iso_ts_key_ms(ts_from) {
+            sql.push("ts_unix >= ?", [Value::Real(ms as f64)]);
+        }
+    }
+
+    if let Some(ref ts_to) = filters.ts_to {
+        if let Ok(ms) = crate::time::iso_ts_key_ms(ts_to) {
+            sql.push("ts_unix <= ?", [Value::Real(ms as f64)]);
+        }
+    }
+
+    push_text_filters(&mut sql, filters, options)?;
+
+    // Bookmark filter (table created when the report is opened)
+    if let Some(bookmarked) = filters.bookmarked {
+        let keyword = if bookmarked { "IN" } else { "NOT IN" };
+        sql.push(format!("id {} (SELECT row_id FROM bookmarks)", keyword), []);
+    }
+
+    // Extracted field predicates (table created when the report is opened)
+    for predicate in filters.fields.iter().flatten() {
+        let (key, value) = match predicate.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim().to_string())),
+            None => (predicate.trim(), None),
+        };
+        if key.is_empty() {
+            return Err(LogcatError::InvalidFilter(format!("Invalid field predicate: {}", predicate)));
+        }
+        let mut params = Vec::new();
+        let condition = field_condition(key, value.as_slice(), &mut params);
+        sql.push(condition, params);
+    }
+
+    Ok(sql)
+}
+
+fn push_text_filters(sql: &mut FilterSql, filters: &LogFilters, options: SqlOptions) -> Result<()> {
+    let regex_mode = filters.text_mode.as_deref() == Some("regex");
+    let case_sensitive = filters.case_sensitive.unwrap_or(false);
+
+    // Include text filter
+    if let Some(ref text) = filters.text {
+        if regex_mode {
+            // Validate up front so errors surface as filter errors
+            compile_user_regex(text, !case_sensitive)?;
+            sql.push("msg REGEXP ?", [Value::Text(sql_regex_pattern(text, case_sensitive))]);
+        } else {
+            if options.trigram_fts && text.chars().count() >= TRIGRAM_MIN_CHARS {
+                // Narrow candidates through the trigram index first
+                sql.push(
+                    "id IN (SELECT rowid FROM logs_fts WHERE logs_fts MATCH ?)",
+                    [Value::Text(fts_phrase(text))],
+                );
+            }
+            sql.push(
+                "lm_contains(msg, ?, ?)",
+                [Value::Text(text.clone()), Value::Integer(case_sensitive as i64)],
+            );
+        }
+    }
+
+    // Exclude text filter (invalid regex is ignored, as before)
+    if let Some(ref not_text) = filters.not_text {
+        if regex_mode {
+            if compile_user_regex(not_text, !case_sensitive).is_ok() {
+                sql.push(
+                    "NOT (msg REGEXP ?)",
+                    [Value::Text(sql_regex_pattern(not_text, case_sensitive))],
+                );
+            }
+        } else {
+            sql.push(
+                "NOT lm_contains(msg, ?, ?)",
+                [Value::Text(not_text.clone()), Value::Integer(case_sensitive as i64)],
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// How `tag` and `exclude_tags` values match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagMode {
+    Substring,
+    Exact,
+    Regex,
+}
+
+impl TagMode {
+    fn parse(mode: Option<&str>) -> Result<Self> {
+        match mode.unwrap_or("substring") {
+            "substring" => Ok(TagMode::Substring),
+            "exact" => Ok(TagMode::Exact),
+            "regex" => Ok(TagMode::Regex),
+            other => Err(LogcatError::InvalidFilter(format!("Unknown tag mode: {}", other))),
+        }
+    }
+}
+
+/// Tag condition matching any of `tags` (or none of them when `exclude`)
+fn push_tag_filter(sql: &mut FilterSql, tags: &[&str], mode: TagMode, exclude: bool) -> Result<()> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    // Exact matches can use idx_tag
+    if mode == TagMode::Exact {
+        push_in_list(sql, "tag", exclude, tags.iter().map(|t| Value::Text(t.to_string())));
+        return Ok(());
+    }
+
+    let mut params = Vec::with_capacity(tags.len());
+    for tag in tags {
+        match mode {
+            TagMode::Regex => {
+                compile_user_regex(tag, false)?;
+                params.push(Value::Text(tag.to_string()));
+            }
+            _ => params.push(Value::Text(format!("%{}%", escape_like(tag)))),
+        }
+    }
+
+    let single = match mode {
+        TagMode::Regex => "tag REGEXP ?",
+        _ => "tag LIKE ? ESCAPE '\\'",
+    };
+    let any = vec![single; tags.len()].join(" OR ");
+    let condition = if exclude {
+        format!("NOT ({})", any)
+    } else {
+        format!("({})", any)
+    };
+    sql.push(condition, params);
+    Ok(())
+}
+
+/// Rows carrying the extracted field `key`, with one of `values` if any
+pub fn field_condition(key: &str, values: &[String], params: &mut Vec<Value>) -> String {
+    params.push(Value::Text(key.to_ascii_lowercase()));
+    if values.is_empty() {
+        return "id IN (SELECT row_id FROM log_fields WHERE key = ?)".to_string();
+    }
+    params.extend(values.iter().map(|v| Value::Text(v.clone())));
+    format!(
+        "id IN (SELECT row_id FROM log_fields WHERE key = ? AND value IN ({}))",
+        vec!["?"; values.len()].join(",")
+    )
+}
+
+/// Include / exclude lists on an integer column
+fn push_id_lists(sql: &mut FilterSql, column: &str, include: Option<&[i32]>, exclude: Option<&[i32]>) {
+    if let Some(ids) = include.filter(|ids| !ids.is_empty()) {
+        push_in_list(sql, column, false, ids.iter().map(|id| Value::Integer(*id as i64)));
+    }
+    if let Some(ids) = exclude.filter(|ids| !ids.is_empty()) {
+        push_in_list(sql, column, true, ids.iter().map(|id| Value::Integer(*id as i64)));
+    }
+}
+
+/// `column IN (...)` / `column NOT IN (...)`
+fn push_in_list<I: IntoIterator<Item = Value>>(sql: &mut FilterSql, column: &str, exclude: bool, values: I) {
+    let values: Vec<Value> = values.into_iter().collect();
+    let placeholders = vec!["?"; values.len()].join(",");
+    let keyword = if exclude { "NOT IN" } else { "IN" };
+    sql.push(format!("{} {} ({})", column, keyword, placeholders), values);
+}
+
+/// Log levels in increasing severity
+pub const LEVEL_ORDER: [char; 6] = ['V', 'D', 'I', 'W', 'E', 'F'];
+
+/// Levels at or above `min` (empty for an unknown level)
+pub fn levels_at_least(min: char) -> Vec<char> {
+    match LEVEL_ORDER.iter().position(|&l| l == min) {
+        Some(rank) => LEVEL_ORDER[rank..].to_vec(),
+        None => Vec::new(),
+    }
+}
+
+/// Escape LIKE wildcards (with `\` as the escape character)
+pub fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Pattern passed to the SQL `REGEXP` function
+///
+/// `REGEXP` only takes (pattern, text), so case-insensitivity is inlined.
+pub fn sql_regex_pattern(pattern: &str, case_sensitive: bool) -> String {
+    if case_sensitive {
+        pattern.to_string()
+    } else {
+        format!("(?i){}", pattern)
+    }
+}
+
+/// Quote text as an FTS5 phrase
+pub fn fts_phrase(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_filters() {
+        let sql = build_filter_sql(&LogFilters::default(), SqlOptions::default()).unwrap();
+        assert!(sql.conditions.is_empty());
+        assert_eq!(sql.where_clause(), "");
+    }
+
+    #[test]
+    fn test_plain_text_uses_fts_when_available() {
+        let filters = LogFilters {
+            text: Some("timeout".to_string()),
+            ..Default::default()
+        };
+
+        let with_fts = build_filter_sql(&filters, SqlOptions { trigram_fts: true }).unwrap();
+        assert!(with_fts.conditions[0].contains("logs_fts MATCH"));
+        assert_eq!(with_fts.params[0], Value::Text("\"timeout\"".to_string()));
+
+        let without_fts = build_filter_sql(&filters, SqlOptions::default()).unwrap();
+        assert_eq!(without_fts.conditions, vec!["lm_contains(msg, ?, ?)".to_string()]);
+    }
+
+    #[test]
+    fn test_short_text_skips_fts() {
+        let filters = LogFilters {
+            text: Some("ab".to_string()),
+            ..Default::default()
+        };
+        let sql = build_filter_sql(&filters, SqlOptions { trigram_fts: true }).unwrap();
+        assert_eq!(sql.conditions.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_regex_is_error() {
+        let filters = LogFilters {
+            text: Some("(a+)+".to_string()),
+            text_mode: Some("regex".to_string()),
+            ..Default::default()
+        };
+        assert!(build_filter_sql(&filters, SqlOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_tag_modes_and_exclusions() {
+        let filters = LogFilters {
+            tag: Some("ActivityManager|WindowManager".to_string()),
+            exclude_tags: Some(vec!["chatty".to_string(), "".to_string()]),
+            tag_mode: Some("exact".to_string()),
+            ..Default::default()
+        };
+        let sql = build_filter_sql(&filters, SqlOptions::default()).unwrap();
+        assert_eq!(sql.conditions, vec!["tag IN (?,?)", "tag NOT IN (?)"]);
+
+        let filters = LogFilters {
+            exclude_tags: Some(vec!["chat%".to_string()]),
+            ..Default::default()
+        };
+        let sql = build_filter_sql(&filters, SqlOptions::default()).unwrap();
+        assert_eq!(sql.conditions, vec!["NOT (tag LIKE ? ESCAPE '\\')"]);
+        assert_eq!(sql.params, vec![Value::Text("%chat\\%%".to_string())]);
+
+        let filters = LogFilters {
+            tag: Some("^Activity".to_string()),
+            tag_mode: Some("regex".to_string()),
+            ..Default::default()
+        };
+        let sql = build_filter_sql(&filters, SqlOptions::default()).unwrap();
+        assert_eq!(sql.conditions, vec!["(tag REGEXP ?)"]);
+
+        let filters = LogFilters {
+            tag_mode: Some("fuzzy".to_string()),
+            ..Default::default()
+        };
+        assert!(build_filter_sql(&filters, SqlOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_field_predicates() {
+        let filters = LogFilters {
+            fields: Some(vec!["Reason=timeout".to_string(), "uid".to_string()]),
+            ..Default::default()
+        };
+        let sql = build_filter_sql(&filters, SqlOptions::default()).unwrap();
+        assert_eq!(
+            sql.conditions,
+            vec![
+                "id IN (SELECT row_id FROM log_fields WHERE key = ? AND value IN (?))",
+                "id IN (SELECT row_id FROM log_fields WHERE key = ?)",
+            ]
+        );
+        assert_eq!(sql.params[0], Value::Text("reason".to_string()));
+
+        let filters = LogFilters {
+            fields: Some(vec!["=timeout".to_string()]),
+            ..Default::default()
+        };
+        assert!(build_filter_sql(&filters, SqlOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_id_lists_and_min_level() {
+        let filters = LogFilters {
+            pids: Some(vec![1, 2, 3]),
+            instances: Some(vec![7]),
+            exclude_tids: Some(vec![4]),
+            min_level: Some("w".to_string()),
+            ..Default::default()
+        };
+        let sql = build_filter_sql(&filters, SqlOptions::default()).unwrap();
+        assert_eq!(
+            sql.conditions,
+            vec!["level IN (?,?,?)", "pid IN (?,?,?)", "instance_id IN (?)", "tid NOT IN (?)"]
+        );
+        assert_eq!(sql.params[0], Value::Text("W".to_string()));
+
+        let filters = LogFilters {
+            min_level: Some("X".to_string()),
+            ..Default::default()
+        };
+        assert!(build_filter_sql(&filters, SqlOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_fts_phrase_escapes_quotes() {
+        assert_eq!(fts_phrase(r#"say "hi""#), r#""say ""hi""""#);
+    }
+}
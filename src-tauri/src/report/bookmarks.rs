@@ -0,0 +1,184 @@
+use crate::cache::now_ms;
+use crate::error::{LogcatError, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// Bookmarks live next to `logs` in the report database, keyed by row id
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS bookmarks (
+        row_id INTEGER PRIMARY KEY,
+        label TEXT NOT NULL DEFAULT '',
+        color TEXT,
+        note TEXT NOT NULL DEFAULT '',
+        created_ms INTEGER NOT NULL,
+        updated_ms INTEGER NOT NULL
+    );";
+
+const BOOKMARK_COLUMNS: &str = "b.row_id, b.label, b.color, b.note, b.created_ms, b.updated_ms";
+
+/// A bookmarked (and optionally annotated) log row
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub row_id: i64,
+    pub label: String,
+    /// CSS colour, `None` for the default
+    pub color: Option<String>,
+    pub note: String,
+    pub created_ms: i64,
+    pub updated_ms: i64,
+}
+
+/// Editable bookmark fields
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkInput {
+    #[serde(default)]
+    pub label: String,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Create the bookmarks table if the database predates it
+pub fn ensure_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+/// Create or replace the bookmark on `row_id` (keeps the creation time)
+pub fn set_bookmark(conn: &Connection, row_id: i64, input: &BookmarkInput) -> Result<Bookmark> {
+    let exists = conn
+        .query_row("SELECT 1 FROM logs WHERE id = ?", [row_id], |_| Ok(()))
+        .optional()
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+    if exists.is_none() {
+        return Err(LogcatError::RowNotFound(row_id));
+    }
+
+    let now = now_ms();
+    conn.execute(
+        "INSERT INTO bookmarks (row_id, label, color, note, created_ms, updated_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+         ON CONFLICT(row_id) DO UPDATE SET
+             label = excluded.label,
+             color = excluded.color,
+             note = excluded.note,
+             updated_ms = excluded.updated_ms",
+        params![row_id, input.label, input.color, input.note, now],
+    )
+    .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    get_bookmark(conn, row_id)?.ok_or(LogcatError::RowNotFound(row_id))
+}
+
+/// Bookmark on `row_id`, if any
+pub fn get_bookmark(conn: &Connection, row_id: i64) -> Result<Option<Bookmark>> {
+    conn.query_row(
+        &format!("SELECT {} FROM bookmarks b WHERE b.row_id = ?", BOOKMARK_COLUMNS),
+        [row_id],
+        read_bookmark,
+    )
+    .optional()
+    .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+/// Remove the bookmark on `row_id`, returning whether one existed
+pub fn delete_bookmark(conn: &Connection, row_id: i64) -> Result<bool> {
+    conn.execute("DELETE FROM bookmarks WHERE row_id = ?", [row_id])
+        .map(|n| n > 0)
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+/// All bookmarks in log order
+pub fn list_bookmarks(conn: &Connection) -> Result<Vec<Bookmark>> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM bookmarks b JOIN logs l ON l.id = b.row_id ORDER BY l.ts_unix, l.id",
+            BOOKMARK_COLUMNS,
+        ))
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    let rows = stmt
+        .query_map([], read_bookmark)
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+fn read_bookmark(row: &Row<'_>) -> rusqlite::Result<Bookmark> {
+    Ok(Bookmark {
+        row_id: row.get(0)?,
+        label: row.get(1)?,
+        color: row.get(2)?,
+        note: row.get(3)?,
+        created_ms: row.get(4)?,
+        updated_ms: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexBuilder;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_db() -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("test_bookmarks_{}.db", nanos));
+        IndexBuilder::new(&path)
+            .unwrap()
+            .build_from_text(
+                "08-24 14:22:35.000  1234  5678 E Tag: third\n\
+                 08-24 14:22:33.000  1234  5678 I Tag: first\n\
+                 08-24 14:22:34.000  1234  5678 W Tag: second\n",
+            )
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_bookmark_crud_survives_reopen() {
+        let path = temp_db();
+        {
+            let conn = Connection::open(&path).unwrap();
+            ensure_schema(&conn).unwrap();
+
+            let input = BookmarkInput {
+                label: "crash".to_string(),
+                color: Some("#f00".to_string()),
+                note: "starts here".to_string(),
+            };
+            let created = set_bookmark(&conn, 1, &input).unwrap();
+            set_bookmark(&conn, 2, &BookmarkInput::default()).unwrap();
+
+            // Updating keeps the creation time
+            let updated = set_bookmark(&conn, 1, &BookmarkInput { note: "edited".to_string(), ..input }).unwrap();
+            assert_eq!(updated.created_ms, created.created_ms);
+            assert_eq!(updated.note, "edited");
+
+            assert!(matches!(
+                set_bookmark(&conn, 99, &BookmarkInput::default()),
+                Err(LogcatError::RowNotFound(99))
+            ));
+        }
+
+        let conn = Connection::open(&path).unwrap();
+        ensure_schema(&conn).unwrap();
+
+        // Listed in log order, not id order
+        let ids: Vec<i64> = list_bookmarks(&conn).unwrap().iter().map(|b| b.row_id).collect();
+        assert_eq!(ids, vec![2, 1]);
+        assert_eq!(get_bookmark(&conn, 1).unwrap().unwrap().label, "crash");
+
+        assert!(delete_bookmark(&conn, 1).unwrap());
+        assert!(!delete_bookmark(&conn, 1).unwrap());
+        assert_eq!(list_bookmarks(&conn).unwrap().len(), 1);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
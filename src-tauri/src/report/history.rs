@@ -0,0 +1,124 @@
+use crate::cache::now_ms;
+use crate::error::{LogcatError, Result};
+use crate::types::LogFilters;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Most recent distinct queries kept per report
+const MAX_HISTORY: usize = 100;
+
+/// Filters are stored as JSON so identical queries collapse into one entry
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS query_history (
+        filters_json TEXT PRIMARY KEY,
+        used_ms INTEGER NOT NULL,
+        use_count INTEGER NOT NULL DEFAULT 1
+    );";
+
+/// A previously run query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub filters: LogFilters,
+    /// Last use (Unix ms)
+    pub used_ms: i64,
+    pub use_count: usize,
+}
+
+/// Create the history table if the database predates it
+pub fn ensure_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+/// Record a query run with `filters` (unfiltered views are not recorded)
+pub fn record_query(conn: &Connection, filters: &LogFilters) -> Result<()> {
+    let json = serde_json::to_string(filters)?;
+    if json == serde_json::to_string(&LogFilters::default())? {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO query_history (filters_json, used_ms) VALUES (?1, ?2)
+         ON CONFLICT(filters_json) DO UPDATE SET
+             used_ms = excluded.used_ms,
+             use_count = use_count + 1",
+        params![json, now_ms()],
+    )
+    .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    conn.execute(
+        "DELETE FROM query_history WHERE filters_json NOT IN
+         (SELECT filters_json FROM query_history ORDER BY used_ms DESC LIMIT ?)",
+        [MAX_HISTORY as i64],
+    )
+    .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Most recently used queries first
+pub fn list_history(conn: &Connection, limit: usize) -> Result<Vec<HistoryEntry>> {
+    let mut stmt = conn
+        .prepare("SELECT filters_json, used_ms, use_count FROM query_history ORDER BY used_ms DESC LIMIT ?")
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    let rows = stmt
+        .query_map([limit as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, usize>(2)?))
+        })
+        .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (json, used_ms, use_count) = row.map_err(|e| LogcatError::Database(e.to_string()))?;
+        entries.push(HistoryEntry {
+            filters: serde_json::from_str(&json)?,
+            used_ms,
+            use_count,
+        });
+    }
+    Ok(entries)
+}
+
+/// Remove all history entries
+pub fn clear_history(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM query_history", [])
+        .map(|_| ())
+        .map_err(|e| LogcatError::Database(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_dedupes_and_orders() {
+        let conn = Connection::open_in_memory().unwrap();
+        ensure_schema(&conn).unwrap();
+
+        let wifi = LogFilters {
+            tag: Some("WifiService".to_string()),
+            ..Default::default()
+        };
+        let binder = LogFilters {
+            query: Some("tag:Binder level>=E".to_string()),
+            ..Default::default()
+        };
+
+        record_query(&conn, &LogFilters::default()).unwrap();
+        record_query(&conn, &wifi).unwrap();
+        record_query(&conn, &binder).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        record_query(&conn, &wifi).unwrap();
+
+        let history = list_history(&conn, 10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].filters.tag.as_deref(), Some("WifiService"));
+        assert_eq!(history[0].use_count, 2);
+        assert_eq!(history[1].filters.query.as_deref(), Some("tag:Binder level>=E"));
+
+        clear_history(&conn).unwrap();
+        assert!(list_history(&conn, 10).unwrap().is_empty());
+    }
+}
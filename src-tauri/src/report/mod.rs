@@ -0,0 +1,5 @@
+mod registry;
+pub mod bookmarks;
+pub mod history;
+
+pub use registry::{ReportRegistry, OpenReport, ReportInfo};
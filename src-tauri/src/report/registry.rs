@@ -0,0 +1,419 @@
+use crate::cache::{now_ms, CacheMeta, DB_FILE};
+use crate::error::{LogcatError, Result};
+use crate::index::reanchor::{self, ReanchorSummary, TimeOverride};
+use crate::index::{fields, processes, templates};
+use crate::query::QueryExecutor;
+use crate::report::bookmarks::{self, Bookmark, BookmarkInput};
+use crate::report::history::{self, HistoryEntry};
+use crate::types::LogFilters;
+use rusqlite::InterruptHandle;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A report opened for querying
+///
+/// Holds one shared SQLite connection for the lifetime of the report.
+pub struct OpenReport {
+    pub id: String,
+    pub cache_id: String,
+    pub cache_dir: PathBuf,
+    pub opened_ms: i64,
+    executor: Mutex<QueryExecutor>,
+    /// Interrupt handle of the background exact-stats query, if any
+    stats_interrupt: Mutex<Option<InterruptHandle>>,
+}
+
+impl OpenReport {
+    /// Lock the report's query executor
+    pub fn executor(&self) -> Result<MutexGuard<'_, QueryExecutor>> {
+        self.executor.lock().map_err(|_| LogcatError::StatePoisoned)
+    }
+
+    /// Create or replace the bookmark on a row
+    pub fn set_bookmark(&self, row_id: i64, input: &BookmarkInput) -> Result<Bookmark> {
+        let executor = self.executor()?;
+        let bookmark = bookmarks::set_bookmark(executor.connection(), row_id, input)?;
+        executor.invalidate_counts();
+        Ok(bookmark)
+    }
+
+    /// Remove the bookmark on a row
+    pub fn delete_bookmark(&self, row_id: i64) -> Result<bool> {
+        let executor = self.executor()?;
+        let deleted = bookmarks::delete_bookmark(executor.connection(), row_id)?;
+        executor.invalidate_counts();
+        Ok(deleted)
+    }
+
+    /// All bookmarks in log order
+    pub fn list_bookmarks(&self) -> Result<Vec<Bookmark>> {
+        bookmarks::list_bookmarks(self.executor()?.connection())
+    }
+
+    /// Add `filters` to the report's query history
+    pub fn record_query(&self, filters: &LogFilters) -> Result<()> {
+        history::record_query(self.executor()?.connection(), filters)
+    }
+
+    /// Recent queries on this report, most recent first
+    pub fn query_history(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        history::list_history(self.executor()?.connection(), limit)
+    }
+
+    /// Forget this report's query history
+    pub fn clear_query_history(&self) -> Result<()> {
+        history::clear_history(self.executor()?.connection())
+    }
+
+    /// Re-extract `key=value` fields with a new tag allow-list
+    pub fn reindex_fields(&self, tags: &[String]) -> Result<usize> {
+        let executor = self.executor()?;
+        let extracted = fields::extract_fields(executor.connection(), tags)?;
+        executor.invalidate_counts();
+        Ok(extracted)
+    }
+
+    /// Process instances in start order, optionally only those of `pid`
+    pub fn process_instances(&self, pid: Option<i32>) -> Result<Vec<processes::ProcessInstance>> {
+        processes::load_instances(self.executor()?.connection(), pid)
+    }
+
+    /// Recompute all timestamps with a user-chosen timezone / report date
+    ///
+    /// Rewrites every row, so like `backfill` it runs on its own connection
+    /// and queries are not blocked meanwhile. Also refreshes the time range
+    /// and anchor recorded in the cache metadata.
+    pub fn apply_time_override(&self, time: &TimeOverride) -> Result<ReanchorSummary> {
+        let conn = rusqlite::Connection::open(self.db_path())
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+        let summary = reanchor::apply_time_override(&conn, time)?;
+        self.executor()?.invalidate_timestamps();
+
+        if let Ok(mut meta) = CacheMeta::load(&self.cache_dir) {
+            meta.min_timestamp_ms = summary.min_timestamp_ms;
+            meta.max_timestamp_ms = summary.max_timestamp_ms;
+            meta.time_anchor = Some(summary.anchor.clone());
+            meta.save(&self.cache_dir)?;
+        }
+        Ok(summary)
+    }
+
+    /// Time override applied to this report, if any
+    pub fn time_override(&self) -> Result<Option<TimeOverride>> {
+        reanchor::load_time_override(self.executor()?.connection())
+    }
+
+    /// Whether the cache predates side tables built at index time
+    pub fn needs_backfill(&self) -> Result<bool> {
+        let executor = self.executor()?;
+        let conn = executor.connection();
+        Ok(!templates::has_templates(conn)? || !processes::has_instances(conn)?)
+    }
+
+    /// Build the side tables missing from a cache made by an older version
+    ///
+    /// Slow on large reports, so it runs on its own connection: queries keep
+    /// using the executor meanwhile and only see the result once it is
+    /// committed. Returns whether anything was built.
+    pub fn backfill(&self) -> Result<bool> {
+        let conn = rusqlite::Connection::open(self.db_path())
+            .map_err(|e| LogcatError::Database(e.to_string()))?;
+
+        let mut built = false;
+        if !templates::has_templates(&conn)? {
+            templates::mine_templates(&conn)?;
+            built = true;
+        }
+        if !processes::has_instances(&conn)? {
+            processes::track_processes(&conn)?;
+            built = true;
+        }
+
+        if built {
+            self.executor()?.invalidate_counts();
+        }
+        Ok(built)
+    }
+
+    /// Path of the report's SQLite database
+    pub fn db_path(&self) -> PathBuf {
+        self.cache_dir.join(DB_FILE)
+    }
+
+    /// Open a second executor for background exact stats
+    ///
+    /// Interrupts the previous background stats query, which is stale once
+    /// the filters change.
+    pub fn open_stats_executor(&self) -> Result<QueryExecutor> {
+        let executor = QueryExecutor::open(&self.db_path())?;
+        let previous = self
+            .stats_interrupt
+            .lock()
+            .map_err(|_| LogcatError::StatePoisoned)?
+            .replace(executor.interrupt_handle());
+        if let Some(handle) = previous {
+            handle.interrupt();
+        }
+        Ok(executor)
+    }
+}
+
+/// Summary of an open report for the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportInfo {
+    pub report_id: String,
+    pub cache_id: String,
+    pub opened_ms: i64,
+    pub active: bool,
+}
+
+/// Registry of simultaneously open reports
+#[derive(Default)]
+pub struct ReportRegistry {
+    reports: HashMap<String, Arc<OpenReport>>,
+    active: Option<String>,
+    next_id: u64,
+}
+
+impl ReportRegistry {
+    /// Open the report stored in a cache directory and make it active
+    ///
+    /// Returns the existing id if the directory is already open.
+    pub fn open(&mut self, cache_dir: &Path) -> Result<String> {
+        if let Some(existing) = self.find_by_dir(cache_dir) {
+            self.active = Some(existing.clone());
+            return Ok(existing);
+        }
+
+        let executor = QueryExecutor::open(&cache_dir.join(DB_FILE))?;
+        bookmarks::ensure_schema(executor.connection())?;
+        history::ensure_schema(executor.connection())?;
+        // Caches built before field extraction get an empty table
+        fields::ensure_schema(executor.connection())?;
+        // Instances of older caches are backfilled in the background
+        processes::ensure_instance_column(executor.connection())?;
+
+        self.next_id += 1;
+        let id = format!("r{}", self.next_id);
+        let cache_id = cache_dir
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let report = OpenReport {
+            id: id.clone(),
+            cache_id,
+            cache_dir: cache_dir.to_path_buf(),
+            opened_ms: now_ms(),
+            executor: Mutex::new(executor),
+            stats_interrupt: Mutex::new(None),
+        };
+
+        self.reports.insert(id.clone(), Arc::new(report));
+        self.active = Some(id.clone());
+        Ok(id)
+    }
+
+    /// Close a report, releasing its connection
+    pub fn close(&mut self, id: &str) -> bool {
+        let removed = self.reports.remove(id).is_some();
+        if self.active.as_deref() == Some(id) {
+            // Fall back to the most recently opened report
+            self.active = self
+                .reports
+                .values()
+                .max_by_key(|r| r.opened_ms)
+                .map(|r| r.id.clone());
+        }
+        removed
+    }
+
+    /// Close whichever report is backed by a cache directory
+    ///
+    /// Used before a cache is rebuilt or deleted.
+    pub fn close_dir(&mut self, cache_dir: &Path) {
+        if let Some(id) = self.find_by_dir(cache_dir) {
+            self.close(&id);
+        }
+    }
+
+    /// Close all reports
+    pub fn close_all(&mut self) {
+        self.reports.clear();
+        self.active = None;
+    }
+
+    /// Get a report by id, or the active report when no id is given
+    pub fn get(&self, id: Option<&str>) -> Result<Arc<OpenReport>> {
+        let id = match id.or(self.active.as_deref()) {
+            Some(id) => id,
+            None => {
+                return Err(LogcatError::ReportNotFound(
+                    "no report open; please parse a bugreport first".to_string(),
+                ))
+            }
+        };
+
+        self.reports
+            .get(id)
+            .cloned()
+            .ok_or_else(|| LogcatError::ReportNotFound(id.to_string()))
+    }
+
+    /// Cache ids of all open reports
+    pub fn cache_ids(&self) -> Vec<String> {
+        self.reports.values().map(|r| r.cache_id.clone()).collect()
+    }
+
+    /// List open reports, oldest first
+    pub fn list(&self) -> Vec<ReportInfo> {
+        let mut infos: Vec<ReportInfo> = self
+            .reports
+            .values()
+            .map(|r| ReportInfo {
+                report_id: r.id.clone(),
+                cache_id: r.cache_id.clone(),
+                opened_ms: r.opened_ms,
+                active: self.active.as_deref() == Some(r.id.as_str()),
+            })
+            .collect();
+        infos.sort_by_key(|r| r.opened_ms);
+        infos
+    }
+
+    fn find_by_dir(&self, cache_dir: &Path) -> Option<String> {
+        self.reports
+            .values()
+            .find(|r| r.cache_dir == cache_dir)
+            .map(|r| r.id.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexBuilder;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_report_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("test_registry_{}_{}", name, nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+        IndexBuilder::new(&dir.join(DB_FILE))
+            .unwrap()
+            .build_from_text("08-24 14:22:33.123  1234  5678 E Tag: hello\n")
+            .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_open_multiple_reports() {
+        let dir_a = temp_report_dir("a");
+        let dir_b = temp_report_dir("b");
+        let mut registry = ReportRegistry::default();
+
+        let a = registry.open(&dir_a).unwrap();
+        let b = registry.open(&dir_b).unwrap();
+        assert_ne!(a, b);
+
+        // Active report is the last opened one
+        assert_eq!(registry.get(None).unwrap().id, b);
+        assert_eq!(registry.get(Some(&a)).unwrap().cache_dir, dir_a);
+
+        // Reopening the same directory reuses the id
+        assert_eq!(registry.open(&dir_a).unwrap(), a);
+        assert_eq!(registry.list().len(), 2);
+
+        std::fs::remove_dir_all(&dir_a).ok();
+        std::fs::remove_dir_all(&dir_b).ok();
+    }
+
+    #[test]
+    fn test_close_falls_back_to_other_report() {
+        let dir_a = temp_report_dir("a");
+        let dir_b = temp_report_dir("b");
+        let mut registry = ReportRegistry::default();
+
+        let a = registry.open(&dir_a).unwrap();
+        let b = registry.open(&dir_b).unwrap();
+
+        assert!(registry.close(&b));
+        assert_eq!(registry.get(None).unwrap().id, a);
+        assert!(registry.get(Some(&b)).is_err());
+
+        registry.close_dir(&dir_a);
+        assert!(registry.get(None).is_err());
+
+        std::fs::remove_dir_all(&dir_a).ok();
+        std::fs::remove_dir_all(&dir_b).ok();
+    }
+
+    #[test]
+    fn test_bookmarks_survive_reopen_and_filter() {
+        let dir = temp_report_dir("bookmarks");
+        let mut registry = ReportRegistry::default();
+
+        let id = registry.open(&dir).unwrap();
+        let report = registry.get(Some(&id)).unwrap();
+        let filters = LogFilters {
+            bookmarked: Some(true),
+            ..Default::default()
+        };
+        let count = |report: &OpenReport| {
+            report.executor().unwrap().get_stats(&filters).unwrap().filtered_rows
+        };
+        assert_eq!(count(&report), Some(0));
+
+        let input = BookmarkInput {
+            label: "first".to_string(),
+            ..Default::default()
+        };
+        report.set_bookmark(1, &input).unwrap();
+        assert_eq!(count(&report), Some(1));
+
+        drop(report);
+        registry.close(&id);
+        let id = registry.open(&dir).unwrap();
+        let report = registry.get(Some(&id)).unwrap();
+        assert_eq!(report.list_bookmarks().unwrap()[0].label, "first");
+        assert_eq!(count(&report), Some(1));
+
+        assert!(report.delete_bookmark(1).unwrap());
+        assert_eq!(count(&report), Some(0));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_open_defers_backfill_of_old_cache() {
+        let dir = temp_report_dir("backfill");
+        {
+            // Simulate a cache built before process tracking
+            let conn = rusqlite::Connection::open(dir.join(DB_FILE)).unwrap();
+            conn.execute_batch("DROP TABLE process_instances; UPDATE logs SET instance_id = NULL;")
+                .unwrap();
+        }
+        let mut registry = ReportRegistry::default();
+
+        let id = registry.open(&dir).unwrap();
+        let report = registry.get(Some(&id)).unwrap();
+        assert!(report.needs_backfill().unwrap());
+        assert!(matches!(report.process_instances(None), Err(LogcatError::IndexPending(_))));
+
+        assert!(report.backfill().unwrap());
+        assert!(!report.needs_backfill().unwrap());
+        assert_eq!(report.process_instances(Some(1234)).unwrap().len(), 1);
+        assert!(!report.backfill().unwrap());
+
+        drop(report);
+        registry.close(&id);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,7 +1,46 @@
-use chrono::{Local, Datelike, NaiveDate};
+use chrono::{DateTime, Local, Datelike, NaiveDate};
 use chrono_tz::Tz;
 use regex::Regex;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Report dates kept in `TimeAnchor::candidates`
+const MAX_CANDIDATES: usize = 10;
+
+/// Where a time anchor value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorSource {
+    /// `== dumpstate: 2024-08-24 14:22:33` header
+    DumpstateHeader,
+    /// Date part of the build id in a build fingerprint
+    Fingerprint,
+    /// System property (`persist.sys.timezone`, `ro.build.date.utc`)
+    Property,
+    /// Set by the user after indexing
+    UserOverride,
+    /// Nothing found: UTC, or the current date
+    Fallback,
+}
+
+/// How far the derived timestamps can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorConfidence {
+    Low,
+    Medium,
+    High,
+}
+
+/// A report date seen while deriving the anchor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorCandidate {
+    pub source: AnchorSource,
+    pub date: NaiveDate,
+    /// Matched text
+    pub text: String,
+}
 
 /// Time anchor derived from bugreport content
 #[derive(Debug, Clone)]
@@ -9,18 +48,70 @@ pub struct TimeAnchor {
     pub tz: Tz,
     pub year: i32,
     pub report_date: Option<NaiveDate>,
+    /// Source of `report_date`, which decides every row's year
+    pub date_source: AnchorSource,
+    pub tz_source: AnchorSource,
+    pub confidence: AnchorConfidence,
+    /// Report dates seen, best source first
+    pub candidates: Vec<AnchorCandidate>,
+}
+
+/// Serializable description of a `TimeAnchor` for the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorInfo {
+    pub timezone: String,
+    pub report_date: Option<NaiveDate>,
+    pub date_source: AnchorSource,
+    pub tz_source: AnchorSource,
+    pub confidence: AnchorConfidence,
+    pub candidates: Vec<AnchorCandidate>,
 }
 
+impl TimeAnchor {
+    /// Anchor chosen by the user; fully trusted
+    pub fn user_override(tz: Tz, report_date: NaiveDate) -> Self {
+        Self {
+            tz,
+            year: report_date.year(),
+            report_date: Some(report_date),
+            date_source: AnchorSource::UserOverride,
+            tz_source: AnchorSource::UserOverride,
+            confidence: AnchorConfidence::High,
+            candidates: Vec::new(),
+        }
+    }
+
+    pub fn info(&self) -> AnchorInfo {
+        AnchorInfo {
+            timezone: self.tz.to_string(),
+            report_date: self.report_date,
+            date_source: self.date_source,
+            tz_source: self.tz_source,
+            confidence: self.confidence,
+            candidates: self.candidates.clone(),
+        }
+    }
+}
+
+/// `persist.sys.timezone=Zone` or getprop's `[persist.sys.timezone]: [Zone]`
 static TZ_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?m)^\s*persist\.sys\.timezone\s*=\s*(?P<tz>\S+)\s*$").unwrap()
+    Regex::new(r"(?m)^\s*(?:persist\.sys\.timezone\s*=\s*|\[persist\.sys\.timezone\]:\s*\[)(?P<tz>[^\s\]]+)\]?\s*$").unwrap()
 });
 
 static DUMPSTATE_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"dumpstate:\s*(\d{4})-(\d{2})-(\d{2})").unwrap()
 });
 
+/// Build id date in a fingerprint line only, e.g. `.../TQ3A.230605.012/...`
+/// (other `\d{6}.\d{3}` runs in a report are not dates)
 static BUILD_DATE_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\d{2})(\d{2})(\d{2})\.(\d{3})").unwrap()
+    Regex::new(r"(?mi)^[^\n]*fingerprint[^\n]*?/[A-Z0-9]{2,8}\.(\d{2})(\d{2})(\d{2})\.\d{3}[A-Z0-9]*/").unwrap()
+});
+
+/// `ro.build.date.utc=1685923200` or `[ro.build.date.utc]: [1685923200]`
+static BUILD_DATE_PROP_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*\[?ro\.build\.date\.utc\]?\s*[:=]\s*\[?(\d{9,11})\]?\s*$").unwrap()
 });
 
 /// Extract timezone from bugreport content
@@ -29,39 +120,88 @@ fn extract_timezone(text: &str) -> Option<Tz> {
         .and_then(|cap| cap["tz"].parse::<Tz>().ok())
 }
 
-/// Extract report date from dumpstate header or build fingerprint
-fn extract_report_date(text: &str) -> Option<NaiveDate> {
-    // Priority 1: dumpstate timestamp (most reliable)
-    // Format: "== dumpstate: 2024-08-24 14:22:33"
-    if let Some(cap) = DUMPSTATE_RE.captures(text) {
-        let y: i32 = cap[1].parse().ok()?;
-        let m: u32 = cap[2].parse().ok()?;
-        let d: u32 = cap[3].parse().ok()?;
-        return NaiveDate::from_ymd_opt(y, m, d);
+/// Report dates from dumpstate headers, build fingerprints and the build
+/// date property, in that order of preference
+fn report_date_candidates(text: &str) -> Vec<AnchorCandidate> {
+    let mut candidates = Vec::new();
+    let mut push = |source, date: Option<NaiveDate>, text: &str| {
+        if let Some(date) = date {
+            let seen = candidates
+                .iter()
+                .any(|c: &AnchorCandidate| c.source == source && c.date == date);
+            if !seen && candidates.len() < MAX_CANDIDATES {
+                candidates.push(AnchorCandidate { source, date, text: text.trim().to_string() });
+            }
+        }
+    };
+
+    // Format: "== dumpstate: 2024-08-24 14:22:33" (most reliable)
+    for cap in DUMPSTATE_RE.captures_iter(text) {
+        let date = ymd(&cap[1], &cap[2], &cap[3], 0);
+        push(AnchorSource::DumpstateHeader, date, &cap[0]);
     }
 
-    // Priority 2: Build fingerprint date
-    // Format: "TQ3A.230605.012" -> 2023-06-05
-    if let Some(cap) = BUILD_DATE_RE.captures(text) {
-        let y: i32 = 2000 + cap[1].parse::<i32>().ok()?;
-        let m: u32 = cap[2].parse().ok()?;
-        let d: u32 = cap[3].parse().ok()?;
-        return NaiveDate::from_ymd_opt(y, m, d);
+    // Format: "TQ3A.230605.012" -> 2023-06-05 (build date, not report date)
+    for cap in BUILD_DATE_RE.captures_iter(text) {
+        let date = ymd(&cap[1], &cap[2], &cap[3], 2000);
+        push(AnchorSource::Fingerprint, date, &cap[0]);
     }
 
-    None
+    for cap in BUILD_DATE_PROP_RE.captures_iter(text) {
+        let date = cap[1]
+            .parse()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map(|dt| dt.date_naive());
+        push(AnchorSource::Property, date, &cap[0]);
+    }
+
+    candidates
+}
+
+fn ymd(y: &str, m: &str, d: &str, century: i32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(century + y.parse::<i32>().ok()?, m.parse().ok()?, d.parse().ok()?)
+}
+
+/// High needs a dumpstate (or user) date and a known timezone; a build
+/// date or a missing timezone drops a level, a guessed year is low
+fn confidence(date_source: AnchorSource, tz_source: AnchorSource) -> AnchorConfidence {
+    let date = match date_source {
+        AnchorSource::DumpstateHeader | AnchorSource::UserOverride => 2,
+        AnchorSource::Fingerprint | AnchorSource::Property => 1,
+        AnchorSource::Fallback => 0,
+    };
+    let tz = if tz_source == AnchorSource::Fallback { 0 } else { 1 };
+    match date + tz {
+        3 => AnchorConfidence::High,
+        2 => AnchorConfidence::Medium,
+        _ => AnchorConfidence::Low,
+    }
 }
 
 /// Derive time anchor from bugreport content
 /// This includes timezone and reference year for timestamp conversion
 pub fn derive_time_anchor(text: &str) -> TimeAnchor {
-    let tz = extract_timezone(text).unwrap_or(chrono_tz::UTC);
-    let report_date = extract_report_date(text);
+    let (tz, tz_source) = match extract_timezone(text) {
+        Some(tz) => (tz, AnchorSource::Property),
+        None => (chrono_tz::UTC, AnchorSource::Fallback),
+    };
+    let candidates = report_date_candidates(text);
+    let report_date = candidates.first().map(|c| c.date);
+    let date_source = candidates.first().map_or(AnchorSource::Fallback, |c| c.source);
     let year = report_date
         .map(|d| d.year())
         .unwrap_or_else(|| Local::now().year());
 
-    TimeAnchor { tz, year, report_date }
+    TimeAnchor {
+        tz,
+        year,
+        report_date,
+        date_source,
+        tz_source,
+        confidence: confidence(date_source, tz_source),
+        candidates,
+    }
 }
 
 /// Infer the most likely year for a given month/day based on reference date
@@ -97,12 +237,47 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_report_date_dumpstate() {
+    fn test_report_date_from_dumpstate() {
         let text = "== dumpstate: 2024-08-24 14:22:33\nother content";
-        let date = extract_report_date(text).unwrap();
+        let date = report_date_candidates(text).first().unwrap().date;
         assert_eq!(date, NaiveDate::from_ymd_opt(2024, 8, 24).unwrap());
     }
 
+    #[test]
+    fn test_build_date_only_from_fingerprint() {
+        // A build-id-like number outside a fingerprint line is not a date
+        assert!(report_date_candidates("took 240105.004 ms").is_empty());
+
+        let text = "Build fingerprint: 'google/raven/raven:14/UQ1A.240105.004/11206848:user/release-keys'";
+        assert_eq!(report_date_candidates(text).first().map(|c| c.date), NaiveDate::from_ymd_opt(2024, 1, 5));
+
+        let text = "[ro.build.fingerprint]: [google/raven/raven:14/UQ1A.240105.004/11206848:user/release-keys]";
+        assert_eq!(report_date_candidates(text).first().map(|c| c.date), NaiveDate::from_ymd_opt(2024, 1, 5));
+    }
+
+    #[test]
+    fn test_anchor_provenance() {
+        let text = "== dumpstate: 2024-08-24 14:22:33\n\
+                    [persist.sys.timezone]: [Asia/Taipei]\n\
+                    Build fingerprint: 'google/raven/raven:14/UQ1A.240105.004/1:user/release-keys'\n\
+                    [ro.build.date.utc]: [1704412800]\n";
+        let anchor = derive_time_anchor(text);
+        assert_eq!(anchor.tz.to_string(), "Asia/Taipei");
+        assert_eq!(anchor.report_date, NaiveDate::from_ymd_opt(2024, 8, 24));
+        assert_eq!((anchor.date_source, anchor.tz_source), (AnchorSource::DumpstateHeader, AnchorSource::Property));
+        assert_eq!(anchor.confidence, AnchorConfidence::High);
+        let sources: Vec<AnchorSource> = anchor.candidates.iter().map(|c| c.source).collect();
+        assert_eq!(sources, vec![AnchorSource::DumpstateHeader, AnchorSource::Fingerprint, AnchorSource::Property]);
+
+        let fallback = derive_time_anchor("08-24 14:22:33.123  1234  5678 I Tag: 230605.012");
+        assert_eq!((fallback.date_source, fallback.tz_source), (AnchorSource::Fallback, AnchorSource::Fallback));
+        assert_eq!(fallback.confidence, AnchorConfidence::Low);
+        assert!(fallback.candidates.is_empty());
+
+        let text = "Build fingerprint: 'a/b/c:14/UQ1A.240105.004/1:user/release-keys'\npersist.sys.timezone=UTC";
+        assert_eq!(derive_time_anchor(text).confidence, AnchorConfidence::Medium);
+    }
+
     #[test]
     fn test_infer_year_same_year() {
         let reference = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
@@ -205,11 +205,7 @@ mod tests {
     use chrono_tz::{Tz, Asia::Taipei};
 
     fn make_anchor(tz: Tz, year: i32) -> TimeAnchor {
-        TimeAnchor {
-            tz,
-            year,
-            report_date: NaiveDate::from_ymd_opt(year, 6, 15),
-        }
+        TimeAnchor::user_override(tz, NaiveDate::from_ymd_opt(year, 6, 15).unwrap())
     }
 
     #[test]
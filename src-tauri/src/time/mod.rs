@@ -1,5 +1,5 @@
 mod anchor;
 mod conversion;
 
-pub use anchor::{TimeAnchor, derive_time_anchor};
+pub use anchor::{AnchorInfo, TimeAnchor, derive_time_anchor};
 pub use conversion::{to_iso_safe, threadtime_ts_key, iso_ts_key_ms};
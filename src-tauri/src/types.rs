@@ -36,6 +36,9 @@ pub struct TimelineEvent {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LogRow {
+    /// Row id in the index database (None for rows not read from SQLite)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
     pub ts: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ts_iso: Option<String>,
@@ -44,6 +47,47 @@ pub struct LogRow {
     pub pid: i32,
     pub tid: i32,
     pub msg: String,
+    /// Process instance of `pid` at this row (see `index::processes`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<i64>,
+    /// Filter match ranges, only set when highlighting was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<RowMatches>,
+    /// Run of similar rows folded into this one, only set in collapse mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<RowRepeat>,
+}
+
+/// Matched range in UTF-16 code units, so it indexes JS strings directly
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Ranges of a row matched by the `text` and `tag` filters
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RowMatches {
+    pub msg: Vec<MatchSpan>,
+    pub tag: Vec<MatchSpan>,
+}
+
+/// Consecutive rows folded into the row carrying this (collapse mode)
+///
+/// The row itself is the earliest of the run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowRepeat {
+    /// Rows in the run, including the first one
+    pub count: usize,
+    pub first_ms: f64,
+    pub last_ms: f64,
+    pub last_id: i64,
+    pub last_ts: String,
+    /// Forward cursor whose next `count` rows are the run itself
+    pub expand_cursor: crate::query::QueryCursor,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Hash)]
@@ -53,10 +97,21 @@ pub struct LogFilters {
     pub ts_to: Option<String>,
     pub levels: Option<Vec<String>>, // ["E","F"] etc.
     pub tag: Option<String>,
+    pub exclude_tags: Option<Vec<String>>,
+    pub tag_mode: Option<String>,       // "substring" | "exact" | "regex"
     pub pid: Option<i32>,
+    pub pids: Option<Vec<i32>>,
+    pub exclude_pids: Option<Vec<i32>>,
+    pub instances: Option<Vec<i64>>,    // process instance ids (one lifetime of a pid)
     pub tid: Option<i32>,
+    pub tids: Option<Vec<i32>>,
+    pub exclude_tids: Option<Vec<i32>>,
+    pub min_level: Option<String>,      // "W" => W, E, F
     pub text: Option<String>,
     pub not_text: Option<String>,
     pub text_mode: Option<String>,      // "plain" | "regex"
     pub case_sensitive: Option<bool>,
+    pub query: Option<String>,          // query language, ANDed with the fields above
+    pub bookmarked: Option<bool>,       // only bookmarked (true) / unbookmarked (false) rows
+    pub fields: Option<Vec<String>>,    // extracted field predicates: "key=value" or "key"
 }
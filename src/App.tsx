@@ -1,13 +1,25 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 // File picker (Tauri v2 plugin)
 import { open } from "@tauri-apps/plugin-dialog";
 import { listen } from "@tauri-apps/api/event";
-import type { ParseSummary } from "./types";
+import type { AnchorInfo, ParseFinished, ParseSummary } from "./types";
 import "./App.css";
 import AppShell, { type ParseProgress } from "./components/AppShell";
 import { LogcatViewV2 } from "./components/logcat";
 
+/** Why the timestamps may be wrong, or null when the anchor is trusted */
+function anchorWarning(anchor?: AnchorInfo): string | null {
+  if (!anchor || anchor.confidence === "high") return null;
+  const reasons: string[] = [];
+  if (anchor.tzSource === "fallback") reasons.push("timezone unknown, assumed UTC");
+  if (anchor.dateSource === "fallback") reasons.push("report date unknown, year guessed");
+  else if (anchor.dateSource === "fingerprint" || anchor.dateSource === "property") {
+    reasons.push(`year taken from build date ${anchor.reportDate ?? ""}`.trim());
+  }
+  return `Timestamps may be wrong: ${reasons.join("; ")}`;
+}
+
 function App() {
   const [, setPath] = useState("");
   const [view, setView] = useState<"dashboard" | "logcat" | "timeline">("dashboard");
@@ -15,6 +27,7 @@ function App() {
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [parseProgress, setParseProgress] = useState<ParseProgress | null>(null);
+  const jobIdRef = useRef<string | null>(null);
 
   // parsing entry is parsePath(p); header 'Open' triggers browse → parsePath
 
@@ -52,17 +65,57 @@ function App() {
     setSummary(null);
     setParseProgress({ percent: 0, phase: "starting", bytesRead: 0, totalBytes: 0, rowsProcessed: 0, details: p });
     try {
-      const res = await invoke<ParseSummary>("parse_bugreport_streaming", { path: p });
+      const res = await runParseJob(p);
       setSummary(res);
       setView("logcat");
     } catch (err) {
-      setError(err instanceof Error ? err.message : "Unknown error");
+      setError(err instanceof Error ? err.message : String(err ?? "Unknown error"));
     } finally {
+      jobIdRef.current = null;
       setParseProgress(null);
       setLoading(false);
     }
   }
 
+  // Start a background parse job and resolve once it finishes
+  async function runParseJob(p: string): Promise<ParseSummary> {
+    const finished = new Map<string, ParseFinished>();
+    let waiter: ((f: ParseFinished) => void) | null = null;
+    let myJobId: string | null = null;
+
+    // Listen before starting so a fast job cannot finish unobserved
+    const unlisten = await listen<ParseFinished>("parse://finished", (event) => {
+      if (myJobId && event.payload.jobId === myJobId && waiter) {
+        waiter(event.payload);
+      } else {
+        finished.set(event.payload.jobId, event.payload);
+      }
+    });
+
+    try {
+      myJobId = await invoke<string>("parse_bugreport_streaming", { path: p });
+      jobIdRef.current = myJobId;
+      const id = myJobId;
+      const result = finished.get(id) ?? (await new Promise<ParseFinished>((resolve) => { waiter = resolve; }));
+      if (result.state !== "completed" || !result.summary) {
+        throw new Error(result.state === "cancelled" ? "Parse cancelled" : result.error ?? "Parse failed");
+      }
+      return result.summary;
+    } finally {
+      unlisten();
+    }
+  }
+
+  async function cancelParse() {
+    const jobId = jobIdRef.current;
+    if (!jobId) return;
+    try {
+      await invoke<boolean>("cancel_parse", { jobId });
+    } catch (err) {
+      console.warn("Failed to cancel parse", err);
+    }
+  }
+
   // logcat view manages its own filters and results
 
   useEffect(() => {
@@ -73,8 +126,9 @@ function App() {
       unsubs.push(await listen("nav://logcat", () => setView("logcat")));
       unsubs.push(await listen("nav://timeline", () => setView("timeline")));
 
-      unsubs.push(await listen<ParseProgress>("parse://progress", (event) => {
+      unsubs.push(await listen<ParseProgress & { jobId: string }>("parse://progress", (event) => {
         const payload = event.payload;
+        if (jobIdRef.current && payload.jobId !== jobIdRef.current) return;
         setParseProgress({
           ...payload,
           percent: Math.min(100, Math.max(0, payload.percent)),
@@ -97,6 +151,7 @@ function App() {
       current={view}
       onNavigate={setView}
       progress={parseProgress}
+      onCancel={cancelParse}
       headerActions={
         <>
           <button className="btn btn-primary" onClick={browse} disabled={loading}>
@@ -137,7 +192,18 @@ function App() {
                   <div className="report-meta">
                     <span className="report-meta-item"><span className="report-meta-label">Report Time</span>{summary.device.reportTime}</span>
                     <span className="report-meta-item"><span className="report-meta-label">Device</span>{summary.device.brand} {summary.device.model}</span>
+                    {summary.timeAnchor && (
+                      <span className="report-meta-item"><span className="report-meta-label">Timezone</span>{summary.timeAnchor.timezone}</span>
+                    )}
                   </div>
+                  {anchorWarning(summary.timeAnchor) && (
+                    <div
+                      className="report-meta-warning"
+                      title={summary.timeAnchor?.candidates.map((c) => `${c.source}: ${c.date} (${c.text})`).join("\n")}
+                    >
+                      {anchorWarning(summary.timeAnchor)}
+                    </div>
+                  )}
                 </div>
 
                 <div className="stats-grid">
@@ -14,6 +14,7 @@ type AppShellProps = PropsWithChildren<{
   headerActions?: React.ReactNode;
   onNavigate?: (key: "dashboard" | "logcat" | "timeline") => void;
   progress?: ParseProgress | null;
+  onCancel?: () => void;
 }>;
 
 const formatBytes = (value: number) => {
@@ -25,7 +26,7 @@ const formatBytes = (value: number) => {
   return `${sized.toFixed(digits)} ${units[index]}`;
 };
 
-export default function AppShell({ children, current = "dashboard", headerActions, onNavigate, progress }: AppShellProps) {
+export default function AppShell({ children, current = "dashboard", headerActions, onNavigate, progress, onCancel }: AppShellProps) {
   return (
     <div className="app-shell">
       <div className="app-bg" />
@@ -58,6 +59,11 @@ export default function AppShell({ children, current = "dashboard", headerAction
               <span>{progress.rowsProcessed.toLocaleString()} rows</span>
               <span>{formatBytes(progress.bytesRead)} / {formatBytes(progress.totalBytes)}</span>
             </div>
+            {onCancel && (
+              <div style={{ marginTop: 12, display: "flex", justifyContent: "flex-end" }}>
+                <button className="btn" onClick={onCancel}>Cancel</button>
+              </div>
+            )}
           </div>
         </div>
       )}
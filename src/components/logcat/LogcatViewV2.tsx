@@ -1,13 +1,34 @@
 import { useCallback, useEffect, useRef, useState, useMemo } from "react";
 import { Virtuoso, VirtuosoHandle } from "react-virtuoso";
 import { useDebouncedCallback } from "use-debounce";
-import type { LogFilters, LogLevel } from "../../types";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { save } from "@tauri-apps/plugin-dialog";
+import type {
+  Bookmark,
+  CollapseMode,
+  ExportFinished,
+  ExportFormat,
+  ExportProgress,
+  FilterPreset,
+  HistoryEntry,
+  LogFilters,
+  LogLevel,
+  QueryValidation,
+} from "../../types";
 import { useLogcatQuery } from "./hooks/useLogcatQuery";
 import { useLogHighlight } from "./hooks/useLogHighlight";
 import styles from "./LogcatViewV2.module.css";
 
 const ALL_LEVELS: LogLevel[] = ["V", "D", "I", "W", "E", "F"];
 
+const EXPORT_EXTENSIONS: Record<ExportFormat, string> = {
+  threadtime: "txt",
+  csv: "csv",
+  jsonl: "jsonl",
+  html: "html",
+};
+
 const THREADTIME_PATTERN = /^(\d{2})-(\d{2})\s+(\d{2}):(\d{2}):(\d{2})(?:\.\d{1,3})?$/;
 
 const FILTER_PATTERN = /^(\d{4})-(\d{2})-(\d{2})\s+(\d{2}):(\d{2}):(\d{2})$/;
@@ -46,6 +67,35 @@ const parseFilterDate = (value: string | undefined): Date | null => {
   return new Date(Number(year), Number(month) - 1, Number(day), Number(hour), Number(minute), Number(second));
 };
 
+
+// "1234, 5678, -42" => include 1234 and 5678, exclude 42
+function parseIdList(val: string): { include: number[]; exclude: number[] } {
+  const include: number[] = [];
+  const exclude: number[] = [];
+  for (const part of val.split(",")) {
+    const n = Number(part.trim());
+    if (!part.trim() || !Number.isInteger(n)) continue;
+    if (n < 0) exclude.push(-n);
+    else include.push(n);
+  }
+  return { include, exclude };
+}
+
+// Keep what the user typed (e.g. a trailing comma) if it means the same ids
+function syncIdInput(current: string, formatted: string): string {
+  const { include, exclude } = parseIdList(current);
+  return formatIdList(undefined, include, exclude) === formatted ? current : formatted;
+}
+
+function formatIdList(single?: number, include?: number[], exclude?: number[]): string {
+  const parts = [
+    ...(single !== undefined ? [single] : []),
+    ...(include ?? []),
+    ...(exclude ?? []).map((n) => -n),
+  ];
+  return parts.join(", ");
+}
+
 export default function LogcatViewV2() {
   const [filters, setFilters] = useState<LogFilters>(() => {
     try {
@@ -57,9 +107,15 @@ export default function LogcatViewV2() {
   });
 
   const [wrap, setWrap] = useState(false);
+  const [collapse, setCollapse] = useState<CollapseMode | null>(null);
   const [showSearch, setShowSearch] = useState(false);
   const [searchText, setSearchText] = useState("");
-  const [searchIndex, setSearchIndex] = useState(-1);
+  const [findStatus, setFindStatus] = useState<{ rowId: number; ordinal: number; total: number } | null>(null);
+  const pendingScrollRef = useRef<number | null>(null);
+  const [bookmarks, setBookmarks] = useState<Map<number, Bookmark>>(new Map());
+  const [presets, setPresets] = useState<FilterPreset[]>([]);
+  const [history, setHistory] = useState<HistoryEntry[]>([]);
+  const [exportJob, setExportJob] = useState<{ jobId: string; percent: number } | null>(null);
   const virtuosoRef = useRef<VirtuosoHandle>(null);
   const searchInputRef = useRef<HTMLInputElement>(null);
 
@@ -72,9 +128,12 @@ export default function LogcatViewV2() {
   const [tagInput, setTagInput] = useState("");
 
   // Local state for debounced inputs (prevents re-render lag during typing)
-  const [localPid, setLocalPid] = useState(filters.pid?.toString() ?? "");
-  const [localTid, setLocalTid] = useState(filters.tid?.toString() ?? "");
+  const [localPid, setLocalPid] = useState(formatIdList(filters.pid, filters.pids, filters.excludePids));
+  const [localTid, setLocalTid] = useState(formatIdList(filters.tid, filters.tids, filters.excludeTids));
+  const [localExcludeTags, setLocalExcludeTags] = useState((filters.excludeTags ?? []).join(", "));
   const [localNotText, setLocalNotText] = useState(filters.notText ?? "");
+  const [localQuery, setLocalQuery] = useState(filters.query ?? "");
+  const [queryError, setQueryError] = useState<string | null>(null);
   const [localFrom, setLocalFrom] = useState("");
   const [localTo, setLocalTo] = useState("");
   const [timeRangeYear, setTimeRangeYear] = useState(() => new Date().getFullYear());
@@ -92,7 +151,9 @@ export default function LogcatViewV2() {
     loadInitial,
     loadNext,
     loadPrev,
-  } = useLogcatQuery(filters);
+    expandRow,
+    findMatch,
+  } = useLogcatQuery(filters, collapse);
 
   const highlight = useLogHighlight(filters);
 
@@ -110,17 +171,50 @@ export default function LogcatViewV2() {
 
   // Debounced sync from local input state to filters
   const debouncedSyncPid = useDebouncedCallback((val: string) => {
-    setFilters((f) => ({ ...f, pid: val ? Number(val) : undefined }));
+    const { include, exclude } = parseIdList(val);
+    setFilters((f) => ({
+      ...f,
+      pid: undefined,
+      pids: include.length ? include : undefined,
+      excludePids: exclude.length ? exclude : undefined,
+      instances: undefined,
+    }));
   }, 300);
 
   const debouncedSyncTid = useDebouncedCallback((val: string) => {
-    setFilters((f) => ({ ...f, tid: val ? Number(val) : undefined }));
+    const { include, exclude } = parseIdList(val);
+    setFilters((f) => ({
+      ...f,
+      tid: undefined,
+      tids: include.length ? include : undefined,
+      excludeTids: exclude.length ? exclude : undefined,
+    }));
+  }, 300);
+
+  const debouncedSyncExcludeTags = useDebouncedCallback((val: string) => {
+    const tags = val.split(",").map((t) => t.trim()).filter(Boolean);
+    setFilters((f) => ({ ...f, excludeTags: tags.length ? tags : undefined }));
   }, 300);
 
   const debouncedSyncNotText = useDebouncedCallback((val: string) => {
     setFilters((f) => ({ ...f, notText: val || undefined }));
   }, 300);
 
+  // Only apply queries that parse; show the error position otherwise
+  const debouncedSyncQuery = useDebouncedCallback(async (val: string) => {
+    try {
+      const res = await invoke<QueryValidation>("validate_query", { query: val });
+      if (!res.valid) {
+        setQueryError(`col ${(res.position ?? 0) + 1}: ${res.message ?? "invalid query"}`);
+        return;
+      }
+      setQueryError(null);
+      setFilters((f) => ({ ...f, query: val.trim() || undefined }));
+    } catch (e: any) {
+      setQueryError(e?.message || String(e));
+    }
+  }, 300);
+
   const applyTimeRange = useCallback((fromInput: string, toInput: string) => {
     const parsedFrom = parseThreadtimeInput(fromInput, timeRangeYear);
     const parsedTo = parseThreadtimeInput(toInput, timeRangeYear);
@@ -146,16 +240,24 @@ export default function LogcatViewV2() {
   // Auto-apply when filters change
   useEffect(() => {
     debouncedLoadInitial();
-  }, [filters, debouncedLoadInitial]);
+  }, [filters, collapse, debouncedLoadInitial]);
 
   // Sync local state when filters change externally (e.g., clear all, click tag/pid)
   useEffect(() => {
-    setLocalPid(filters.pid?.toString() ?? "");
-  }, [filters.pid]);
+    setLocalPid((cur) => syncIdInput(cur, formatIdList(filters.pid, filters.pids, filters.excludePids)));
+  }, [filters.pid, filters.pids, filters.excludePids]);
 
   useEffect(() => {
-    setLocalTid(filters.tid?.toString() ?? "");
-  }, [filters.tid]);
+    setLocalTid((cur) => syncIdInput(cur, formatIdList(filters.tid, filters.tids, filters.excludeTids)));
+  }, [filters.tid, filters.tids, filters.excludeTids]);
+
+  useEffect(() => {
+    setLocalExcludeTags((cur) => {
+      const next = (filters.excludeTags ?? []).join(", ");
+      const same = cur.split(",").map((t) => t.trim()).filter(Boolean).join(", ") === next;
+      return same ? cur : next;
+    });
+  }, [filters.excludeTags]);
 
   useEffect(() => {
     setLocalNotText(filters.notText ?? "");
@@ -194,7 +296,15 @@ export default function LogcatViewV2() {
     return () => window.removeEventListener("lm:logcat:apply", handler as EventListener);
   }, []);
 
-  const setPid = (pid?: number) => setFilters((f) => ({ ...f, pid }));
+  // A pid from a row defaults to the process instance alive at that row
+  const setPid = (pid?: number, instanceId?: number) =>
+    setFilters((f) => ({
+      ...f,
+      pid,
+      pids: undefined,
+      excludePids: undefined,
+      instances: instanceId != null ? [instanceId] : undefined,
+    }));
 
   // Escape special regex characters for plain text search
   const escapeRegex = (str: string) => str.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
@@ -310,23 +420,38 @@ export default function LogcatViewV2() {
       .map(({ idx }) => idx);
   }, [rows, searchText]);
 
-  const goToSearchResult = (direction: "next" | "prev") => {
-    if (searchMatches.length === 0) return;
+  const goToSearchResult = async (direction: "next" | "prev") => {
+    const query = searchText.trim();
+    if (!query) return;
 
-    let newIndex: number;
-    if (searchIndex === -1) {
-      newIndex = 0;
-    } else if (direction === "next") {
-      newIndex = (searchIndex + 1) % searchMatches.length;
-    } else {
-      newIndex = (searchIndex - 1 + searchMatches.length) % searchMatches.length;
+    try {
+      const res = await findMatch(
+        { text: query },
+        findStatus?.rowId ?? null,
+        direction === "next" ? "forward" : "backward"
+      );
+      if (res.rowId == null) {
+        setFindStatus(null);
+        return;
+      }
+      pendingScrollRef.current = res.rowId;
+      setFindStatus({ rowId: res.rowId, ordinal: res.ordinal, total: res.totalMatches });
+    } catch (e) {
+      console.error("Find failed:", e);
     }
-
-    setSearchIndex(newIndex);
-    const rowIndex = searchMatches[newIndex];
-    virtuosoRef.current?.scrollToIndex({ index: rowIndex, align: "center" });
   };
 
+  // Scroll to the current match once its page is loaded
+  useEffect(() => {
+    const rowId = pendingScrollRef.current;
+    if (rowId == null) return;
+    const index = rows.findIndex((r) => r.id === rowId);
+    if (index >= 0) {
+      pendingScrollRef.current = null;
+      virtuosoRef.current?.scrollToIndex({ index, align: "center" });
+    }
+  }, [rows, findStatus]);
+
   const handleSearchKeyDown = (e: React.KeyboardEvent) => {
     if (e.key === "Enter") {
       e.preventDefault();
@@ -339,7 +464,7 @@ export default function LogcatViewV2() {
   const closeSearch = () => {
     setShowSearch(false);
     setSearchText("");
-    setSearchIndex(-1);
+    setFindStatus(null);
   };
 
   const openSearch = () => {
@@ -365,30 +490,137 @@ export default function LogcatViewV2() {
     return () => window.removeEventListener("keydown", handleGlobalKeyDown);
   }, [showSearch]);
 
-  // Reset search index when search text changes
+  // Bookmarks are stored in the report database
   useEffect(() => {
-    setSearchIndex(-1);
-  }, [searchText]);
+    invoke<Bookmark[]>("list_bookmarks")
+      .then((list) => setBookmarks(new Map(list.map((b) => [b.rowId, b]))))
+      .catch(() => setBookmarks(new Map()));
+  }, []);
+
+  const toggleBookmark = useCallback(async (rowId: number) => {
+    try {
+      if (bookmarks.has(rowId)) {
+        await invoke<boolean>("delete_bookmark", { rowId });
+        setBookmarks((m) => {
+          const next = new Map(m);
+          next.delete(rowId);
+          return next;
+        });
+      } else {
+        const note = window.prompt("Bookmark note (optional)") ?? "";
+        const bookmark = await invoke<Bookmark>("set_bookmark", { rowId, bookmark: { note } });
+        setBookmarks((m) => new Map(m).set(rowId, bookmark));
+      }
+    } catch (e) {
+      console.error("Bookmark failed:", e);
+    }
+  }, [bookmarks]);
+
+  // Global presets and this report's recent queries
+  const loadPresets = useCallback(() => {
+    invoke<FilterPreset[]>("list_presets").then(setPresets).catch(() => setPresets([]));
+  }, []);
+
+  useEffect(() => {
+    loadPresets();
+  }, [loadPresets]);
+
+  const loadHistory = useCallback(() => {
+    invoke<HistoryEntry[]>("list_query_history", { limit: 20 }).then(setHistory).catch(() => setHistory([]));
+  }, []);
+
+  const applyPreset = async (name: string) => {
+    try {
+      setFilters(await invoke<LogFilters>("apply_preset", { name }));
+    } catch (e) {
+      console.error("Apply preset failed:", e);
+    }
+  };
+
+  const savePreset = async () => {
+    const name = window.prompt("Preset name");
+    if (!name?.trim()) return;
+    try {
+      await invoke<FilterPreset>("save_preset", { name, filters });
+      loadPresets();
+    } catch (e) {
+      console.error("Save preset failed:", e);
+    }
+  };
+
+  // Export progress and outcome for the running export job
+  useEffect(() => {
+    const unlistenProgress = listen<ExportProgress>("export://progress", (event) => {
+      const { jobId, percent } = event.payload;
+      setExportJob((job) => (job?.jobId === jobId ? { jobId, percent } : job));
+    });
+    const unlistenFinished = listen<ExportFinished>("export://finished", (event) => {
+      setExportJob((job) => (job?.jobId === event.payload.jobId ? null : job));
+      if (event.payload.state === "failed") {
+        console.error("Export failed:", event.payload.error);
+      }
+    });
+    return () => {
+      unlistenProgress.then((fn) => fn());
+      unlistenFinished.then((fn) => fn());
+    };
+  }, []);
+
+  const exportLogs = async (format: ExportFormat) => {
+    const extension = EXPORT_EXTENSIONS[format];
+    const path = await save({
+      defaultPath: `logcat.${extension}`,
+      filters: [{ name: format, extensions: [extension] }],
+    });
+    if (!path) return;
+    try {
+      const jobId = await invoke<string>("export_logs", {
+        path,
+        options: { format, filters, includeBookmarks: true },
+      });
+      setExportJob({ jobId, percent: 0 });
+    } catch (e) {
+      console.error("Export failed:", e);
+    }
+  };
+
+  const cancelExport = () => {
+    if (exportJob) invoke<boolean>("cancel_export", { jobId: exportJob.jobId });
+  };
+
+  // Restart find when search text or filters change
+  useEffect(() => {
+    setFindStatus(null);
+  }, [searchText, filters]);
 
   const renderRow = useCallback(
     (index: number) => {
       const r = rows[index];
       if (!r) return null;
 
-      const { parts, matches } = highlight(r.msg);
+      const { parts, matches } = highlight(r.msg, r.matches?.msg);
+      const tagParts = highlight(r.tag, r.matches?.tag ?? []);
+      const bookmark = r.id != null ? bookmarks.get(r.id) : undefined;
       const isError = r.level === "E" || r.level === "F";
       const isSearchMatch = searchMatches.includes(index);
-      const isCurrentMatch = searchIndex >= 0 && searchMatches[searchIndex] === index;
+      const isCurrentMatch = findStatus != null && r.id === findStatus.rowId;
 
       return (
         <div
           className={`${styles.logRow} ${isError ? (r.level === "F" ? styles.levelF : styles.levelE) : ""} ${isSearchMatch ? styles.searchMatch : ""} ${isCurrentMatch ? styles.currentMatch : ""}`}
         >
-          <div className={styles.cellTs}>{r.ts}</div>
+          <div
+            className={`${styles.cellTs} ${bookmark ? styles.bookmarked : ""}`}
+            onClick={() => r.id != null && toggleBookmark(r.id)}
+            title={bookmark ? bookmark.note || bookmark.label || "Remove bookmark" : "Bookmark row"}
+            style={bookmark?.color ? { borderLeftColor: bookmark.color } : undefined}
+          >
+            {r.ts}
+          </div>
           <div
             className={styles.cellPid}
-            onClick={() => setPid(r.pid)}
-            title="Filter by PID"
+            onClick={(e) => setPid(r.pid, e.shiftKey ? undefined : r.instanceId)}
+            title="Filter by this process instance (Shift: every process with this PID)"
           >
             {String(r.pid).padStart(5, " ")}/{String(r.tid).padStart(5, " ")}
           </div>
@@ -396,9 +628,23 @@ export default function LogcatViewV2() {
             <span className={`${styles.levelBadge} ${styles[r.level]}`}>{r.level}</span>
           </div>
           <div className={styles.cellTag} onClick={() => addTagChip(r.tag)} title="Filter by Tag">
-            {r.tag}
+            {tagParts.parts.map((part, i) => (
+              <span key={i}>
+                {part}
+                {i < tagParts.matches.length && <span className={styles.highlight}>{tagParts.matches[i]}</span>}
+              </span>
+            ))}
           </div>
           <div className={`${styles.cellMsg} ${wrap ? styles.wrap : styles.nowrap}`}>
+            {r.repeat && r.id != null && (
+              <button
+                className={styles.repeatBadge}
+                onClick={() => expandRow(r.id!)}
+                title={`${r.repeat.count} similar rows until ${r.repeat.lastTs} - click to expand`}
+              >
+                ×{r.repeat.count}
+              </button>
+            )}
             {parts.map((part, i) => (
               <span key={i}>
                 {part}
@@ -409,7 +655,7 @@ export default function LogcatViewV2() {
         </div>
       );
     },
-    [rows, highlight, wrap, searchMatches, searchIndex]
+    [rows, highlight, wrap, searchMatches, findStatus, bookmarks, toggleBookmark, expandRow]
   );
 
   const formatFilterValue = (key: string, value: unknown): string => {
@@ -427,11 +673,22 @@ export default function LogcatViewV2() {
       tid: "TID",
       text: "Text",
       notText: "Exclude",
+      excludeTags: "Not tag",
+      tagMode: "Tag mode",
+      pids: "PIDs",
+      instances: "Instance",
+      excludePids: "Not PID",
+      tids: "TIDs",
+      excludeTids: "Not TID",
+      minLevel: "Min level",
+      query: "Query",
       levels: "Level",
       tsFrom: "From",
       tsTo: "To",
       textMode: "Mode",
       caseSensitive: "Case",
+      bookmarked: "Bookmarked",
+      fields: "Fields",
     };
     return keyMap[key] || key;
   };
@@ -444,6 +701,13 @@ export default function LogcatViewV2() {
           <h2 className={styles.title}>LOGCAT</h2>
           {stats && (
             <div className={styles.stats}>
+              <div className={styles.statItem}>
+                <span>Matching</span>
+                <strong>
+                  {stats.isEstimate ? "~" : ""}
+                  {(stats.filteredRows ?? stats.totalRows).toLocaleString()}
+                </strong>
+              </div>
               <div className={styles.statItem}>
                 <span>Total</span>
                 <strong>{stats.totalRows.toLocaleString()}</strong>
@@ -464,6 +728,74 @@ export default function LogcatViewV2() {
           )}
         </div>
         <div className={styles.headerActions}>
+          <select
+            className={styles.presetSelect}
+            value=""
+            onChange={(e) => e.target.value && applyPreset(e.target.value)}
+            title="Apply a saved filter preset"
+            aria-label="Filter presets"
+          >
+            <option value="">Presets…</option>
+            {presets.map((p) => (
+              <option key={p.name} value={p.name} title={p.description ?? undefined}>
+                {p.name}
+              </option>
+            ))}
+          </select>
+          <button className={styles.headerBtn} onClick={savePreset} title="Save filters as preset" aria-label="Save preset">
+            ＋
+          </button>
+          <select
+            className={styles.presetSelect}
+            value=""
+            onFocus={loadHistory}
+            onChange={(e) => {
+              const entry = history[Number(e.target.value)];
+              if (entry) setFilters(entry.filters);
+            }}
+            title="Recent queries on this report"
+            aria-label="Query history"
+          >
+            <option value="">Recent…</option>
+            {history.map((h, i) => (
+              <option key={i} value={i}>
+                {Object.entries(h.filters)
+                  .filter(([, v]) => v != null && v !== "" && !(Array.isArray(v) && v.length === 0))
+                  .map(([k, v]) => `${formatFilterKey(k)}: ${formatFilterValue(k, v)}`)
+                  .join(" · ")}
+              </option>
+            ))}
+          </select>
+          {exportJob ? (
+            <button className={styles.headerBtn} onClick={cancelExport} title="Cancel export" aria-label="Cancel export">
+              {Math.round(exportJob.percent)}% ✕
+            </button>
+          ) : (
+            <select
+              className={styles.presetSelect}
+              value=""
+              onChange={(e) => e.target.value && exportLogs(e.target.value as ExportFormat)}
+              title="Export all rows matching the filters"
+              aria-label="Export"
+            >
+              <option value="">Export…</option>
+              <option value="threadtime">Text (threadtime)</option>
+              <option value="csv">CSV</option>
+              <option value="jsonl">JSON Lines</option>
+              <option value="html">HTML</option>
+            </select>
+          )}
+          <select
+            className={styles.presetSelect}
+            value={collapse ?? ""}
+            onChange={(e) => setCollapse((e.target.value || null) as CollapseMode | null)}
+            title="Fold consecutive repeated messages"
+            aria-label="Collapse repeats"
+          >
+            <option value="">No collapse</option>
+            <option value="exact">Collapse identical</option>
+            <option value="template">Collapse similar</option>
+          </select>
           <label className={styles.wrapToggle} title="Wrap long lines">
             <input
               type="checkbox"
@@ -540,6 +872,16 @@ export default function LogcatViewV2() {
                   />
                   <span>Aa</span>
                 </label>
+                <label className={styles.optionSmall} title="Bookmarked rows only" aria-label="Bookmarked rows only">
+                  <input
+                    type="checkbox"
+                    checked={!!filters.bookmarked}
+                    onChange={(e) =>
+                      setFilters((f) => ({ ...f, bookmarked: e.target.checked || undefined }))
+                    }
+                  />
+                  <span>★</span>
+                </label>
               </div>
             </div>
           </div>
@@ -584,12 +926,28 @@ export default function LogcatViewV2() {
             </div>
           </div>
 
+          <div className={styles.filterGroup}>
+            <label className={styles.filterLabel}>Exclude tags</label>
+            <div className={styles.chipsInput}>
+              <input
+                className={styles.chipInput}
+                placeholder="chatty, Choreographer"
+                value={localExcludeTags}
+                onChange={(e) => {
+                  const val = e.target.value;
+                  setLocalExcludeTags(val);
+                  debouncedSyncExcludeTags(val);
+                }}
+              />
+            </div>
+          </div>
+
           <div className={styles.filterGroupSmall}>
             <label className={styles.filterLabel}>PID</label>
             <div className={styles.chipsInput}>
               <input
                 className={styles.chipInput}
-                placeholder="1234"
+                placeholder="1234, -42"
                 value={localPid}
                 onChange={(e) => {
                   const val = e.target.value;
@@ -605,7 +963,7 @@ export default function LogcatViewV2() {
             <div className={styles.chipsInput}>
               <input
                 className={styles.chipInput}
-                placeholder="5678"
+                placeholder="5678, -42"
                 value={localTid}
                 onChange={(e) => {
                   const val = e.target.value;
@@ -631,6 +989,24 @@ export default function LogcatViewV2() {
               />
             </div>
           </div>
+
+          <div className={styles.filterGroup}>
+            <label className={styles.filterLabel}>Query</label>
+            <div className={styles.chipsInput} title={queryError ?? undefined}>
+              <input
+                className={styles.chipInput}
+                placeholder='level>=W -tag:chatty (msg:"ANR" OR /timeout \d+ms/)'
+                value={localQuery}
+                aria-invalid={queryError ? true : undefined}
+                onChange={(e) => {
+                  const val = e.target.value;
+                  setLocalQuery(val);
+                  debouncedSyncQuery(val);
+                }}
+              />
+            </div>
+            {queryError && <span className={styles.filterError}>{queryError}</span>}
+          </div>
         </div>
 
         {/* Row 3: Time Range */}
@@ -762,14 +1138,12 @@ export default function LogcatViewV2() {
               <div className={styles.searchDivider} />
               <div className={styles.searchControls}>
                 <span className={styles.searchCount}>
-                  {searchMatches.length > 0
-                    ? `${searchIndex + 1}/${searchMatches.length}`
-                    : "0/0"}
+                  {findStatus ? `${findStatus.ordinal}/${findStatus.total}` : "–"}
                 </span>
                 <button
                   className={styles.searchNav}
                   onClick={() => goToSearchResult("prev")}
-                  disabled={searchMatches.length === 0}
+                  disabled={!searchText.trim()}
                   title="Previous (Shift+Enter)"
                 >
                   ▲
@@ -777,7 +1151,7 @@ export default function LogcatViewV2() {
                 <button
                   className={styles.searchNav}
                   onClick={() => goToSearchResult("next")}
-                  disabled={searchMatches.length === 0}
+                  disabled={!searchText.trim()}
                   title="Next (Enter)"
                 >
                   ▼
@@ -867,7 +1241,8 @@ export default function LogcatViewV2() {
           )}
           {!loading && !loadingNext && !loadingPrev && rows.length > 0 && (
             <span>
-              Showing {rows.length.toLocaleString()} of {stats?.totalRows.toLocaleString() ?? "?"} rows
+              Showing {rows.length.toLocaleString()} · {stats?.isEstimate ? "~" : ""}
+              {(stats?.filteredRows ?? stats?.totalRows)?.toLocaleString() ?? "?"} of {stats?.totalRows.toLocaleString() ?? "?"} rows
             </span>
           )}
         </div>
@@ -1,5 +1,5 @@
 import { useMemo, useCallback } from "react";
-import type { LogFilters } from "../../../types";
+import type { LogFilters, MatchSpan } from "../../../types";
 
 const SAFE_HIGHLIGHT_LIMIT = 500;
 
@@ -44,7 +44,11 @@ export function useLogHighlight(filters: LogFilters) {
   }, [filters.text, filters.textMode, filters.caseSensitive]);
 
   return useCallback(
-    (text: string): { parts: string[]; matches: string[] } => {
+    (text: string, spans?: MatchSpan[]): { parts: string[]; matches: string[] } => {
+      // Backend spans come from the same matcher that filtered the row
+      if (spans) {
+        return splitBySpans(text, spans);
+      }
       // Skip highlighting for very long strings
       if (text.length > SAFE_HIGHLIGHT_LIMIT) {
         return { parts: [text], matches: [] };
@@ -55,6 +59,21 @@ export function useLogHighlight(filters: LogFilters) {
   );
 }
 
+export function splitBySpans(text: string, spans: MatchSpan[]): { parts: string[]; matches: string[] } {
+  const parts: string[] = [];
+  const matches: string[] = [];
+  let lastIndex = 0;
+
+  for (const span of spans) {
+    parts.push(text.slice(lastIndex, span.start));
+    matches.push(text.slice(span.start, span.end));
+    lastIndex = span.end;
+  }
+
+  parts.push(text.slice(lastIndex));
+  return { parts, matches };
+}
+
 function createPlainHighlight(
   query: string,
   caseSensitive: boolean
@@ -1,6 +1,7 @@
-import { useState, useRef, useCallback } from "react";
+import { useState, useRef, useCallback, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { LogRow, LogFilters, QueryResponse, QueryCursor, CursorDirection, LogcatStats } from "../../../types";
+import { listen } from "@tauri-apps/api/event";
+import type { LogRow, LogFilters, QueryResponse, QueryCursor, CursorDirection, LogcatStats, ExactStats, FindResponse, JumpResponse, JumpTarget, CollapseMode } from "../../../types";
 
 const BATCH_SIZE = 500;
 
@@ -28,7 +29,7 @@ interface QueryState {
   firstItemIndex: number;
 }
 
-export function useLogcatQuery(filters: LogFilters) {
+export function useLogcatQuery(filters: LogFilters, collapse: CollapseMode | null = null) {
   const [state, setState] = useState<QueryState>({
     rows: [],
     nextCursor: null,
@@ -45,6 +46,19 @@ export function useLogcatQuery(filters: LogFilters) {
 
   const reqRef = useRef(0);
 
+  // Replace estimated stats once the exact figures for the same filters arrive
+  useEffect(() => {
+    const unlisten = listen<ExactStats>("stats://exact", (event) => {
+      const exact = event.payload.stats;
+      setState((s) =>
+        s.stats?.isEstimate && s.stats.filterHash === exact.filterHash ? { ...s, stats: exact } : s
+      );
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const loadInitial = useCallback(async () => {
     const myId = ++reqRef.current;
     setState((s) => ({ ...s, loading: true, error: null, rows: [], firstItemIndex: 0 }));
@@ -59,6 +73,8 @@ export function useLogcatQuery(filters: LogFilters) {
         cursor: null,
         limit: BATCH_SIZE,
         direction: "forward" as CursorDirection,
+        highlight: true,
+        collapse,
       });
 
       if (reqRef.current !== myId) return;
@@ -82,7 +98,7 @@ export function useLogcatQuery(filters: LogFilters) {
         error: e?.message || String(e),
       }));
     }
-  }, [filters]);
+  }, [filters, collapse]);
 
   const loadNext = useCallback(async () => {
     if (state.loadingNext || !state.hasMoreNext || !state.nextCursor) return;
@@ -95,6 +111,8 @@ export function useLogcatQuery(filters: LogFilters) {
         cursor: state.nextCursor,
         limit: BATCH_SIZE,
         direction: "forward" as CursorDirection,
+        highlight: true,
+        collapse,
       });
 
       setState((s) => {
@@ -132,7 +150,7 @@ export function useLogcatQuery(filters: LogFilters) {
         error: s.rows.length > 0 ? null : errMsg,
       }));
     }
-  }, [filters, state.loadingNext, state.hasMoreNext, state.nextCursor, state.stats]);
+  }, [filters, collapse, state.loadingNext, state.hasMoreNext, state.nextCursor, state.stats]);
 
   const loadPrev = useCallback(async () => {
     if (state.loadingPrev || !state.hasMorePrev || !state.prevCursor) return;
@@ -145,6 +163,8 @@ export function useLogcatQuery(filters: LogFilters) {
         cursor: state.prevCursor,
         limit: BATCH_SIZE,
         direction: "backward" as CursorDirection,
+        highlight: true,
+        collapse,
       });
 
       setState((s) => {
@@ -182,20 +202,44 @@ export function useLogcatQuery(filters: LogFilters) {
         error: s.rows.length > 0 ? null : errMsg, // Only show error if no data
       }));
     }
-  }, [filters, state.loadingPrev, state.hasMorePrev, state.prevCursor]);
+  }, [filters, collapse, state.loadingPrev, state.hasMorePrev, state.prevCursor]);
+
+  // Replace a collapsed row with the rows of its run
+  const expandRow = useCallback(async (rowId: number) => {
+    const row = state.rows.find((r) => r.id === rowId);
+    if (!row?.repeat) return;
+
+    const response = await invoke<QueryResponse>("query_logcat_v2", {
+      filters,
+      cursor: row.repeat.expandCursor,
+      limit: row.repeat.count,
+      direction: "forward" as CursorDirection,
+      highlight: true,
+    });
 
-  const jumpToTime = useCallback(async (targetTime: string) => {
+    setState((s) => {
+      const index = s.rows.findIndex((r) => r.id === rowId);
+      if (index < 0) return s;
+      const rows = [...s.rows];
+      rows.splice(index, 1, ...response.rows);
+      return { ...s, rows };
+    });
+  }, [filters, state.rows]);
+
+  // Load a window centred on `target`; resolves to the target's row index
+  const jumpTo = useCallback(async (target: JumpTarget): Promise<number | null> => {
     const myId = ++reqRef.current;
     setState((s) => ({ ...s, loading: true, error: null }));
 
     try {
-      const response = await invoke<QueryResponse>("jump_to_time", {
+      const response = await invoke<JumpResponse>("jump_to", {
         filters,
-        targetTime,
+        target,
         limit: BATCH_SIZE,
+        highlight: true,
       });
 
-      if (reqRef.current !== myId) return;
+      if (reqRef.current !== myId) return null;
 
       setState((s) => ({
         ...s,
@@ -207,21 +251,59 @@ export function useLogcatQuery(filters: LogFilters) {
         loading: false,
         firstItemIndex: 0,
       }));
+      return response.targetIndex;
     } catch (e: any) {
-      if (reqRef.current !== myId) return;
+      if (reqRef.current !== myId) return null;
       setState((s) => ({
         ...s,
         loading: false,
         error: e?.message || String(e),
       }));
+      return null;
     }
   }, [filters]);
 
+  const jumpToTime = useCallback(
+    (targetTime: string) => jumpTo({ kind: "time", time: targetTime }),
+    [jumpTo]
+  );
+
+  // Find next/previous `search` match in the whole filtered view; loads the
+  // page starting at the match unless it is already loaded
+  const findMatch = useCallback(async (search: LogFilters, fromRowId: number | null, direction: CursorDirection) => {
+    const response = await invoke<FindResponse>("find_match", {
+      filters,
+      search,
+      fromRowId,
+      direction,
+      limit: BATCH_SIZE,
+      highlight: true,
+    });
+
+    const page = response.page;
+    if (page && !state.rows.some((r) => r.id === response.rowId)) {
+      ++reqRef.current;
+      setState((s) => ({
+        ...s,
+        rows: page.rows,
+        nextCursor: page.nextCursor,
+        prevCursor: page.prevCursor,
+        hasMoreNext: page.hasMoreNext,
+        hasMorePrev: page.hasMorePrev,
+        firstItemIndex: 0,
+      }));
+    }
+    return response;
+  }, [filters, state.rows]);
+
   return {
     ...state,
     loadInitial,
     loadNext,
     loadPrev,
+    expandRow,
+    jumpTo,
     jumpToTime,
+    findMatch,
   };
 }
@@ -17,17 +17,52 @@ export type DeviceInfo = {
 };
 
 export type ParseSummary = {
+  reportId: string;
+  cacheId: string;
   device: DeviceInfo;
   events: number;
   anrs: number;
   crashes: number;
   efTotal: number;
   efRecent: number;
+  /** Absent for caches built before anchors were recorded */
+  timeAnchor?: AnchorInfo;
+};
+
+export type AnchorSource = "dumpstate_header" | "fingerprint" | "property" | "user_override" | "fallback";
+
+export type AnchorConfidence = "high" | "medium" | "low";
+
+export type AnchorCandidate = {
+  source: AnchorSource;
+  /** YYYY-MM-DD */
+  date: string;
+  text: string;
+};
+
+/** Where the timezone and year of every timestamp came from */
+export type AnchorInfo = {
+  timezone: string;
+  reportDate?: string;
+  dateSource: AnchorSource;
+  tzSource: AnchorSource;
+  confidence: AnchorConfidence;
+  candidates: AnchorCandidate[];
+};
+
+export type JobState = "running" | "completed" | "cancelled" | "failed";
+
+export type ParseFinished = {
+  jobId: string;
+  state: JobState;
+  summary?: ParseSummary;
+  error?: string;
 };
 
 export type LogLevel = "V" | "D" | "I" | "W" | "E" | "F";
 
 export type LogRow = {
+  id?: number;
   ts: string;
   tsIso?: string;
   level: LogLevel;
@@ -35,6 +70,33 @@ export type LogRow = {
   pid: number;
   tid: number;
   msg: string;
+  /** Process instance of `pid` at this row (one lifetime of the pid) */
+  instanceId?: number;
+  matches?: RowMatches;
+  repeat?: RowRepeat;
+};
+
+export type CollapseMode = "exact" | "template";
+
+/** Run of similar consecutive rows folded into one row (collapse mode) */
+export type RowRepeat = {
+  count: number;
+  firstMs: number;
+  lastMs: number;
+  lastId: number;
+  lastTs: string;
+  expandCursor: QueryCursor;
+};
+
+/** Match range in UTF-16 code units (string indices) */
+export type MatchSpan = {
+  start: number;
+  end: number;
+};
+
+export type RowMatches = {
+  msg: MatchSpan[];
+  tag: MatchSpan[];
 };
 
 export type LogFilters = {
@@ -42,12 +104,31 @@ export type LogFilters = {
   tsTo?: string;
   levels?: LogLevel[];
   tag?: string;
+  excludeTags?: string[];
+  tagMode?: "substring" | "exact" | "regex";
   pid?: number;
+  pids?: number[];
+  excludePids?: number[];
+  /** Process instance ids, narrowing a reused pid to one lifetime */
+  instances?: number[];
   tid?: number;
+  tids?: number[];
+  excludeTids?: number[];
+  minLevel?: LogLevel;
   text?: string;
   notText?: string;
   textMode?: "plain" | "regex";
   caseSensitive?: boolean;
+  query?: string;
+  bookmarked?: boolean;
+  /** Extracted field predicates: "key=value" or "key" */
+  fields?: string[];
+};
+
+export type QueryValidation = {
+  valid: boolean;
+  position?: number;
+  message?: string;
 };
 
 export type LogStreamResp = {
@@ -66,6 +147,7 @@ export type CursorDirection = "forward" | "backward";
 
 export type QueryCursor = {
   position: number;
+  tsUnix: number;
   direction: CursorDirection;
   filterHash: number;
 };
@@ -80,6 +162,104 @@ export type QueryResponse = {
   positionRatio: number;
 };
 
+export type JumpTarget =
+  | { kind: "time"; time: string }
+  | { kind: "row"; rowId: number }
+  | { kind: "event"; event: "crash" | "anr"; number: number; offsetMs?: number }
+  | { kind: "expr"; text: string };
+
+export type JumpResponse = QueryResponse & {
+  targetIndex: number;
+  targetMs: number;
+};
+
+export type Bookmark = {
+  rowId: number;
+  label: string;
+  color?: string | null;
+  note: string;
+  createdMs: number;
+  updatedMs: number;
+};
+
+export type BookmarkInput = {
+  label?: string;
+  color?: string | null;
+  note?: string;
+};
+
+export type FilterPreset = {
+  name: string;
+  description?: string | null;
+  filters: LogFilters;
+  createdMs: number;
+  updatedMs: number;
+};
+
+export type PresetImport = {
+  added: number;
+  replaced: number;
+  skipped: number;
+};
+
+export type HistoryEntry = {
+  filters: LogFilters;
+  usedMs: number;
+  useCount: number;
+};
+
+export type ExportFormat = "threadtime" | "csv" | "jsonl" | "html";
+
+export type ExportOptions = {
+  format: ExportFormat;
+  filters: LogFilters;
+  startMs?: number;
+  endMs?: number;
+  includeBookmarks?: boolean;
+};
+
+export type ExportSummary = {
+  path: string;
+  format: ExportFormat;
+  rows: number;
+  bookmarks: number;
+  bytes: number;
+};
+
+export type ExportProgress = {
+  jobId: string;
+  rowsWritten: number;
+  totalRows: number;
+  percent: number;
+};
+
+export type ExportFinished = {
+  jobId: string;
+  state: JobState;
+  summary?: ExportSummary;
+  error?: string;
+};
+
+export type FindResponse = {
+  rowId: number | null;
+  ordinal: number;
+  totalMatches: number;
+  wrapped: boolean;
+  page: QueryResponse | null;
+};
+
+export type ContextScope = "all" | "pid" | "tid";
+
+export type ContextResponse = {
+  rows: LogRow[];
+  anchorIndex: number;
+  nextCursor: QueryCursor | null;
+  prevCursor: QueryCursor | null;
+  hasMoreNext: boolean;
+  hasMorePrev: boolean;
+  filters: LogFilters;
+};
+
 export type LevelCounts = {
   verbose: number;
   debug: number;
@@ -97,4 +277,93 @@ export type LogcatStats = {
   minTsDisplay?: string;
   maxTsDisplay?: string;
   levelCounts: LevelCounts;
+  isEstimate: boolean;
+  filterHash: number;
+};
+
+export type HistogramBucket = {
+  startMs: number;
+  total: number;
+  levelCounts: LevelCounts;
+  crashes: number;
+  anrs: number;
+};
+
+export type Histogram = {
+  startMs: number;
+  endMs: number;
+  bucketMs: number;
+  buckets: HistogramBucket[];
+  maxTotal: number;
+};
+
+export type AggregateBy = "tag" | "pid" | "process" | "level" | "template" | { field: string };
+
+/** One lifetime of a pid, reconstructed from process start / death records */
+export type ProcessInstance = {
+  id: number;
+  pid: number;
+  name?: string;
+  startMs: number;
+  endMs: number;
+  startedBy: string;
+  endedBy?: string;
+  rowCount: number;
+};
+
+/** Timezone / report date set by the user in place of the derived time anchor */
+export type TimeOverride = {
+  timezone: string;
+  /** YYYY-MM-DD; omitted keeps the rows' current dates */
+  reportDate?: string;
+  appliedMs: number;
+};
+
+export type ReanchorSummary = {
+  rows: number;
+  failedRows: number;
+  minTimestampMs?: number;
+  maxTimestampMs?: number;
+  anchor: AnchorInfo;
+};
+
+export type AggregateSort = "count" | "rate" | "key";
+
+export type AggregateEntry = {
+  key: string;
+  count: number;
+  share: number;
+  firstMs: number;
+  lastMs: number;
+  ratePerMinute: number;
+};
+
+export type AggregateResponse = {
+  by: AggregateBy;
+  entries: AggregateEntry[];
+  totalRows: number;
+  distinctKeys: number;
+  ratePerMinute: number;
+};
+
+export type TemplateEntry = {
+  templateId: number;
+  tag: string;
+  template: string;
+  count: number;
+  share: number;
+  firstMs: number;
+  lastMs: number;
+  samples: LogRow[];
+};
+
+export type TemplateResponse = {
+  entries: TemplateEntry[];
+  totalRows: number;
+  distinctTemplates: number;
+};
+
+export type ExactStats = {
+  reportId: string;
+  stats: LogcatStats;
 };